@@ -0,0 +1,167 @@
+//! Sunrise alarm: a gradual warm wake ramp for bedroom panels.
+//!
+//! `--alarm "07:00=45"` ramps the panel from deep red up to warm white
+//! over the 45 minutes before 07:00, holds there briefly, then goes
+//! back to normal output. The ramp is keyed to the [`WallClock`] like
+//! the other schedules, and a `cancel_alarm` command or any GPIO button
+//! press dismisses the current firing.
+//!
+//! [`WallClock`]: crate::profiles::WallClock
+
+use crate::frame::Pixel;
+
+const MINUTES_PER_DAY: f64 = 24.0 * 60.0;
+
+/// How long the ramp holds at full after the wake time before clearing
+/// on its own.
+const HOLD_MINUTES: f64 = 10.0;
+
+/// Default ramp length when the spec gives only a time.
+const DEFAULT_RAMP_MINUTES: u32 = 30;
+
+/// Parse `HH:MM` or `HH:MM=RAMP_MINUTES` into (wake minute, ramp
+/// minutes).
+pub fn parse_alarm(spec: &str) -> Result<(u32, u32), String> {
+    let spec = spec.trim();
+    let bad = || format!("alarm '{}': expected HH:MM or HH:MM=RAMP_MINUTES", spec);
+    let (time, ramp) = match spec.split_once('=') {
+        Some((time, ramp)) => (time, ramp.trim().parse().map_err(|_| bad())?),
+        None => (spec, DEFAULT_RAMP_MINUTES),
+    };
+    if ramp == 0 || ramp > 120 {
+        return Err(format!("alarm '{}': ramp must be 1-120 minutes", spec));
+    }
+    let (hours, mins) = time.split_once(':').ok_or_else(bad)?;
+    let hours: u32 = hours.trim().parse().map_err(|_| bad())?;
+    let mins: u32 = mins.trim().parse().map_err(|_| bad())?;
+    if hours >= 24 || mins >= 60 {
+        return Err(bad());
+    }
+    Ok((hours * 60 + mins, ramp))
+}
+
+/// The scheduled alarm. Callers supply epoch seconds from a wall clock;
+/// with no trustworthy clock the alarm simply never fires.
+pub struct SunriseAlarm {
+    wake_minutes: u32,
+    ramp_minutes: u32,
+    utc_offset_minutes: i32,
+    /// Dismissals suppress the rest of the current firing.
+    suppressed_until: Option<i64>,
+}
+
+impl SunriseAlarm {
+    pub fn new(wake_minutes: u32, ramp_minutes: u32, utc_offset_hours: f64) -> Self {
+        Self {
+            wake_minutes,
+            ramp_minutes,
+            utc_offset_minutes: (utc_offset_hours * 60.0) as i32,
+            suppressed_until: None,
+        }
+    }
+
+    /// Ramp progress at the given wall-clock time: 0 as the ramp begins,
+    /// 1 at the wake time and through the hold, `None` outside the
+    /// firing (or while dismissed).
+    pub fn progress_at(&self, epoch_secs: i64) -> Option<f64> {
+        if self.suppressed_until.is_some_and(|until| epoch_secs < until) {
+            return None;
+        }
+        let local_minutes =
+            ((epoch_secs + self.utc_offset_minutes as i64 * 60) as f64 / 60.0).rem_euclid(MINUTES_PER_DAY);
+        // Signed minutes relative to the wake time, wrapped so a ramp
+        // crossing midnight still reads as "just before waking".
+        let mut delta = local_minutes - self.wake_minutes as f64;
+        if delta < -MINUTES_PER_DAY / 2.0 {
+            delta += MINUTES_PER_DAY;
+        } else if delta >= MINUTES_PER_DAY / 2.0 {
+            delta -= MINUTES_PER_DAY;
+        }
+        if (-(self.ramp_minutes as f64)..0.0).contains(&delta) {
+            Some(1.0 + delta / self.ramp_minutes as f64)
+        } else if (0.0..HOLD_MINUTES).contains(&delta) {
+            Some(1.0)
+        } else {
+            None
+        }
+    }
+
+    /// Dismiss the current firing; the alarm re-arms for the next day.
+    pub fn cancel(&mut self, epoch_secs: i64) {
+        self.suppressed_until =
+            Some(epoch_secs + ((self.ramp_minutes as f64 + HOLD_MINUTES) * 60.0) as i64);
+    }
+}
+
+/// The ramp color at a progress: deep red through orange to warm white,
+/// dim to bright. Perceptually a sunrise, not colorimetrically one.
+pub fn ramp_color(progress: f64) -> Pixel {
+    const ANCHORS: [(f64, [f64; 3]); 4] = [
+        (0.0, [2.0, 0.0, 0.0]),
+        (0.4, [160.0, 24.0, 0.0]),
+        (0.7, [255.0, 96.0, 8.0]),
+        (1.0, [255.0, 190.0, 120.0]),
+    ];
+    let p = progress.clamp(0.0, 1.0);
+    let seg = ANCHORS.windows(2).find(|w| p <= w[1].0).unwrap();
+    let (p0, c0) = seg[0];
+    let (p1, c1) = seg[1];
+    let t = if p1 > p0 { (p - p0) / (p1 - p0) } else { 0.0 };
+    Pixel {
+        r: (c0[0] + (c1[0] - c0[0]) * t).round() as u8,
+        g: (c0[1] + (c1[1] - c0[1]) * t).round() as u8,
+        b: (c0[2] + (c1[2] - c0[2]) * t).round() as u8,
+    }
+}
+
+/// The whole-panel frame for a ramp progress.
+pub fn ramp_frame(progress: f64, led_count: usize) -> Vec<Pixel> {
+    vec![ramp_color(progress); led_count]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wake_time_and_ramp() {
+        assert_eq!(parse_alarm("07:00"), Ok((7 * 60, DEFAULT_RAMP_MINUTES)));
+        assert_eq!(parse_alarm("06:30=45"), Ok((6 * 60 + 30, 45)));
+        assert!(parse_alarm("25:00").is_err());
+        assert!(parse_alarm("07:00=0").is_err());
+    }
+
+    #[test]
+    fn ramps_up_to_the_wake_time_and_holds() {
+        let alarm = SunriseAlarm::new(7 * 60, 30, 0.0);
+        let day: i64 = 1_700_000_000 / 86_400 * 86_400; // midnight UTC
+        assert_eq!(alarm.progress_at(day + 6 * 3600), None);
+        let half = alarm.progress_at(day + 6 * 3600 + 45 * 60).unwrap();
+        assert!((half - 0.5).abs() < 1e-6);
+        assert_eq!(alarm.progress_at(day + 7 * 3600 + 60), Some(1.0));
+        assert_eq!(alarm.progress_at(day + 8 * 3600), None);
+    }
+
+    #[test]
+    fn cancel_dismisses_the_current_firing_only() {
+        let mut alarm = SunriseAlarm::new(0, 30, 0.0); // wakes at midnight
+        let day: i64 = 1_700_000_000 / 86_400 * 86_400;
+        let mid_ramp = day - 15 * 60;
+        assert!(alarm.progress_at(mid_ramp).is_some());
+        alarm.cancel(mid_ramp);
+        assert_eq!(alarm.progress_at(mid_ramp), None);
+        assert_eq!(alarm.progress_at(day + 60), None);
+        // Tomorrow's firing is unaffected.
+        assert!(alarm.progress_at(mid_ramp + 86_400).is_some());
+    }
+
+    #[test]
+    fn ramp_brightens_and_warms() {
+        let start = ramp_color(0.0);
+        let end = ramp_color(1.0);
+        assert!(start.r < 10 && start.b == 0);
+        assert!(end.r == 255 && end.b > 0);
+        let mid = ramp_color(0.5);
+        assert!(mid.r > start.r && mid.r < end.r);
+    }
+}
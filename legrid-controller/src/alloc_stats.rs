@@ -0,0 +1,69 @@
+//! Allocation telemetry for `--profile-alloc`.
+//!
+//! The binary installs [`CountingAllocator`] as the global allocator so
+//! allocation rates can be reported in stats without external tooling.
+//! The inner allocator is swappable at build time: enable the `jemalloc`
+//! or `mimalloc` cargo feature to chase fragmentation on long uptimes.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+pub struct CountingAllocator<A> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+static ALLOC_CALLS: AtomicU64 = AtomicU64::new(0);
+static ALLOC_BYTES: AtomicU64 = AtomicU64::new(0);
+static DEALLOC_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOC_CALLS.fetch_add(1, Ordering::Relaxed);
+        ALLOC_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        DEALLOC_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOC_CALLS.fetch_add(1, Ordering::Relaxed);
+        ALLOC_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        DEALLOC_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Snapshot of the allocation counters, used to derive rates between two
+/// stats reports.
+#[derive(Clone, Copy)]
+pub struct AllocSnapshot {
+    pub calls: u64,
+    pub bytes: u64,
+    pub taken_at: Instant,
+}
+
+impl AllocSnapshot {
+    pub fn take() -> Self {
+        Self {
+            calls: ALLOC_CALLS.load(Ordering::Relaxed),
+            bytes: ALLOC_BYTES.load(Ordering::Relaxed),
+            taken_at: Instant::now(),
+        }
+    }
+
+    pub fn live_bytes() -> u64 {
+        ALLOC_BYTES
+            .load(Ordering::Relaxed)
+            .saturating_sub(DEALLOC_BYTES.load(Ordering::Relaxed))
+    }
+}
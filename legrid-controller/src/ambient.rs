@@ -0,0 +1,161 @@
+//! Ambient "mood light" scene: sample the room through a camera and
+//! render slowly-evolving gradients in its dominant colors. Zero
+//! configuration — point a camera at the room, pass
+//! `--ambient-camera /dev/video0`, and the panel follows the
+//! environment.
+
+use crate::frame::Pixel;
+
+/// Anchor colors the gradient blends between.
+pub const PALETTE_SIZE: usize = 3;
+
+/// Per-frame easing toward a freshly sampled palette. At 30 FPS this
+/// crossfades a scene change over roughly five seconds.
+const EASE: f64 = 0.007;
+
+/// Pick the room's dominant colors from a captured frame. Pixels land
+/// in a coarse 4-bit-per-channel histogram; the fullest bins win, and
+/// each bin answers with the average of its members so the result isn't
+/// quantized. Near-black bins are skipped — a dim room should read as
+/// its accent colors, not as black.
+pub fn dominant_colors(pixels: &[Pixel], count: usize) -> Vec<Pixel> {
+    const BINS: usize = 16;
+    let mut sums = vec![(0u64, 0u64, 0u64, 0u64); BINS * BINS * BINS];
+    for p in pixels {
+        let bin = (p.r as usize >> 4) * BINS * BINS
+            + (p.g as usize >> 4) * BINS
+            + (p.b as usize >> 4);
+        let (r, g, b, n) = &mut sums[bin];
+        *r += p.r as u64;
+        *g += p.g as u64;
+        *b += p.b as u64;
+        *n += 1;
+    }
+    let mut ranked: Vec<&(u64, u64, u64, u64)> = sums
+        .iter()
+        .filter(|(r, g, b, n)| *n > 0 && (r + g + b) / n >= 48)
+        .collect();
+    ranked.sort_by_key(|bin| std::cmp::Reverse(bin.3));
+    ranked
+        .iter()
+        .take(count)
+        .map(|(r, g, b, n)| Pixel {
+            r: (r / n) as u8,
+            g: (g / n) as u8,
+            b: (b / n) as u8,
+        })
+        .collect()
+}
+
+/// The evolving scene: a palette eased toward the latest sample and a
+/// slow phase drifting the gradient across the panel.
+pub struct AmbientScene {
+    current: Vec<Pixel>,
+    target: Vec<Pixel>,
+    phase: f64,
+}
+
+impl Default for AmbientScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AmbientScene {
+    pub fn new() -> Self {
+        // Warm neutral to fade up from before the first sample lands.
+        let start = vec![Pixel { r: 40, g: 28, b: 16 }; PALETTE_SIZE];
+        Self { current: start.clone(), target: start, phase: 0.0 }
+    }
+
+    /// Adopt a freshly sampled palette as the crossfade target. Short
+    /// samples (a nearly black room) keep the previous target rather
+    /// than snapping to emptiness.
+    pub fn set_target(&mut self, palette: &[Pixel]) {
+        for (slot, color) in self.target.iter_mut().zip(palette) {
+            *slot = *color;
+        }
+    }
+
+    /// One animation step: ease toward the target and drift the phase.
+    /// Returns the frame for the panel.
+    pub fn step(&mut self, width: u16, led_count: usize) -> Vec<Pixel> {
+        for (cur, tgt) in self.current.iter_mut().zip(&self.target) {
+            cur.r = ease_channel(cur.r, tgt.r);
+            cur.g = ease_channel(cur.g, tgt.g);
+            cur.b = ease_channel(cur.b, tgt.b);
+        }
+        self.phase += EASE;
+
+        let width = width.max(1) as usize;
+        (0..led_count)
+            .map(|i| {
+                let x = (i % width) as f64 / width as f64;
+                self.color_at(x + self.phase)
+            })
+            .collect()
+    }
+
+    /// Gradient color at a (wrapping) position: blend between adjacent
+    /// palette anchors.
+    fn color_at(&self, pos: f64) -> Pixel {
+        let n = self.current.len();
+        let scaled = pos.rem_euclid(1.0) * n as f64;
+        let a = &self.current[scaled as usize % n];
+        let b = &self.current[(scaled as usize + 1) % n];
+        let t = scaled.fract();
+        Pixel {
+            r: lerp(a.r, b.r, t),
+            g: lerp(a.g, b.g, t),
+            b: lerp(a.b, b.b, t),
+        }
+    }
+}
+
+fn lerp(a: u8, b: u8, t: f64) -> u8 {
+    (a as f64 + (b as f64 - a as f64) * t).round() as u8
+}
+
+/// Move one channel a step toward its target, always by at least one so
+/// the fade actually lands.
+fn ease_channel(cur: u8, tgt: u8) -> u8 {
+    let delta = tgt as f64 - cur as f64;
+    let step = (delta * EASE * 30.0).abs().max(1.0).min(delta.abs());
+    if delta >= 0.0 {
+        cur + step as u8
+    } else {
+        cur - step as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dominant_colors_find_the_room_accents() {
+        // A mostly-red room with a blue accent and a dark floor the
+        // sampler should ignore.
+        let mut frame = vec![Pixel { r: 200, g: 30, b: 20 }; 100];
+        frame.extend(vec![Pixel { r: 20, g: 40, b: 220 }; 30]);
+        frame.extend(vec![Pixel { r: 5, g: 5, b: 5 }; 500]);
+        let colors = dominant_colors(&frame, 2);
+        assert_eq!(colors.len(), 2);
+        assert!(colors[0].r > 150, "largest bright bin should be the red wall");
+        assert!(colors[1].b > 150, "accent should survive the dark majority");
+    }
+
+    #[test]
+    fn scene_eases_toward_the_sampled_palette() {
+        let mut scene = AmbientScene::new();
+        let red = Pixel { r: 250, g: 0, b: 0 };
+        scene.set_target(&[red; PALETTE_SIZE]);
+        let before = scene.step(8, 8)[0].r;
+        for _ in 0..2000 {
+            scene.step(8, 8);
+        }
+        let after = scene.step(8, 8)[0].r;
+        assert!(after > before, "fade should progress ({} -> {})", before, after);
+        assert!(after >= 245, "fade should land near the target, got {}", after);
+    }
+}
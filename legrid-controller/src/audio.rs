@@ -0,0 +1,302 @@
+//! Audio-reactive input: ALSA capture, FFT, and the built-in
+//! visualizations.
+//!
+//! Capture shells out to `arecord` for raw S16LE mono rather than linking
+//! alsa-lib, keeping the crate dependency-free. The analysis thread
+//! windows the stream into 1024-sample blocks, runs a radix-2 FFT, and
+//! publishes log-spaced band magnitudes, an RMS level, and a beat flag
+//! for the render side to pick up (`--audio-device hw:1 --audio-effect
+//! spectrum`), or for the output stage to modulate incoming frames with
+//! (`--audio-modulate`).
+
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::frame::Pixel;
+
+/// Samples per analysis block; must stay a power of two for the FFT.
+pub const BLOCK_SAMPLES: usize = 1024;
+/// Capture rate passed to arecord.
+pub const SAMPLE_RATE: u32 = 44_100;
+/// Spectrum resolution after log-spaced band grouping.
+pub const BAND_COUNT: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioEffect {
+    Spectrum,
+    Vu,
+    BeatPulse,
+}
+
+impl AudioEffect {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "spectrum" => Some(AudioEffect::Spectrum),
+            "vu" => Some(AudioEffect::Vu),
+            "beat-pulse" => Some(AudioEffect::BeatPulse),
+            _ => None,
+        }
+    }
+}
+
+/// One block's worth of analysis, as published to the render side.
+#[derive(Debug, Clone, Default)]
+pub struct AudioAnalysis {
+    /// Log-spaced band magnitudes, normalized to [0, 1].
+    pub bands: Vec<f64>,
+    /// RMS level of the block, normalized to [0, 1].
+    pub level: f64,
+    /// True for the block where a beat was detected.
+    pub beat: bool,
+}
+
+/// In-place iterative radix-2 FFT over (re, im) pairs. len must be a
+/// power of two.
+pub fn fft(buf: &mut [(f64, f64)]) {
+    let n = buf.len();
+    debug_assert!(n.is_power_of_two());
+    // Bit-reversal permutation.
+    let mut j = 0usize;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        let (w_re, w_im) = (angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f64, 0.0f64);
+            for k in 0..len / 2 {
+                let (a_re, a_im) = buf[start + k];
+                let (b_re, b_im) = buf[start + k + len / 2];
+                let (t_re, t_im) = (b_re * cur_re - b_im * cur_im, b_re * cur_im + b_im * cur_re);
+                buf[start + k] = (a_re + t_re, a_im + t_im);
+                buf[start + k + len / 2] = (a_re - t_re, a_im - t_im);
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Analyze one block of signed 16-bit samples: band magnitudes, RMS,
+/// and a beat flag against the running energy average (carried in
+/// `energy_avg` between blocks).
+pub fn analyze_block(samples: &[i16], energy_avg: &mut f64) -> AudioAnalysis {
+    let n = samples.len().min(BLOCK_SAMPLES).next_power_of_two().min(BLOCK_SAMPLES);
+    let mut buf: Vec<(f64, f64)> = samples[..n.min(samples.len())]
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            // Hann window keeps block edges from smearing the spectrum.
+            let w = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos();
+            (s as f64 / 32768.0 * w, 0.0)
+        })
+        .collect();
+    buf.resize(n, (0.0, 0.0));
+    fft(&mut buf);
+
+    // Group the positive-frequency bins into log-spaced bands.
+    let half = n / 2;
+    let mut bands = vec![0.0f64; BAND_COUNT];
+    for (band, slot) in bands.iter_mut().enumerate() {
+        let lo = ((half as f64).powf(band as f64 / BAND_COUNT as f64)) as usize;
+        let hi = (((half as f64).powf((band + 1) as f64 / BAND_COUNT as f64)) as usize).max(lo + 1);
+        let sum: f64 = buf[lo..hi.min(half)]
+            .iter()
+            .map(|&(re, im)| (re * re + im * im).sqrt())
+            .sum();
+        *slot = (sum / (hi - lo) as f64 * 4.0).min(1.0);
+    }
+
+    let rms = (samples.iter().map(|&s| {
+        let v = s as f64 / 32768.0;
+        v * v
+    }).sum::<f64>() / samples.len().max(1) as f64)
+        .sqrt();
+
+    // Beat: low-band energy well above its running average.
+    let energy: f64 = bands[..BAND_COUNT / 4].iter().sum();
+    let beat = *energy_avg > 0.0 && energy > *energy_avg * 1.6 && energy > 0.05;
+    *energy_avg = if *energy_avg > 0.0 {
+        *energy_avg * 0.9 + energy * 0.1
+    } else {
+        energy
+    };
+
+    AudioAnalysis {
+        bands,
+        level: (rms * 3.0).min(1.0),
+        beat,
+    }
+}
+
+/// Latest analysis, shared between the capture thread and the renderer.
+pub type SharedAnalysis = Arc<Mutex<AudioAnalysis>>;
+
+/// Spawn the capture/analysis thread. Exits (and logs) if arecord dies;
+/// the shared slot then just stops updating.
+pub fn spawn_capture(device: String) -> io::Result<SharedAnalysis> {
+    let shared: SharedAnalysis = Arc::new(Mutex::new(AudioAnalysis::default()));
+    let slot = shared.clone();
+    let mut child = Command::new("arecord")
+        .args(["-D", &device, "-f", "S16_LE", "-r", &SAMPLE_RATE.to_string(), "-c", "1", "-t", "raw", "-q"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    crate::log_info!("audio", "Audio capture from {} at {} Hz", device, SAMPLE_RATE);
+    thread::spawn(move || {
+        let mut raw = vec![0u8; BLOCK_SAMPLES * 2];
+        let mut energy_avg = 0.0f64;
+        loop {
+            let mut filled = 0;
+            while filled < raw.len() {
+                match stdout.read(&mut raw[filled..]) {
+                    Ok(0) => {
+                        crate::log_info!("audio", "Audio capture ended (arecord exited)");
+                        let _ = child.wait();
+                        return;
+                    }
+                    Ok(n) => filled += n,
+                    Err(e) => {
+                        crate::log_warn!("audio", "Audio capture error: {}", e);
+                        let _ = child.kill();
+                        return;
+                    }
+                }
+            }
+            let samples: Vec<i16> = raw
+                .chunks_exact(2)
+                .map(|c| i16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            let analysis = analyze_block(&samples, &mut energy_avg);
+            *slot.lock().unwrap() = analysis;
+        }
+    });
+    Ok(shared)
+}
+
+/// Render one frame of the chosen visualization onto the grid.
+pub fn render_audio_effect(
+    effect: AudioEffect,
+    analysis: &AudioAnalysis,
+    width: usize,
+    height: usize,
+) -> Vec<Pixel> {
+    let mut frame = vec![Pixel::BLACK; width * height];
+    match effect {
+        AudioEffect::Spectrum => {
+            // One column group per band, bar height by magnitude, green
+            // through red toward the top.
+            for x in 0..width {
+                let band = x * BAND_COUNT / width.max(1);
+                let magnitude = analysis.bands.get(band).copied().unwrap_or(0.0);
+                let bar = (magnitude * height as f64).round() as usize;
+                for dy in 0..bar.min(height) {
+                    let y = height - 1 - dy;
+                    let heat = dy as f64 / height.max(1) as f64;
+                    frame[y * width + x] = Pixel {
+                        r: (heat * 255.0) as u8,
+                        g: ((1.0 - heat) * 255.0) as u8,
+                        b: 0,
+                    };
+                }
+            }
+        }
+        AudioEffect::Vu => {
+            // Horizontal level meter across the middle rows.
+            let lit = (analysis.level * width as f64).round() as usize;
+            for y in height / 3..(2 * height / 3).max(height / 3 + 1).min(height) {
+                for x in 0..lit.min(width) {
+                    let heat = x as f64 / width.max(1) as f64;
+                    frame[y * width + x] = Pixel {
+                        r: (heat * 255.0) as u8,
+                        g: ((1.0 - heat) * 200.0 + 55.0) as u8,
+                        b: 0,
+                    };
+                }
+            }
+        }
+        AudioEffect::BeatPulse => {
+            // Whole-panel flash on the beat, decaying with the level.
+            let v = if analysis.beat { 255 } else { (analysis.level * 180.0) as u8 };
+            frame.fill(Pixel { r: v, g: v, b: v });
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_finds_a_pure_tone() {
+        // Bin-8 cosine over 64 samples: the spectrum peaks at bin 8.
+        let n = 64;
+        let mut buf: Vec<(f64, f64)> = (0..n)
+            .map(|i| ((2.0 * std::f64::consts::PI * 8.0 * i as f64 / n as f64).cos(), 0.0))
+            .collect();
+        fft(&mut buf);
+        let peak = (0..n / 2)
+            .max_by(|&a, &b| {
+                let ma = buf[a].0.hypot(buf[a].1);
+                let mb = buf[b].0.hypot(buf[b].1);
+                ma.partial_cmp(&mb).unwrap()
+            })
+            .unwrap();
+        assert_eq!(peak, 8);
+    }
+
+    #[test]
+    fn loud_blocks_raise_the_level() {
+        let mut avg = 0.0;
+        let quiet = analyze_block(&vec![0i16; BLOCK_SAMPLES], &mut avg);
+        let loud = analyze_block(&vec![20_000i16; BLOCK_SAMPLES], &mut avg);
+        assert_eq!(quiet.level, 0.0);
+        assert!(loud.level > 0.5);
+    }
+
+    #[test]
+    fn beat_fires_on_a_low_energy_jump() {
+        let mut avg = 0.0;
+        // Establish a quiet-but-nonzero baseline, then hit a bass burst.
+        let soft: Vec<i16> = (0..BLOCK_SAMPLES)
+            .map(|i| ((i as f64 * 0.05).sin() * 800.0) as i16)
+            .collect();
+        for _ in 0..5 {
+            analyze_block(&soft, &mut avg);
+        }
+        let burst: Vec<i16> = (0..BLOCK_SAMPLES)
+            .map(|i| ((i as f64 * 0.05).sin() * 30_000.0) as i16)
+            .collect();
+        assert!(analyze_block(&burst, &mut avg).beat);
+    }
+
+    #[test]
+    fn spectrum_bars_rise_from_the_bottom() {
+        let analysis = AudioAnalysis {
+            bands: vec![1.0; BAND_COUNT],
+            level: 1.0,
+            beat: false,
+        };
+        let frame = render_audio_effect(AudioEffect::Spectrum, &analysis, 8, 8);
+        // Full-scale bands light the bottom row everywhere.
+        assert!(frame[7 * 8..].iter().all(|p| p.g > 0 || p.r > 0));
+        let vu = render_audio_effect(AudioEffect::Vu, &analysis, 8, 8);
+        assert!(vu.iter().any(|p| p.g > 0));
+    }
+}
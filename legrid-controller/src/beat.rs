@@ -0,0 +1,157 @@
+//! Beat-synchronized animation clock.
+//!
+//! VJ setups want the built-in effects in musical time even when nothing
+//! is streaming frames. A BPM value (or a series of taps) arriving over
+//! the control channel locks a beat clock; while locked, the idle
+//! animator runs off beats instead of wall seconds — one beat advances
+//! the animation clock by one second, so 60 BPM matches the free-running
+//! clock and faster music runs the effects proportionally faster. Taps
+//! also set the downbeat, so the breathing effect inhales on the one.
+
+use std::time::Instant;
+
+/// Tempi outside this range are almost certainly a typo or a unit error.
+const MIN_BPM: f64 = 20.0;
+const MAX_BPM: f64 = 300.0;
+/// A gap longer than this between taps starts a fresh tap sequence.
+const TAP_TIMEOUT_SECS: f64 = 2.0;
+/// Tempo is averaged over at most this many recent taps.
+const MAX_TAPS: usize = 8;
+
+/// The tempo lock. Unlocked (no BPM yet, or cleared) it reports `None`
+/// and the animator falls back to its own clock.
+#[derive(Default)]
+pub struct BeatClock {
+    bpm: Option<f64>,
+    /// Beat zero; tap sequences move it so the phase follows the taps.
+    origin: Option<Instant>,
+    taps: Vec<Instant>,
+}
+
+impl BeatClock {
+    /// Lock to an explicit tempo, with the downbeat at `now`.
+    pub fn set_bpm(&mut self, bpm: f64, now: Instant) -> Result<(), String> {
+        if !(MIN_BPM..=MAX_BPM).contains(&bpm) {
+            return Err(format!("bpm {} outside {}..={}", bpm, MIN_BPM, MAX_BPM));
+        }
+        self.bpm = Some(bpm);
+        self.origin = Some(now);
+        self.taps.clear();
+        Ok(())
+    }
+
+    /// Lock to a tempo whose downbeat was `phase` beats in the past —
+    /// how a Link session hands over its beat grid rather than starting
+    /// a new one at message arrival.
+    pub fn set_bpm_at_phase(&mut self, bpm: f64, phase: f64, now: Instant) -> Result<(), String> {
+        self.set_bpm(bpm, now)?;
+        let behind = phase.rem_euclid(1.0) * 60.0 / bpm;
+        self.origin = Some(now - std::time::Duration::from_secs_f64(behind));
+        Ok(())
+    }
+
+    /// Register one tempo tap. Two or more taps in quick succession
+    /// derive the BPM from the average interval and put the downbeat on
+    /// the latest tap; a long pause starts a fresh sequence.
+    pub fn tap(&mut self, now: Instant) {
+        if let Some(last) = self.taps.last() {
+            if now.duration_since(*last).as_secs_f64() > TAP_TIMEOUT_SECS {
+                self.taps.clear();
+            }
+        }
+        self.taps.push(now);
+        if self.taps.len() > MAX_TAPS {
+            self.taps.remove(0);
+        }
+        if self.taps.len() < 2 {
+            return;
+        }
+        let span = now.duration_since(self.taps[0]).as_secs_f64();
+        let interval = span / (self.taps.len() - 1) as f64;
+        let bpm = 60.0 / interval;
+        if (MIN_BPM..=MAX_BPM).contains(&bpm) {
+            self.bpm = Some(bpm);
+            self.origin = Some(now);
+        }
+    }
+
+    /// Drop the lock; effects go back to wall time.
+    pub fn clear(&mut self) {
+        self.bpm = None;
+        self.origin = None;
+        self.taps.clear();
+    }
+
+    pub fn bpm(&self) -> Option<f64> {
+        self.bpm
+    }
+
+    /// Beats elapsed since the downbeat, or `None` while unlocked.
+    pub fn beats_at(&self, now: Instant) -> Option<f64> {
+        let bpm = self.bpm?;
+        let origin = self.origin?;
+        Some(now.saturating_duration_since(origin).as_secs_f64() * bpm / 60.0)
+    }
+
+    /// The animation clock while locked: beats, read as seconds by the
+    /// effect code. `None` while unlocked.
+    pub fn effect_time(&self, now: Instant) -> Option<f64> {
+        self.beats_at(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn explicit_bpm_scales_the_clock() {
+        let t0 = Instant::now();
+        let mut clock = BeatClock::default();
+        assert!(clock.effect_time(t0).is_none());
+        clock.set_bpm(120.0, t0).unwrap();
+        // 120 BPM: two beats per wall second.
+        let beats = clock.effect_time(t0 + Duration::from_secs(3)).unwrap();
+        assert!((beats - 6.0).abs() < 1e-9);
+        assert!(clock.set_bpm(1000.0, t0).is_err());
+        clock.clear();
+        assert!(clock.effect_time(t0).is_none());
+    }
+
+    #[test]
+    fn phase_handover_joins_an_existing_grid() {
+        let t0 = Instant::now();
+        let mut clock = BeatClock::default();
+        // 120 BPM, a quarter beat past the downbeat at handover.
+        clock.set_bpm_at_phase(120.0, 0.25, t0).unwrap();
+        let beats = clock.beats_at(t0).unwrap();
+        assert!((beats - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn taps_derive_tempo_and_downbeat() {
+        let t0 = Instant::now();
+        let mut clock = BeatClock::default();
+        // Four taps 500ms apart: 120 BPM, downbeat on the last tap.
+        for i in 0..4 {
+            clock.tap(t0 + Duration::from_millis(500 * i));
+        }
+        let bpm = clock.bpm().unwrap();
+        assert!((bpm - 120.0).abs() < 0.5);
+        let beats = clock.beats_at(t0 + Duration::from_millis(2000)).unwrap();
+        assert!((beats - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn a_long_pause_starts_a_fresh_tap_sequence() {
+        let t0 = Instant::now();
+        let mut clock = BeatClock::default();
+        clock.tap(t0);
+        clock.tap(t0 + Duration::from_secs(10));
+        // The stale tap was discarded, so no tempo yet.
+        assert!(clock.bpm().is_none());
+        clock.tap(t0 + Duration::from_millis(10_500));
+        assert!((clock.bpm().unwrap() - 120.0).abs() < 0.5);
+    }
+}
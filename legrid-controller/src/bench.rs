@@ -0,0 +1,116 @@
+//! In-process benchmark: the `bench` subcommand.
+//!
+//! `local_controller bench [--led-count N --pipeline ...]` synthesizes
+//! frames and times the three hot stages — frame parsing, the pixel
+//! pipeline, and the output driver — separately, then reports the maximum
+//! sustainable FPS for the configured chain alongside the backend's wire
+//! ceiling. Run it when sizing a larger installation, before the panel
+//! tells you the hard way.
+
+use std::io;
+use std::time::Instant;
+
+use crate::config::parse_args;
+use crate::frame::{FrameParser, Pixel, MSG_TYPE_FRAME};
+use crate::pipeline::PixelPipeline;
+
+const WARMUP_ITERATIONS: usize = 20;
+const ITERATIONS: usize = 500;
+/// The driver stage may sleep (BCM slices) or log per frame, so it gets
+/// fewer iterations.
+const DRIVER_ITERATIONS: usize = 50;
+
+/// Mean microseconds per call over a warmed-up run.
+fn time_us(iterations: usize, mut f: impl FnMut()) -> f64 {
+    for _ in 0..WARMUP_ITERATIONS {
+        f();
+    }
+    let started = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    started.elapsed().as_micros() as f64 / iterations as f64
+}
+
+/// A full-grid gradient frame: every pixel distinct, so stages can't take
+/// uniform-color shortcuts.
+fn synth_pixels(led_count: usize) -> Vec<Pixel> {
+    (0..led_count)
+        .map(|i| Pixel {
+            r: (i % 256) as u8,
+            g: (i / 3 % 256) as u8,
+            b: (255 - i % 256) as u8,
+        })
+        .collect()
+}
+
+pub fn run_bench(args: &[String]) -> io::Result<()> {
+    // `bench` takes the ordinary controller flags; args[0] is the
+    // subcommand token, which parse_args skips like a program name.
+    let config = parse_args(args)?;
+    let led_count = config.led_count;
+    let (width, height) = (config.width, config.height);
+
+    let pixels = synth_pixels(led_count);
+    let mut payload = vec![1u8, MSG_TYPE_FRAME, 0, 0, 0, 0];
+    payload.extend_from_slice(&width.to_le_bytes());
+    payload.extend_from_slice(&height.to_le_bytes());
+    for p in &pixels {
+        payload.extend_from_slice(&[p.r, p.g, p.b]);
+    }
+
+    println!(
+        "Benchmarking {} LEDs ({}x{}), {} iterations per stage",
+        led_count, width, height, ITERATIONS
+    );
+
+    let parse_us = time_us(ITERATIONS, || {
+        FrameParser::parse(&payload).expect("bench frame parses");
+    });
+
+    // Bench the configured pipeline, or a representative gamma+dither
+    // chain when none is configured, so the number means something.
+    let spec = config.pipeline_spec.as_deref().unwrap_or("gamma:2.2,dither");
+    let mut pipeline = PixelPipeline::with_stages(config.color_order, spec)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let pipeline_us = time_us(ITERATIONS, || {
+        pipeline.apply(&pixels);
+    });
+
+    let mut driver = config.driver.create(width as usize, height as usize)?;
+    let wire = pipeline.apply(&pixels);
+    let driver_us = time_us(DRIVER_ITERATIONS, || {
+        let _ = driver.render(&wire, width as usize, height as usize);
+    });
+
+    let total_us = parse_us + pipeline_us + driver_us;
+    println!("  parse:    {:>9.1} us/frame", parse_us);
+    println!("  pipeline: {:>9.1} us/frame  [{}]", pipeline_us, pipeline.stage_names().join(","));
+    println!("  driver:   {:>9.1} us/frame  [{}]", driver_us, driver.name());
+    println!("  total:    {:>9.1} us/frame -> {:.1} FPS sustainable", total_us, 1_000_000.0 / total_us);
+    match driver.max_safe_fps(led_count) {
+        Some(fps) => println!("  wire ceiling: {:.1} FPS for {} LEDs", fps, led_count),
+        None => println!("  wire ceiling: none (simulator backend)"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synth_frames_vary_per_pixel() {
+        let pixels = synth_pixels(10);
+        assert_eq!(pixels.len(), 10);
+        assert_ne!(pixels[0], pixels[1]);
+    }
+
+    #[test]
+    fn timing_loop_reports_positive_micros() {
+        let mut n = 0u64;
+        let us = time_us(10, || n += 1);
+        assert_eq!(n, 10 + WARMUP_ITERATIONS as u64);
+        assert!(us >= 0.0);
+    }
+}
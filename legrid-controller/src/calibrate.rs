@@ -0,0 +1,145 @@
+//! Camera-feedback pixel-map calibration (experimental).
+//!
+//! `--calibrate-camera /dev/video0` flashes binary structured-light
+//! patterns — one frame per bit of the LED index — while a V4L2 camera
+//! pointed at the panel captures each one. Every camera pixel that sees
+//! the panel then spells out the index of the LED lighting it, and the
+//! centroid of each index's pixels is that LED's physical position. The
+//! result is a normalized pixel map on stdout, the starting point for
+//! mapping irregular hand-wired installations.
+//!
+//! Capture goes through the shared [`crate::camera`] shell-out to
+//! `v4l2-ctl`.
+
+use crate::frame::Pixel;
+
+/// A camera pixel must brighten at least this much between all-off and
+/// all-on to count as seeing the panel rather than the room.
+const MIN_CONTRAST: u8 = 40;
+
+/// Patterns needed to give every LED a distinct on/off signature: one
+/// per bit of the highest index.
+pub fn pattern_count(led_count: usize) -> usize {
+    let highest = led_count.max(1) - 1;
+    if highest == 0 {
+        1
+    } else {
+        (usize::BITS - highest.leading_zeros()) as usize
+    }
+}
+
+/// The frame for one bit plane: LED i is lit iff bit `bit` of i is set.
+pub fn bit_pattern(led_count: usize, bit: usize) -> Vec<Pixel> {
+    let white = Pixel { r: 255, g: 255, b: 255 };
+    (0..led_count)
+        .map(|i| if i >> bit & 1 == 1 { white } else { Pixel::BLACK })
+        .collect()
+}
+
+/// Decode the captured planes into per-LED positions, normalized to
+/// [0, 1] in camera space. `all_on`/`all_off` bracket the exposure so
+/// each camera pixel gets its own threshold; pixels without contrast are
+/// the room and are ignored. LEDs nothing decoded to come back `None` —
+/// occluded, dead, or outside the camera's view.
+pub fn decode_positions(
+    width: usize,
+    height: usize,
+    all_off: &[u8],
+    all_on: &[u8],
+    patterns: &[Vec<u8>],
+    led_count: usize,
+) -> Vec<Option<(f64, f64)>> {
+    let mut sums = vec![(0.0f64, 0.0f64, 0u32); led_count];
+    for y in 0..height {
+        for x in 0..width {
+            let at = y * width + x;
+            if all_on[at].saturating_sub(all_off[at]) < MIN_CONTRAST {
+                continue;
+            }
+            let mid = all_off[at] / 2 + all_on[at] / 2;
+            let mut index = 0usize;
+            for (bit, plane) in patterns.iter().enumerate() {
+                if plane[at] > mid {
+                    index |= 1 << bit;
+                }
+            }
+            if index >= led_count {
+                continue; // decode noise spelled a nonexistent LED
+            }
+            let (sx, sy, n) = &mut sums[index];
+            *sx += x as f64 + 0.5;
+            *sy += y as f64 + 0.5;
+            *n += 1;
+        }
+    }
+    sums.iter()
+        .map(|(sx, sy, n)| {
+            if *n == 0 {
+                None
+            } else {
+                Some((sx / *n as f64 / width as f64, sy / *n as f64 / height as f64))
+            }
+        })
+        .collect()
+}
+
+/// The derived map as a JSON message: one `[x, y]` (or null) per LED.
+pub fn pixel_map_json(positions: &[Option<(f64, f64)>]) -> String {
+    let entries: Vec<String> = positions
+        .iter()
+        .map(|p| match p {
+            Some((x, y)) => format!("[{:.4},{:.4}]", x, y),
+            None => "null".to_string(),
+        })
+        .collect();
+    let mapped = positions.iter().filter(|p| p.is_some()).count();
+    format!(
+        "{{\"type\":\"pixel_map\",\"mapped\":{},\"led_count\":{},\"positions\":[{}]}}",
+        mapped,
+        positions.len(),
+        entries.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_count_covers_the_chain() {
+        assert_eq!(pattern_count(1), 1);
+        assert_eq!(pattern_count(2), 1);
+        assert_eq!(pattern_count(3), 2);
+        assert_eq!(pattern_count(256), 8);
+        assert_eq!(pattern_count(600), 10);
+    }
+
+    #[test]
+    fn bit_patterns_spell_the_index() {
+        let plane0 = bit_pattern(4, 0);
+        let plane1 = bit_pattern(4, 1);
+        let lit = |p: &Pixel| p.r > 0;
+        // LED 2 is 0b10: dark in plane 0, lit in plane 1.
+        assert!(!lit(&plane0[2]) && lit(&plane1[2]));
+        assert!(lit(&plane0[3]) && lit(&plane1[3]));
+        assert!(!lit(&plane0[0]) && !lit(&plane1[0]));
+    }
+
+    #[test]
+    fn decode_recovers_positions_and_skips_the_room() {
+        // 4x1 camera: LED 0 at pixel 0, LED 1 at pixel 3; pixels 1-2 see
+        // only the room and stay dark throughout.
+        let all_off = vec![10u8, 10, 10, 10];
+        let all_on = vec![200u8, 10, 10, 200];
+        let plane0 = vec![10u8, 10, 10, 200]; // LED 1 (0b1) lit
+        let positions = decode_positions(4, 1, &all_off, &all_on, &[plane0], 2);
+        let (x0, _) = positions[0].unwrap();
+        let (x1, _) = positions[1].unwrap();
+        assert!((x0 - 0.125).abs() < 1e-9);
+        assert!((x1 - 0.875).abs() < 1e-9);
+
+        // An LED the camera never decodes comes back None.
+        let positions = decode_positions(4, 1, &all_off, &all_on, &[vec![10u8; 4]], 3);
+        assert!(positions[1].is_none());
+    }
+}
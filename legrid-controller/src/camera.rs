@@ -0,0 +1,68 @@
+//! V4L2 camera capture shared by the calibration and ambient modes.
+//!
+//! Frames come from shelling out to `v4l2-ctl` for raw RGB3 rather than
+//! linking libv4l, the same keep-the-crate-dependency-free approach as
+//! the arecord-based audio capture.
+
+use std::io;
+use std::process::Command;
+
+use crate::frame::Pixel;
+
+/// Capture resolution requested from the camera. Small on purpose: the
+/// consumers work per-pixel and a room or panel fills a 160x120 view
+/// comfortably.
+pub const CAPTURE_WIDTH: usize = 160;
+pub const CAPTURE_HEIGHT: usize = 120;
+
+/// Grab one RGB frame from the camera at the capture size. A few frames
+/// are skipped so auto-exposure settles on the current scene.
+pub fn capture_rgb(device: &str) -> io::Result<Vec<Pixel>> {
+    let output = Command::new("v4l2-ctl")
+        .args([
+            "--device",
+            device,
+            "--set-fmt-video",
+            &format!(
+                "width={},height={},pixelformat=RGB3",
+                CAPTURE_WIDTH, CAPTURE_HEIGHT
+            ),
+            "--stream-mmap",
+            "--stream-skip=3",
+            "--stream-count=1",
+            "--stream-to=-",
+        ])
+        .output()
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Cannot run v4l2-ctl (is v4l-utils installed?): {}", e),
+            )
+        })?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "v4l2-ctl failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let need = CAPTURE_WIDTH * CAPTURE_HEIGHT * 3;
+    if output.stdout.len() < need {
+        return Err(io::Error::other(format!(
+            "short capture: {} of {} bytes",
+            output.stdout.len(),
+            need
+        )));
+    }
+    Ok(output.stdout[..need]
+        .chunks_exact(3)
+        .map(|rgb| Pixel { r: rgb[0], g: rgb[1], b: rgb[2] })
+        .collect())
+}
+
+/// Grab one grayscale frame: an RGB capture with the channels averaged.
+pub fn capture_gray(device: &str) -> io::Result<Vec<u8>> {
+    Ok(capture_rgb(device)?
+        .iter()
+        .map(|p| ((p.r as u16 + p.g as u16 + p.b as u16) / 3) as u8)
+        .collect())
+}
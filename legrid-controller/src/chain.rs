@@ -0,0 +1,232 @@
+//! Downstream controller chaining.
+//!
+//! Multi-controller walls run one head unit that receives the host stream
+//! and forwards it to downstream controllers over TCP (same length-prefixed
+//! framing as stdio; run the remote binary under socat or similar).
+//! Downstreams send their stats messages back on the same socket; the head
+//! folds them into its own stats report so the whole wall can be monitored
+//! from one place.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::controller::json_num_field;
+
+/// How long to wait before retrying a dead downstream connection.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The latest stats a downstream hop reported, plus what the head knows
+/// about the link itself.
+#[derive(Debug, Clone, Default)]
+pub struct HopStats {
+    pub connected: bool,
+    pub frames_sent: u64,
+    /// frames_processed from the hop's last stats message.
+    pub frames_processed: Option<f64>,
+    pub fps: Option<f64>,
+    pub temperature_c: Option<f64>,
+}
+
+impl HopStats {
+    /// Frames the head sent that the hop has not (yet) reported processing.
+    /// A steadily growing value means the hop is dropping frames.
+    pub fn loss(&self) -> Option<u64> {
+        let processed = self.frames_processed? as u64;
+        Some(self.frames_sent.saturating_sub(processed))
+    }
+}
+
+/// Fold a downstream stats message into the hop's view. Only the fields
+/// the aggregate report uses are extracted.
+pub fn update_hop_stats(stats: &mut HopStats, body: &str) {
+    if let Some(v) = json_num_field(body, "frames_processed") {
+        stats.frames_processed = Some(v);
+    }
+    if let Some(v) = json_num_field(body, "fps") {
+        stats.fps = Some(v);
+    }
+    if let Some(v) = json_num_field(body, "temperature_c") {
+        stats.temperature_c = Some(v);
+    }
+}
+
+/// Render the per-hop aggregate as a JSON array for the stats message.
+pub fn hops_json(hops: &[(String, HopStats)]) -> String {
+    let entries: Vec<String> = hops
+        .iter()
+        .map(|(addr, stats)| {
+            let mut entry = format!(
+                "{{\"addr\":\"{}\",\"connected\":{},\"frames_sent\":{}",
+                addr, stats.connected, stats.frames_sent
+            );
+            if let Some(loss) = stats.loss() {
+                entry.push_str(&format!(",\"loss\":{}", loss));
+            }
+            if let Some(fps) = stats.fps {
+                entry.push_str(&format!(",\"fps\":{:.1}", fps));
+            }
+            if let Some(temp) = stats.temperature_c {
+                entry.push_str(&format!(",\"temperature_c\":{:.1}", temp));
+            }
+            entry.push('}');
+            entry
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
+/// One TCP link to a downstream controller. The writer side lives on the
+/// main loop; a reader thread drains the hop's stats messages into shared
+/// state.
+struct DownstreamLink {
+    addr: String,
+    stream: Option<TcpStream>,
+    last_attempt: Option<Instant>,
+    stats: Arc<Mutex<HopStats>>,
+}
+
+impl DownstreamLink {
+    fn new(addr: String) -> Self {
+        Self {
+            addr,
+            stream: None,
+            last_attempt: None,
+            stats: Arc::new(Mutex::new(HopStats::default())),
+        }
+    }
+
+    fn connect(&mut self) {
+        if self.last_attempt.is_some_and(|t| t.elapsed() < RECONNECT_INTERVAL) {
+            return;
+        }
+        self.last_attempt = Some(Instant::now());
+        match TcpStream::connect(&self.addr) {
+            Ok(stream) => {
+                crate::log_info!("chain", "Connected to downstream controller {}", self.addr);
+                if let Ok(reader) = stream.try_clone() {
+                    spawn_stats_reader(reader, Arc::clone(&self.stats));
+                }
+                self.stats.lock().unwrap().connected = true;
+                self.stream = Some(stream);
+            }
+            Err(e) => {
+                crate::log_warn!("chain", "Downstream {} unreachable: {}", self.addr, e);
+            }
+        }
+    }
+
+    fn forward(&mut self, payload: &[u8]) {
+        if self.stream.is_none() {
+            self.connect();
+        }
+        let Some(stream) = self.stream.as_mut() else {
+            return;
+        };
+        let length = (payload.len() as u32).to_le_bytes();
+        let result = stream.write_all(&length).and_then(|_| stream.write_all(payload));
+        if let Err(e) = result {
+            crate::log_warn!("chain", "Downstream {} write failed, dropping link: {}", self.addr, e);
+            self.stream = None;
+            self.stats.lock().unwrap().connected = false;
+            return;
+        }
+        self.stats.lock().unwrap().frames_sent += 1;
+    }
+}
+
+/// Drain length-prefixed JSON stats messages from a downstream socket.
+fn spawn_stats_reader(mut stream: TcpStream, stats: Arc<Mutex<HopStats>>) {
+    thread::spawn(move || {
+        loop {
+            let mut length_bytes = [0u8; 4];
+            if stream.read_exact(&mut length_bytes).is_err() {
+                break;
+            }
+            let length = u32::from_le_bytes(length_bytes) as usize;
+            let mut body = vec![0u8; length];
+            if stream.read_exact(&mut body).is_err() {
+                break;
+            }
+            if let Ok(body) = std::str::from_utf8(&body) {
+                update_hop_stats(&mut stats.lock().unwrap(), body);
+            }
+        }
+        stats.lock().unwrap().connected = false;
+    });
+}
+
+/// Forwards the incoming frame stream to every configured downstream
+/// controller and aggregates their stats.
+pub struct ChainForwarder {
+    links: Vec<DownstreamLink>,
+}
+
+impl ChainForwarder {
+    pub fn new(addrs: &[String]) -> io::Result<Self> {
+        let mut links: Vec<DownstreamLink> = addrs.iter().cloned().map(DownstreamLink::new).collect();
+        for link in links.iter_mut() {
+            link.connect();
+        }
+        Ok(Self { links })
+    }
+
+    /// Relay a raw frame message to every hop. Failed hops are skipped and
+    /// retried on a timer; the local output path is never blocked on them.
+    pub fn forward(&mut self, payload: &[u8]) {
+        for link in self.links.iter_mut() {
+            link.forward(payload);
+        }
+    }
+
+    /// Snapshot every hop's stats for the aggregate report.
+    pub fn hops(&self) -> Vec<(String, HopStats)> {
+        self.links
+            .iter()
+            .map(|link| (link.addr.clone(), link.stats.lock().unwrap().clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hop_stats_track_downstream_reports() {
+        let mut stats = HopStats {
+            frames_sent: 100,
+            ..HopStats::default()
+        };
+        update_hop_stats(&mut stats, "{\"frames_processed\":97,\"fps\":29.8,\"temperature_c\":51.2}");
+        assert_eq!(stats.frames_processed, Some(97.0));
+        assert_eq!(stats.loss(), Some(3));
+        assert_eq!(stats.fps, Some(29.8));
+    }
+
+    #[test]
+    fn loss_is_unknown_before_the_first_report() {
+        let stats = HopStats {
+            frames_sent: 10,
+            ..HopStats::default()
+        };
+        assert_eq!(stats.loss(), None);
+    }
+
+    #[test]
+    fn hops_json_includes_known_fields_only() {
+        let mut stats = HopStats {
+            connected: true,
+            frames_sent: 5,
+            ..HopStats::default()
+        };
+        update_hop_stats(&mut stats, "{\"frames_processed\":5,\"fps\":30.0}");
+        let json = hops_json(&[("10.0.0.2:9000".to_string(), stats)]);
+        assert_eq!(
+            json,
+            "[{\"addr\":\"10.0.0.2:9000\",\"connected\":true,\"frames_sent\":5,\"loss\":0,\"fps\":30.0}]"
+        );
+    }
+}
@@ -0,0 +1,988 @@
+//! Runtime configuration: command-line parsing plus the two-stage config
+//! apply used by control messages.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::audio::AudioEffect;
+use crate::driver::DriverKind;
+use crate::effects::{IdleEffect, TestPattern};
+use crate::frame::Pixel;
+use crate::pipeline::{BlendSpace, ColorOrder, InterpolateMode};
+
+/// How long a newly applied config has to prove itself before we either
+/// commit it or roll back to the previous one.
+pub const CONFIG_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Runtime configuration, settable from the command line and replaceable at
+/// runtime via a config control message.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub width: u16,
+    pub height: u16,
+    pub led_pin: u8,
+    pub led_count: usize,
+    pub driver: DriverKind,
+    pub color_order: ColorOrder,
+    pub output_fps: f64,
+    pub interpolate: InterpolateMode,
+    /// Space blends and crossfades run in; linear fixes sRGB midpoints.
+    pub blend_space: BlendSpace,
+    /// Hard cap on how fast frames are pushed to the strip; 0 disables.
+    pub max_fps: f64,
+    /// Idle takeover: effect shown after idle_timeout seconds without frames.
+    pub idle_effect: IdleEffect,
+    pub idle_timeout: f64,
+    pub idle_color: Pixel,
+    /// Include allocation-rate telemetry in stats messages.
+    pub profile_alloc: bool,
+    /// Standalone test pattern; when set the controller ignores stdin.
+    pub test_pattern: Option<TestPattern>,
+    /// Watch mode: display content files dropped into this directory.
+    pub watch_dir: Option<PathBuf>,
+    pub watch_dwell: f64,
+    /// Dump the incoming frame stream to this file while running.
+    pub record_path: Option<PathBuf>,
+    /// Replay a recorded stream instead of reading stdin.
+    pub play_path: Option<PathBuf>,
+    pub play_speed: f64,
+    pub play_loop: bool,
+    /// Interactive color-order detection on the attached panel.
+    pub detect_color_order: bool,
+    /// Estimate the connected chain length from supply current and exit.
+    pub detect_chain_length: bool,
+    /// Derive a physical pixel map from structured-light patterns seen
+    /// by a V4L2 camera at this device path (experimental).
+    pub calibrate_camera: Option<String>,
+    /// Ambient mood-light mode: sample room colors from the camera at
+    /// this device path and render matching gradients.
+    pub ambient_camera: Option<String>,
+    pub save_color_order: Option<PathBuf>,
+    /// HTTP upload endpoint for content files; requires a token.
+    pub upload_port: Option<u16>,
+    pub upload_token: Option<String>,
+    /// Where uploaded content lands; defaults to the watch directory.
+    pub content_dir: Option<PathBuf>,
+    /// Downstream controllers (host:port) to relay the frame stream to.
+    pub forward_addrs: Vec<String>,
+    /// The --config file this config was loaded from, kept for hot reload.
+    pub config_path: Option<PathBuf>,
+    /// Multi-channel output map ("pin:start-end,..."); validated against
+    /// led_count when the controller starts.
+    pub channels_spec: Option<String>,
+    /// Post-processor chain ("gamma:2.2,brightness:0.8,..."), in execution
+    /// order; empty means raw pass-through.
+    pub pipeline_spec: Option<String>,
+    /// Panel tiling ("xoff,yoff:WxH:orientation:pin;..."): the logical
+    /// frame is split across multiple physical panels.
+    pub tiles_spec: Option<String>,
+    /// Prometheus metrics endpoint port; None disables it.
+    pub metrics_port: Option<u16>,
+    /// MQTT broker (host:port) for the Home Assistant bridge.
+    pub mqtt_addr: Option<String>,
+    /// Join the local Ableton Link session for tempo sync; needs a build
+    /// with the ableton-link feature.
+    pub link: bool,
+    /// Outbound webhooks for threshold events
+    /// ("https://hooks.example/abc https://other/xyz|thermal_throttle").
+    pub webhooks_spec: Option<String>,
+    /// Failover role: "primary:host:port" or "standby:port".
+    pub failover_spec: Option<String>,
+    /// GPIO pin that switches the data line / PSU relay on takeover.
+    pub takeover_gpio: Option<u8>,
+    /// GPIO inputs ("button:pin;encoder:pinA,pinB;..."); events go
+    /// upstream over the stats channel.
+    pub inputs_spec: Option<String>,
+    /// UPS power-failing pin; triggers blanking, a state save, and a
+    /// filesystem sync before the supply dies.
+    pub power_loss_gpio: Option<u8>,
+    /// Thermal throttle thresholds ("warn:max" in degrees C).
+    pub thermal_spec: Option<String>,
+    /// Time-of-day profile keyframes ("HH:MM=brightness:kelvin;...").
+    pub profiles_spec: Option<String>,
+    /// Do-not-disturb windows ("HH:MM-HH:MM;..."): notifications wait
+    /// until the window ends and one-shot effects are dropped.
+    pub dnd_spec: Option<String>,
+    /// Sunrise alarm ("HH:MM" or "HH:MM=RAMP_MINUTES"): warm wake ramp
+    /// ending at the given time.
+    pub alarm_spec: Option<String>,
+    /// Enabled HTTP widgets ("weather;..."), each a self-refreshing
+    /// overlay layer.
+    pub widgets_spec: Option<String>,
+    /// Idle-effect shuffle ("rainbow,sparkle;dwell=300;brightness=0.4-0.9"):
+    /// rotates idle content through a whitelist instead of one effect.
+    pub shuffle_spec: Option<String>,
+    /// Effect-modifier chain for the main layer
+    /// ("mirror:h,kaleidoscope,..."), applied before the pipeline.
+    pub modifiers_spec: Option<String>,
+    /// Separate modifier chain for the overlay stream.
+    pub overlay_modifiers_spec: Option<String>,
+    /// Local timezone offset from UTC, in hours, for time-keyed features.
+    pub utc_offset: f64,
+    /// Site latitude/longitude in degrees (east positive), enabling
+    /// sunrise/sunset-keyed profile keyframes.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Battery-backed RTC (e.g. "rtc0" for a DS3231) consulted when the
+    /// system clock is clearly unset at boot.
+    pub rtc_device: Option<String>,
+    /// Embed the frame ID as a low-order watermark in displayed frames.
+    pub watermark: bool,
+    /// Verify incoming frames carry an intact watermark; report via stats.
+    pub verify_watermark: bool,
+    /// Binary-coded-modulation depth (1-8 bits) for on/off-only backends;
+    /// None outputs full frames directly.
+    pub bcm_depth: Option<u8>,
+    /// ALSA capture device for the audio-reactive subsystem.
+    pub audio_device: Option<String>,
+    /// Which built-in visualization the audio drives.
+    pub audio_effect: AudioEffect,
+    /// Modulate incoming frames with the audio level instead of running
+    /// the visualization standalone.
+    pub audio_modulate: bool,
+    /// Splash frame shown at startup; written by the save_splash command.
+    pub splash_path: Option<PathBuf>,
+    /// Autosave the live frame and restore it after a crash/restart.
+    pub restore_last_frame: bool,
+    /// Directory for runtime state (splash autosave, recordings, saved
+    /// color order, uploaded content); point it at a tmpfs on Pis with a
+    /// read-only root filesystem. Relative state paths resolve under it.
+    pub state_dir: Option<PathBuf>,
+    /// Disable every implicit runtime write, for fully read-only setups
+    /// with no tmpfs to spare.
+    pub no_persist: bool,
+    /// NxN physical pixels per logical pixel; 1 is native resolution.
+    /// Full frames are averaged down, logical-size frames replicated up.
+    pub bin: usize,
+    /// Port for the live PNG snapshot endpoint; None disables it.
+    pub snapshot_port: Option<u16>,
+    /// Per-segment color-correction spec for mixed-batch panels.
+    pub correction_spec: Option<String>,
+    /// Second post-processor chain for A/B comparison against --pipeline.
+    pub pipeline_b_spec: Option<String>,
+    /// Backend the B pipeline renders to (e.g. the simulator while A
+    /// drives hardware); without it only deltas are tracked.
+    pub ab_driver: Option<DriverKind>,
+    /// Minimum severity that reaches stderr; debug includes per-frame spam.
+    pub log_level: crate::log::Level,
+    /// Text for humans, JSON for journald/Vector collectors.
+    pub log_format: crate::log::LogFormat,
+}
+
+impl Config {
+    pub fn defaults() -> Self {
+        Self {
+            width: 25,
+            height: 24,
+            led_pin: 18,
+            led_count: 600,
+            driver: DriverKind::Mock,
+            color_order: ColorOrder::Rgb,
+            output_fps: 0.0,
+            interpolate: InterpolateMode::None,
+            blend_space: BlendSpace::Srgb,
+            max_fps: 0.0,
+            idle_effect: IdleEffect::None,
+            idle_timeout: 5.0,
+            idle_color: Pixel { r: 255, g: 180, b: 60 },
+            profile_alloc: false,
+            test_pattern: None,
+            watch_dir: None,
+            watch_dwell: 10.0,
+            record_path: None,
+            play_path: None,
+            play_speed: 1.0,
+            play_loop: false,
+            detect_color_order: false,
+            detect_chain_length: false,
+            calibrate_camera: None,
+            ambient_camera: None,
+            save_color_order: None,
+            upload_port: None,
+            upload_token: None,
+            content_dir: None,
+            forward_addrs: Vec::new(),
+            config_path: None,
+            channels_spec: None,
+            pipeline_spec: None,
+            tiles_spec: None,
+            metrics_port: None,
+            mqtt_addr: None,
+            link: false,
+            webhooks_spec: None,
+            failover_spec: None,
+            takeover_gpio: None,
+            inputs_spec: None,
+            power_loss_gpio: None,
+            thermal_spec: None,
+            profiles_spec: None,
+            dnd_spec: None,
+            alarm_spec: None,
+            widgets_spec: None,
+            shuffle_spec: None,
+            modifiers_spec: None,
+            overlay_modifiers_spec: None,
+            utc_offset: 0.0,
+            latitude: None,
+            longitude: None,
+            rtc_device: None,
+            watermark: false,
+            verify_watermark: false,
+            bcm_depth: None,
+            audio_device: None,
+            audio_effect: AudioEffect::Spectrum,
+            audio_modulate: false,
+            splash_path: None,
+            restore_last_frame: false,
+            state_dir: None,
+            no_persist: false,
+            bin: 1,
+            snapshot_port: None,
+            correction_spec: None,
+            pipeline_b_spec: None,
+            ab_driver: None,
+            log_level: crate::log::Level::Info,
+            log_format: crate::log::LogFormat::Text,
+        }
+    }
+
+    /// The configured site, when both halves are present.
+    pub fn location(&self) -> Option<(f64, f64)> {
+        Some((self.latitude?, self.longitude?))
+    }
+}
+
+/// Parse an RRGGBB hex color as used by --idle-color and control messages.
+pub fn parse_hex_color(s: &str) -> Option<Pixel> {
+    let s = s.trim_start_matches('#');
+    if s.len() != 6 {
+        return None;
+    }
+    Some(Pixel {
+        r: u8::from_str_radix(&s[0..2], 16).ok()?,
+        g: u8::from_str_radix(&s[2..4], 16).ok()?,
+        b: u8::from_str_radix(&s[4..6], 16).ok()?,
+    })
+}
+
+/// Apply one `key = value` pair from a config file. Returns an error
+/// message naming the offending key so operators can fix the file without
+/// reading source.
+fn apply_config_key(config: &mut Config, key: &str, value: &TomlValue) -> Result<(), String> {
+    let bad = |expected: &str| format!("config key '{}': expected {}", key, expected);
+    match key {
+        "width" => config.width = value.as_int().ok_or_else(|| bad("an integer"))? as u16,
+        "height" => config.height = value.as_int().ok_or_else(|| bad("an integer"))? as u16,
+        "led_pin" => config.led_pin = value.as_int().ok_or_else(|| bad("an integer"))? as u8,
+        "led_count" => config.led_count = value.as_int().ok_or_else(|| bad("an integer"))? as usize,
+        "driver" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.driver = DriverKind::parse(s).ok_or_else(|| bad("mock|terminal|window"))?;
+        }
+        "color_order" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.color_order = ColorOrder::parse(s).ok_or_else(|| bad("a permutation of rgb"))?;
+        }
+        "output_fps" => config.output_fps = value.as_float().ok_or_else(|| bad("a number"))?,
+        "interpolate" => {
+            config.interpolate = match value.as_str().ok_or_else(|| bad("a string"))? {
+                "linear" => InterpolateMode::Linear,
+                "none" => InterpolateMode::None,
+                _ => return Err(bad("none|linear")),
+            };
+        }
+        "blend_space" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.blend_space = BlendSpace::parse(s).ok_or_else(|| bad("srgb|linear"))?;
+        }
+        "max_fps" => config.max_fps = value.as_float().ok_or_else(|| bad("a number"))?,
+        "idle_effect" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.idle_effect = IdleEffect::parse(s);
+        }
+        "idle_timeout" => config.idle_timeout = value.as_float().ok_or_else(|| bad("a number"))?,
+        "idle_color" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.idle_color = parse_hex_color(s).ok_or_else(|| bad("an RRGGBB hex color"))?;
+        }
+        "profile_alloc" => config.profile_alloc = value.as_bool().ok_or_else(|| bad("a boolean"))?,
+        "ambient_camera" => config.ambient_camera = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string()),
+        "watch_dir" => config.watch_dir = Some(PathBuf::from(value.as_str().ok_or_else(|| bad("a string"))?)),
+        "watch_dwell" => config.watch_dwell = value.as_float().ok_or_else(|| bad("a number"))?,
+        "upload_port" => config.upload_port = Some(value.as_int().ok_or_else(|| bad("an integer"))? as u16),
+        "upload_token" => {
+            config.upload_token = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "content_dir" => {
+            config.content_dir = Some(PathBuf::from(value.as_str().ok_or_else(|| bad("a string"))?))
+        }
+        "pipeline" => {
+            config.pipeline_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "channels" => {
+            config.channels_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "tiles" => {
+            config.tiles_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "metrics_port" => {
+            config.metrics_port = Some(value.as_int().ok_or_else(|| bad("an integer"))? as u16)
+        }
+        "mqtt" => {
+            config.mqtt_addr = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "link" => config.link = value.as_bool().ok_or_else(|| bad("a boolean"))?,
+        "webhooks" => {
+            config.webhooks_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "failover" => {
+            config.failover_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "takeover_gpio" => {
+            config.takeover_gpio = Some(value.as_int().ok_or_else(|| bad("an integer"))? as u8)
+        }
+        "inputs" => {
+            config.inputs_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "power_loss_gpio" => {
+            config.power_loss_gpio = Some(value.as_int().ok_or_else(|| bad("an integer"))? as u8)
+        }
+        "thermal_limit" => {
+            config.thermal_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "profiles" => {
+            config.profiles_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "dnd" => {
+            config.dnd_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "alarm" => {
+            config.alarm_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "widgets" => {
+            config.widgets_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "shuffle" => {
+            config.shuffle_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "modifiers" => {
+            config.modifiers_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "overlay_modifiers" => {
+            config.overlay_modifiers_spec =
+                Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "utc_offset" => config.utc_offset = value.as_float().ok_or_else(|| bad("a number"))?,
+        "latitude" => config.latitude = Some(value.as_float().ok_or_else(|| bad("a number"))?),
+        "longitude" => config.longitude = Some(value.as_float().ok_or_else(|| bad("a number"))?),
+        "rtc" => {
+            config.rtc_device = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "watermark" => config.watermark = value.as_bool().ok_or_else(|| bad("a boolean"))?,
+        "verify_watermark" => {
+            config.verify_watermark = value.as_bool().ok_or_else(|| bad("a boolean"))?
+        }
+        "bcm_depth" => {
+            config.bcm_depth = Some(value.as_int().ok_or_else(|| bad("an integer"))? as u8)
+        }
+        "audio_device" => {
+            config.audio_device = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "audio_effect" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.audio_effect =
+                AudioEffect::parse(s).ok_or_else(|| bad("spectrum|vu|beat-pulse"))?;
+        }
+        "audio_modulate" => {
+            config.audio_modulate = value.as_bool().ok_or_else(|| bad("a boolean"))?
+        }
+        "splash" => {
+            config.splash_path = Some(PathBuf::from(value.as_str().ok_or_else(|| bad("a string"))?))
+        }
+        "restore_last_frame" => {
+            config.restore_last_frame = value.as_bool().ok_or_else(|| bad("a boolean"))?
+        }
+        "state_dir" => {
+            config.state_dir = Some(PathBuf::from(value.as_str().ok_or_else(|| bad("a string"))?))
+        }
+        "no_persist" => config.no_persist = value.as_bool().ok_or_else(|| bad("a boolean"))?,
+        "bin" => {
+            config.bin = (value.as_int().ok_or_else(|| bad("an integer"))? as usize).max(1)
+        }
+        "snapshot_http" => {
+            config.snapshot_port = Some(value.as_int().ok_or_else(|| bad("an integer"))? as u16)
+        }
+        "correction" => {
+            config.correction_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "pipeline_b" => {
+            config.pipeline_b_spec = Some(value.as_str().ok_or_else(|| bad("a string"))?.to_string())
+        }
+        "ab_driver" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.ab_driver = Some(DriverKind::parse(s).ok_or_else(|| bad("a driver name"))?);
+        }
+        "log_level" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.log_level =
+                crate::log::Level::parse(s).ok_or_else(|| bad("debug|info|warn|error"))?;
+        }
+        "log_format" => {
+            let s = value.as_str().ok_or_else(|| bad("a string"))?;
+            config.log_format =
+                crate::log::LogFormat::parse(s).ok_or_else(|| bad("text|json"))?;
+        }
+        "forward" => {
+            config.forward_addrs = value
+                .as_str_array()
+                .ok_or_else(|| bad("an array of \"host:port\" strings"))?;
+        }
+        other => return Err(format!("unknown config key '{}'", other)),
+    }
+    Ok(())
+}
+
+/// A value from the config file. The parser handles the flat subset of
+/// TOML the controller needs: strings, numbers, booleans, and arrays of
+/// strings; no tables or dotted keys.
+enum TomlValue {
+    Str(String),
+    Num(f64),
+    Bool(bool),
+    StrArray(Vec<String>),
+}
+
+impl TomlValue {
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if let Some(stripped) = raw.strip_prefix('"') {
+            return Some(TomlValue::Str(stripped.strip_suffix('"')?.to_string()));
+        }
+        if raw == "true" || raw == "false" {
+            return Some(TomlValue::Bool(raw == "true"));
+        }
+        if let Some(inner) = raw.strip_prefix('[') {
+            let inner = inner.strip_suffix(']')?.trim();
+            let mut items = Vec::new();
+            if !inner.is_empty() {
+                for item in inner.split(',') {
+                    let item = item.trim();
+                    items.push(item.strip_prefix('"')?.strip_suffix('"')?.to_string());
+                }
+            }
+            return Some(TomlValue::StrArray(items));
+        }
+        raw.parse().ok().map(TomlValue::Num)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            TomlValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            TomlValue::Num(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            TomlValue::Num(n) if n.fract() == 0.0 => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            TomlValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn as_str_array(&self) -> Option<Vec<String>> {
+        match self {
+            TomlValue::StrArray(items) => Some(items.clone()),
+            TomlValue::Str(s) => Some(vec![s.clone()]),
+            _ => None,
+        }
+    }
+}
+
+/// Apply a TOML config file's contents onto `config`. Section headers are
+/// accepted and ignored so files can stay organized; every `key = value`
+/// line must name a known option.
+pub fn apply_config_file(config: &mut Config, contents: &str) -> Result<(), String> {
+    for (line_no, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (line.starts_with('[') && line.ends_with(']')) {
+            continue;
+        }
+        let Some((key, raw_value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected 'key = value'", line_no + 1));
+        };
+        let key = key.trim();
+        let value = TomlValue::parse(raw_value)
+            .ok_or_else(|| format!("config key '{}': unparseable value", key))?;
+        apply_config_key(config, key, &value)?;
+    }
+    Ok(())
+}
+
+/// Build a config from command-line arguments (args[0] is the program
+/// name, as from `std::env::args`). A `--config file.toml` is applied
+/// first, so the remaining flags override file values.
+pub fn parse_args(args: &[String]) -> io::Result<Config> {
+    let mut config = Config::defaults();
+
+    for i in 1..args.len() {
+        if args[i] == "--config" && i + 1 < args.len() {
+            let path = &args[i + 1];
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("Cannot read {}: {}", path, e)))?;
+            apply_config_file(&mut config, &contents)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", path, e)))?;
+            config.config_path = Some(PathBuf::from(path));
+        }
+    }
+
+    for i in 1..args.len() {
+        match args[i].as_str() {
+            "--width"
+                if i + 1 < args.len() => {
+                    config.width = args[i + 1].parse().unwrap_or(25);
+                }
+            "--height"
+                if i + 1 < args.len() => {
+                    config.height = args[i + 1].parse().unwrap_or(24);
+                }
+            "--led-pin"
+                if i + 1 < args.len() => {
+                    config.led_pin = args[i + 1].parse().unwrap_or(18);
+                }
+            "--led-count"
+                if i + 1 < args.len() => {
+                    config.led_count = args[i + 1].parse().unwrap_or(600);
+                }
+            "--output-fps"
+                if i + 1 < args.len() => {
+                    config.output_fps = args[i + 1].parse().unwrap_or(0.0);
+                }
+            "--interpolate"
+                if i + 1 < args.len() => {
+                    config.interpolate = match args[i + 1].as_str() {
+                        "linear" => InterpolateMode::Linear,
+                        _ => InterpolateMode::None,
+                    };
+                }
+            "--blend-space"
+                if i + 1 < args.len() => {
+                    match BlendSpace::parse(&args[i + 1]) {
+                        Some(space) => config.blend_space = space,
+                        None => crate::log_warn!("config", "Unknown blend space: {} (expected srgb|linear)", args[i + 1]),
+                    }
+                }
+            "--max-fps"
+                if i + 1 < args.len() => {
+                    config.max_fps = args[i + 1].parse().unwrap_or(0.0);
+                }
+            "--idle-effect"
+                if i + 1 < args.len() => {
+                    config.idle_effect = IdleEffect::parse(&args[i + 1]);
+                }
+            "--idle-timeout"
+                if i + 1 < args.len() => {
+                    config.idle_timeout = args[i + 1].parse().unwrap_or(5.0);
+                }
+            "--idle-color"
+                if i + 1 < args.len() => {
+                    if let Some(color) = parse_hex_color(&args[i + 1]) {
+                        config.idle_color = color;
+                    }
+                }
+            "--profile-alloc" => {
+                config.profile_alloc = true;
+            }
+            "--test-pattern"
+                if i + 1 < args.len() => {
+                    config.test_pattern = TestPattern::parse(&args[i + 1]);
+                    if config.test_pattern.is_none() {
+                        crate::log_warn!("config", "Unknown test pattern: {} (expected wipe|sweep|index|gradient|checkerboard|bitflip|ramp|coords)",
+                                  args[i + 1]);
+                    }
+                }
+            "--watch-dir"
+                if i + 1 < args.len() => {
+                    config.watch_dir = Some(PathBuf::from(&args[i + 1]));
+                }
+            "--watch-dwell"
+                if i + 1 < args.len() => {
+                    config.watch_dwell = args[i + 1].parse().unwrap_or(10.0);
+                }
+            "--record"
+                if i + 1 < args.len() => {
+                    config.record_path = Some(PathBuf::from(&args[i + 1]));
+                }
+            "--play"
+                if i + 1 < args.len() => {
+                    config.play_path = Some(PathBuf::from(&args[i + 1]));
+                }
+            "--play-speed"
+                if i + 1 < args.len() => {
+                    config.play_speed = args[i + 1].parse().unwrap_or(1.0);
+                }
+            "--play-loop" => {
+                config.play_loop = true;
+            }
+            "--upload-port"
+                if i + 1 < args.len() => {
+                    config.upload_port = args[i + 1].parse().ok();
+                }
+            "--upload-token"
+                if i + 1 < args.len() => {
+                    config.upload_token = Some(args[i + 1].clone());
+                }
+            "--content-dir"
+                if i + 1 < args.len() => {
+                    config.content_dir = Some(PathBuf::from(&args[i + 1]));
+                }
+            "--driver"
+                if i + 1 < args.len() => {
+                    match DriverKind::parse(&args[i + 1]) {
+                        Some(kind) => config.driver = kind,
+                        None => crate::log_warn!("config", "Unknown driver: {} (expected mock|terminal|window)", args[i + 1]),
+                    }
+                }
+            "--color-order"
+                if i + 1 < args.len() => {
+                    match ColorOrder::parse(&args[i + 1]) {
+                        Some(order) => config.color_order = order,
+                        None => crate::log_warn!("config", "Unknown color order: {} (expected a permutation of rgb)", args[i + 1]),
+                    }
+                }
+            "--forward"
+                if i + 1 < args.len() => {
+                    config.forward_addrs.push(args[i + 1].clone());
+                }
+            "--channels"
+                if i + 1 < args.len() => {
+                    config.channels_spec = Some(args[i + 1].clone());
+                }
+            "--pipeline"
+                if i + 1 < args.len() => {
+                    config.pipeline_spec = Some(args[i + 1].clone());
+                }
+            "--tiles"
+                if i + 1 < args.len() => {
+                    config.tiles_spec = Some(args[i + 1].clone());
+                }
+            "--metrics-port"
+                if i + 1 < args.len() => {
+                    config.metrics_port = args[i + 1].parse().ok();
+                }
+            "--mqtt"
+                if i + 1 < args.len() => {
+                    config.mqtt_addr = Some(args[i + 1].clone());
+                }
+            "--link" => {
+                config.link = true;
+            }
+            "--webhooks"
+                if i + 1 < args.len() => {
+                    config.webhooks_spec = Some(args[i + 1].clone());
+                }
+            "--failover"
+                if i + 1 < args.len() => {
+                    config.failover_spec = Some(args[i + 1].clone());
+                }
+            "--takeover-gpio"
+                if i + 1 < args.len() => {
+                    config.takeover_gpio = args[i + 1].parse().ok();
+                }
+            "--inputs"
+                if i + 1 < args.len() => {
+                    config.inputs_spec = Some(args[i + 1].clone());
+                }
+            "--power-loss-gpio"
+                if i + 1 < args.len() => {
+                    config.power_loss_gpio = args[i + 1].parse().ok();
+                }
+            "--thermal-limit"
+                if i + 1 < args.len() => {
+                    config.thermal_spec = Some(args[i + 1].clone());
+                }
+            "--profiles"
+                if i + 1 < args.len() => {
+                    config.profiles_spec = Some(args[i + 1].clone());
+                }
+            "--dnd"
+                if i + 1 < args.len() => {
+                    config.dnd_spec = Some(args[i + 1].clone());
+                }
+            "--alarm"
+                if i + 1 < args.len() => {
+                    config.alarm_spec = Some(args[i + 1].clone());
+                }
+            "--widgets"
+                if i + 1 < args.len() => {
+                    config.widgets_spec = Some(args[i + 1].clone());
+                }
+            "--shuffle"
+                if i + 1 < args.len() => {
+                    config.shuffle_spec = Some(args[i + 1].clone());
+                }
+            "--modifiers"
+                if i + 1 < args.len() => {
+                    config.modifiers_spec = Some(args[i + 1].clone());
+                }
+            "--overlay-modifiers"
+                if i + 1 < args.len() => {
+                    config.overlay_modifiers_spec = Some(args[i + 1].clone());
+                }
+            "--utc-offset"
+                if i + 1 < args.len() => {
+                    config.utc_offset = args[i + 1].parse().unwrap_or(0.0);
+                }
+            "--location"
+                if i + 1 < args.len() => {
+                    match args[i + 1].split_once(',').and_then(|(lat, lon)| {
+                        Some((lat.trim().parse().ok()?, lon.trim().parse().ok()?))
+                    }) {
+                        Some((lat, lon)) => {
+                            config.latitude = Some(lat);
+                            config.longitude = Some(lon);
+                        }
+                        None => crate::log_warn!("config", "Bad --location: {} (expected LAT,LON in degrees)", args[i + 1]),
+                    }
+                }
+            "--rtc"
+                if i + 1 < args.len() => {
+                    config.rtc_device = Some(args[i + 1].clone());
+                }
+            "--bcm-depth"
+                if i + 1 < args.len() => {
+                    config.bcm_depth = args[i + 1].parse().ok();
+                }
+            "--audio-device"
+                if i + 1 < args.len() => {
+                    config.audio_device = Some(args[i + 1].clone());
+                }
+            "--audio-effect"
+                if i + 1 < args.len() => {
+                    match AudioEffect::parse(&args[i + 1]) {
+                        Some(effect) => config.audio_effect = effect,
+                        None => crate::log_warn!("config",
+                            "Unknown audio effect: {} (expected spectrum|vu|beat-pulse)",
+                            args[i + 1]
+                        ),
+                    }
+                }
+            "--audio-modulate" => {
+                config.audio_modulate = true;
+            }
+            "--splash"
+                if i + 1 < args.len() => {
+                    config.splash_path = Some(PathBuf::from(&args[i + 1]));
+                }
+            "--restore-last" => {
+                config.restore_last_frame = true;
+            }
+            "--state-dir"
+                if i + 1 < args.len() => {
+                    config.state_dir = Some(PathBuf::from(&args[i + 1]));
+                }
+            "--no-persist" => {
+                config.no_persist = true;
+            }
+            "--bin"
+                if i + 1 < args.len() => {
+                    config.bin = args[i + 1].parse().unwrap_or(1).max(1);
+                }
+            "--snapshot-http"
+                if i + 1 < args.len() => {
+                    config.snapshot_port = args[i + 1].parse().ok();
+                }
+            "--correction"
+                if i + 1 < args.len() => {
+                    config.correction_spec = Some(args[i + 1].clone());
+                }
+            "--pipeline-b"
+                if i + 1 < args.len() => {
+                    config.pipeline_b_spec = Some(args[i + 1].clone());
+                }
+            "--ab-driver"
+                if i + 1 < args.len() => {
+                    match DriverKind::parse(&args[i + 1]) {
+                        Some(kind) => config.ab_driver = Some(kind),
+                        None => crate::log_warn!("config", "Unknown A/B driver: {}", args[i + 1]),
+                    }
+                }
+            "--log-level"
+                if i + 1 < args.len() => {
+                    match crate::log::Level::parse(&args[i + 1]) {
+                        Some(level) => config.log_level = level,
+                        None => crate::log_warn!("config", "Unknown log level: {} (expected debug|info|warn|error)", args[i + 1]),
+                    }
+                }
+            "--log-format"
+                if i + 1 < args.len() => {
+                    match crate::log::LogFormat::parse(&args[i + 1]) {
+                        Some(format) => config.log_format = format,
+                        None => crate::log_warn!("config", "Unknown log format: {} (expected text|json)", args[i + 1]),
+                    }
+                }
+            "--watermark" => {
+                config.watermark = true;
+            }
+            "--verify-watermark" => {
+                config.verify_watermark = true;
+            }
+            "--detect-color-order" => {
+                config.detect_color_order = true;
+            }
+            "--detect-chain-length" => {
+                config.detect_chain_length = true;
+            }
+            "--calibrate-camera"
+                if i + 1 < args.len() => {
+                    config.calibrate_camera = Some(args[i + 1].clone());
+                }
+            "--ambient-camera"
+                if i + 1 < args.len() => {
+                    config.ambient_camera = Some(args[i + 1].clone());
+                }
+            "--save-color-order"
+                if i + 1 < args.len() => {
+                    config.save_color_order = Some(PathBuf::from(&args[i + 1]));
+                }
+            _ => {}
+        }
+    }
+
+    // Read-only root support: relative state paths land in --state-dir
+    // (usually a tmpfs) instead of the working directory.
+    if let Some(dir) = config.state_dir.clone() {
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            crate::log_warn!("config", "Cannot create state dir {}: {}", dir.display(), e);
+        }
+        for path in [
+            &mut config.splash_path,
+            &mut config.record_path,
+            &mut config.save_color_order,
+            &mut config.content_dir,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if path.is_relative() {
+                *path = dir.join(&*path);
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("local_controller")
+            .chain(list.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn parses_grid_dimensions() {
+        let config = parse_args(&args(&["--width", "10", "--height", "8", "--led-count", "80"])).unwrap();
+        assert_eq!((config.width, config.height), (10, 8));
+        assert_eq!(config.led_count, 80);
+    }
+
+    #[test]
+    fn unknown_flags_are_ignored() {
+        let config = parse_args(&args(&["--no-such-flag", "--max-fps", "60"])).unwrap();
+        assert_eq!(config.max_fps, 60.0);
+    }
+
+    #[test]
+    fn config_file_sets_known_keys() {
+        let mut config = Config::defaults();
+        let file = concat!(
+            "# grid\n",
+            "[controller]\n",
+            "width = 10\n",
+            "height = 8\n",
+            "driver = \"terminal\"\n",
+            "color_order = \"grb\"\n",
+            "max_fps = 60.5\n",
+            "profile_alloc = true\n",
+            "forward = [\"10.0.0.2:9000\", \"10.0.0.3:9000\"]\n",
+        );
+        apply_config_file(&mut config, file).unwrap();
+        assert_eq!((config.width, config.height), (10, 8));
+        assert_eq!(config.driver, DriverKind::Terminal);
+        assert_eq!(config.color_order, ColorOrder::Grb);
+        assert_eq!(config.max_fps, 60.5);
+        assert!(config.profile_alloc);
+        assert_eq!(config.forward_addrs.len(), 2);
+    }
+
+    #[test]
+    fn config_file_errors_name_the_key() {
+        let mut config = Config::defaults();
+        let err = apply_config_file(&mut config, "ledcount = 600\n").unwrap_err();
+        assert!(err.contains("ledcount"), "{}", err);
+        let err = apply_config_file(&mut config, "width = \"wide\"\n").unwrap_err();
+        assert!(err.contains("'width'"), "{}", err);
+    }
+
+    #[test]
+    fn cli_flags_override_the_config_file() {
+        let path = std::env::temp_dir().join("legrid-config-test.toml");
+        std::fs::write(&path, "width = 10\nheight = 8\n").unwrap();
+        let config = parse_args(&args(&[
+            "--config",
+            path.to_str().unwrap(),
+            "--width",
+            "50",
+        ]))
+        .unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!((config.width, config.height), (50, 8));
+    }
+
+    #[test]
+    fn state_dir_resolves_relative_state_paths() {
+        let dir = std::env::temp_dir().join("legrid-state-test");
+        let config = parse_args(&args(&[
+            "--state-dir",
+            dir.to_str().unwrap(),
+            "--splash",
+            "splash.bin",
+            "--record",
+            "/var/tmp/session.rec",
+        ]))
+        .unwrap();
+        assert_eq!(config.splash_path.as_deref(), Some(dir.join("splash.bin").as_path()));
+        // Absolute paths are left where the operator put them.
+        assert_eq!(
+            config.record_path.as_deref(),
+            Some(std::path::Path::new("/var/tmp/session.rec"))
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn hex_color_parses_with_and_without_hash() {
+        assert_eq!(parse_hex_color("#ff8040"), Some(Pixel { r: 255, g: 128, b: 64 }));
+        assert_eq!(parse_hex_color("ff8040"), Some(Pixel { r: 255, g: 128, b: 64 }));
+        assert_eq!(parse_hex_color("zzz"), None);
+    }
+}
@@ -0,0 +1,631 @@
+//! Content decoding for watch mode and thumbnails.
+//!
+//! Watch mode displays image files dropped into a directory. Decoders are
+//! kept dependency-free: PPM (P6), uncompressed BMP, and GIF (including
+//! animations). Anything else is skipped with a log line.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::frame::{frame_payload_rgb, Pixel};
+use crate::png::encode_png;
+use crate::record::read_recording;
+
+/// Extensions watch mode will pick up from the content directory.
+pub const CONTENT_EXTENSIONS: &[&str] = &["gif", "ppm", "bmp"];
+
+/// Longest side of a generated thumbnail. Small grids get scaled up so a
+/// 25x24 panel preview is still visible in a management UI.
+pub const THUMB_MAX_DIM: usize = 96;
+
+/// Largest pixel count a decoder will allocate for. Dimensions come
+/// straight out of untrusted file headers; a 65535x65535 GIF screen is a
+/// 12 GB canvas request, not an image anyone is displaying on a grid.
+const MAX_IMAGE_PIXELS: usize = 1 << 24;
+
+/// One displayable frame of a content file, already resampled to the grid.
+pub struct ContentFrame {
+    pub pixels: Vec<Pixel>,
+    pub delay: Duration,
+}
+
+/// Nearest-neighbour resample from an RGB buffer to the grid size.
+pub fn resample_to_grid(src: &[u8], src_w: usize, src_h: usize, dst_w: usize, dst_h: usize) -> Vec<Pixel> {
+    // A zero-size or short source cannot be sampled. The decoders reject
+    // such images, but this runs in the controller main loop on files an
+    // operator dropped in a directory — a black frame beats a panic.
+    let needed = src_w.checked_mul(src_h).and_then(|n| n.checked_mul(3));
+    if needed.is_none_or(|n| n == 0 || src.len() < n) {
+        return vec![Pixel::BLACK; dst_w * dst_h];
+    }
+    let mut out = Vec::with_capacity(dst_w * dst_h);
+    for y in 0..dst_h {
+        for x in 0..dst_w {
+            let sx = x * src_w / dst_w.max(1);
+            let sy = y * src_h / dst_h.max(1);
+            let idx = (sy * src_w + sx) * 3;
+            out.push(Pixel {
+                r: src[idx],
+                g: src[idx + 1],
+                b: src[idx + 2],
+            });
+        }
+    }
+    out
+}
+
+pub fn decode_ppm(data: &[u8]) -> io::Result<(usize, usize, Vec<u8>)> {
+    let bad = || io::Error::new(io::ErrorKind::InvalidData, "Invalid PPM file");
+    if !data.starts_with(b"P6") {
+        return Err(bad());
+    }
+    // Header tokens: magic, width, height, maxval; comments start with '#'.
+    let mut tokens = Vec::new();
+    let mut pos = 2;
+    while tokens.len() < 3 && pos < data.len() {
+        while pos < data.len() && (data[pos] as char).is_whitespace() {
+            pos += 1;
+        }
+        if pos < data.len() && data[pos] == b'#' {
+            while pos < data.len() && data[pos] != b'\n' {
+                pos += 1;
+            }
+            continue;
+        }
+        let start = pos;
+        while pos < data.len() && !(data[pos] as char).is_whitespace() {
+            pos += 1;
+        }
+        tokens.push(std::str::from_utf8(&data[start..pos]).map_err(|_| bad())?.to_string());
+    }
+    if tokens.len() < 3 {
+        return Err(bad());
+    }
+    let width: usize = tokens[0].parse().map_err(|_| bad())?;
+    let height: usize = tokens[1].parse().map_err(|_| bad())?;
+    if width == 0 || height == 0 {
+        return Err(bad());
+    }
+    pos += 1; // single whitespace after maxval
+    let expected = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(3))
+        .ok_or_else(bad)?;
+    if pos.checked_add(expected).is_none_or(|end| data.len() < end) {
+        return Err(bad());
+    }
+    Ok((width, height, data[pos..pos + expected].to_vec()))
+}
+
+pub fn decode_bmp(data: &[u8]) -> io::Result<(usize, usize, Vec<u8>)> {
+    let bad = || io::Error::new(io::ErrorKind::InvalidData, "Invalid BMP file");
+    if data.len() < 54 || !data.starts_with(b"BM") {
+        return Err(bad());
+    }
+    let pixel_offset = u32::from_le_bytes([data[10], data[11], data[12], data[13]]) as usize;
+    let width = i32::from_le_bytes([data[18], data[19], data[20], data[21]]);
+    let height = i32::from_le_bytes([data[22], data[23], data[24], data[25]]);
+    let bpp = u16::from_le_bytes([data[28], data[29]]);
+    let compression = u32::from_le_bytes([data[30], data[31], data[32], data[33]]);
+    if width <= 0 || height == 0 || compression != 0 || !(bpp == 24 || bpp == 32) {
+        return Err(bad());
+    }
+    let (width, flipped) = (width as usize, height > 0);
+    let height = height.unsigned_abs() as usize;
+    let bytes_per_px = bpp as usize / 8;
+    let row_stride = width
+        .checked_mul(bytes_per_px)
+        .map(|n| n.div_ceil(4) * 4)
+        .ok_or_else(bad)?;
+    let needed = row_stride
+        .checked_mul(height)
+        .and_then(|n| n.checked_add(pixel_offset))
+        .ok_or_else(bad)?;
+    if data.len() < needed {
+        return Err(bad());
+    }
+    let mut rgb = vec![0u8; width * height * 3];
+    for y in 0..height {
+        let src_row = if flipped { height - 1 - y } else { y };
+        let row = &data[pixel_offset + src_row * row_stride..];
+        for x in 0..width {
+            let px = &row[x * bytes_per_px..];
+            let dst = (y * width + x) * 3;
+            // BMP stores BGR(A)
+            rgb[dst] = px[2];
+            rgb[dst + 1] = px[1];
+            rgb[dst + 2] = px[0];
+        }
+    }
+    Ok((width, height, rgb))
+}
+
+/// One decoded GIF frame: the flattened RGB canvas and its display delay.
+pub type GifFrame = (Vec<u8>, Duration);
+
+/// Minimal GIF decoder: logical screen + global/local color tables, LZW
+/// image data, graphics-control delays, interlacing, and transparency.
+pub fn decode_gif(data: &[u8]) -> io::Result<(usize, usize, Vec<GifFrame>)> {
+    let bad = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid GIF: {}", msg));
+    if data.len() < 13 || !(data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")) {
+        return Err(bad("signature"));
+    }
+    let screen_w = u16::from_le_bytes([data[6], data[7]]) as usize;
+    let screen_h = u16::from_le_bytes([data[8], data[9]]) as usize;
+    // A zero-size screen yields frames nothing can sample, and the canvas
+    // below is allocated from these header fields alone — bound them first.
+    if screen_w == 0 || screen_h == 0 || screen_w * screen_h > MAX_IMAGE_PIXELS {
+        return Err(bad("screen dimensions"));
+    }
+    let flags = data[10];
+    let mut pos = 13;
+
+    let mut global_palette: Vec<[u8; 3]> = Vec::new();
+    if flags & 0x80 != 0 {
+        let size = 2usize << (flags & 0x07);
+        for i in 0..size {
+            let p = pos + i * 3;
+            if p + 2 >= data.len() {
+                return Err(bad("global color table"));
+            }
+            global_palette.push([data[p], data[p + 1], data[p + 2]]);
+        }
+        pos += size * 3;
+    }
+
+    let mut canvas = vec![0u8; screen_w * screen_h * 3];
+    let mut frames = Vec::new();
+    let mut delay = Duration::from_millis(100);
+    let mut transparent: Option<u8> = None;
+    let mut disposal = 0u8;
+
+    while pos < data.len() {
+        match data[pos] {
+            0x3B => break, // trailer
+            0x21 => {
+                // Extension block
+                if pos + 1 >= data.len() {
+                    return Err(bad("extension"));
+                }
+                let label = data[pos + 1];
+                pos += 2;
+                // Graphics control extension carries delay + transparency.
+                if label == 0xF9 && pos + 5 < data.len() && data[pos] == 4 {
+                    let packed = data[pos + 1];
+                    disposal = (packed >> 2) & 0x07;
+                    let centis = u16::from_le_bytes([data[pos + 2], data[pos + 3]]);
+                    delay = Duration::from_millis((centis.max(2) as u64) * 10);
+                    transparent = if packed & 1 != 0 { Some(data[pos + 4]) } else { None };
+                }
+                // Skip sub-blocks regardless of label.
+                while pos < data.len() && data[pos] != 0 {
+                    pos += data[pos] as usize + 1;
+                }
+                pos += 1;
+            }
+            0x2C => {
+                // Image descriptor
+                if pos + 10 > data.len() {
+                    return Err(bad("image descriptor"));
+                }
+                let left = u16::from_le_bytes([data[pos + 1], data[pos + 2]]) as usize;
+                let top = u16::from_le_bytes([data[pos + 3], data[pos + 4]]) as usize;
+                let img_w = u16::from_le_bytes([data[pos + 5], data[pos + 6]]) as usize;
+                let img_h = u16::from_le_bytes([data[pos + 7], data[pos + 8]]) as usize;
+                let img_flags = data[pos + 9];
+                pos += 10;
+                // lzw_decode preallocates the expected output; keep a
+                // hostile descriptor from turning that into gigabytes.
+                if img_w == 0 || img_h == 0 || img_w * img_h > MAX_IMAGE_PIXELS {
+                    return Err(bad("image dimensions"));
+                }
+
+                let mut palette = global_palette.clone();
+                if img_flags & 0x80 != 0 {
+                    let size = 2usize << (img_flags & 0x07);
+                    palette.clear();
+                    for i in 0..size {
+                        let p = pos + i * 3;
+                        if p + 2 >= data.len() {
+                            return Err(bad("local color table"));
+                        }
+                        palette.push([data[p], data[p + 1], data[p + 2]]);
+                    }
+                    pos += size * 3;
+                }
+                if palette.is_empty() {
+                    return Err(bad("no color table"));
+                }
+
+                // Collect LZW data from sub-blocks.
+                if pos >= data.len() {
+                    return Err(bad("lzw code size"));
+                }
+                let min_code_size = data[pos];
+                pos += 1;
+                let mut lzw = Vec::new();
+                while pos < data.len() && data[pos] != 0 {
+                    let len = data[pos] as usize;
+                    if pos + 1 + len > data.len() {
+                        return Err(bad("sub-block"));
+                    }
+                    lzw.extend_from_slice(&data[pos + 1..pos + 1 + len]);
+                    pos += len + 1;
+                }
+                pos += 1;
+
+                let indices =
+                    lzw_decode(&lzw, min_code_size, img_w * img_h).ok_or_else(|| bad("lzw stream"))?;
+
+                // Deinterlace row order if needed.
+                let row_order: Vec<usize> = if img_flags & 0x40 != 0 {
+                    let mut order = Vec::with_capacity(img_h);
+                    for (start, stride) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+                        let mut y = start;
+                        while y < img_h {
+                            order.push(y);
+                            y += stride;
+                        }
+                    }
+                    order
+                } else {
+                    (0..img_h).collect()
+                };
+
+                if disposal == 2 {
+                    canvas.fill(0);
+                }
+                for (src_y, &dst_y) in row_order.iter().enumerate() {
+                    for x in 0..img_w {
+                        let idx = indices[src_y * img_w + x];
+                        if Some(idx) == transparent {
+                            continue;
+                        }
+                        let (cx, cy) = (left + x, top + dst_y);
+                        if cx >= screen_w || cy >= screen_h {
+                            continue;
+                        }
+                        let color = palette.get(idx as usize).copied().unwrap_or([0, 0, 0]);
+                        let dst = (cy * screen_w + cx) * 3;
+                        canvas[dst..dst + 3].copy_from_slice(&color);
+                    }
+                }
+                frames.push((canvas.clone(), delay));
+            }
+            _ => return Err(bad("unknown block")),
+        }
+    }
+
+    if frames.is_empty() {
+        return Err(bad("no frames"));
+    }
+    Ok((screen_w, screen_h, frames))
+}
+
+/// Standard GIF LZW decompression.
+fn lzw_decode(data: &[u8], min_code_size: u8, expected: usize) -> Option<Vec<u8>> {
+    let min_code_size = min_code_size as usize;
+    if min_code_size > 11 {
+        return None;
+    }
+    let clear_code = 1usize << min_code_size;
+    let end_code = clear_code + 1;
+
+    let mut dict: Vec<Vec<u8>> = Vec::new();
+    let reset_dict = |dict: &mut Vec<Vec<u8>>| {
+        dict.clear();
+        for i in 0..clear_code {
+            dict.push(vec![i as u8]);
+        }
+        dict.push(Vec::new()); // clear
+        dict.push(Vec::new()); // end
+    };
+    reset_dict(&mut dict);
+
+    let mut code_size = min_code_size + 1;
+    let mut out = Vec::with_capacity(expected);
+    let mut prev: Option<usize> = None;
+    let mut bit_pos = 0usize;
+
+    loop {
+        if bit_pos + code_size > data.len() * 8 {
+            break;
+        }
+        // Read `code_size` bits, LSB-first.
+        let mut code = 0usize;
+        for i in 0..code_size {
+            let p = bit_pos + i;
+            if data[p / 8] & (1 << (p % 8)) != 0 {
+                code |= 1 << i;
+            }
+        }
+        bit_pos += code_size;
+
+        if code == clear_code {
+            reset_dict(&mut dict);
+            code_size = min_code_size + 1;
+            prev = None;
+            continue;
+        }
+        if code == end_code {
+            break;
+        }
+
+        let entry = if code < dict.len() {
+            dict[code].clone()
+        } else if let Some(p) = prev {
+            // The KwKwK case.
+            let mut e = dict[p].clone();
+            e.push(dict[p][0]);
+            e
+        } else {
+            return None;
+        };
+
+        if let Some(p) = prev {
+            let mut new_entry = dict[p].clone();
+            new_entry.push(entry[0]);
+            if dict.len() < 4096 {
+                dict.push(new_entry);
+                if dict.len() == (1 << code_size) && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+        }
+
+        out.extend_from_slice(&entry);
+        prev = Some(code);
+        if out.len() >= expected {
+            break;
+        }
+    }
+
+    out.truncate(expected);
+    if out.len() == expected {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+/// Load a content file and resample it to the grid.
+pub fn load_content(path: &Path, grid_w: usize, grid_h: usize) -> io::Result<Vec<ContentFrame>> {
+    let data = std::fs::read(path)?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "gif" => {
+            let (w, h, frames) = decode_gif(&data)?;
+            Ok(frames
+                .into_iter()
+                .map(|(rgb, delay)| ContentFrame {
+                    pixels: resample_to_grid(&rgb, w, h, grid_w, grid_h),
+                    delay,
+                })
+                .collect())
+        }
+        "ppm" => {
+            let (w, h, rgb) = decode_ppm(&data)?;
+            Ok(vec![ContentFrame {
+                pixels: resample_to_grid(&rgb, w, h, grid_w, grid_h),
+                delay: Duration::ZERO,
+            }])
+        }
+        "bmp" => {
+            let (w, h, rgb) = decode_bmp(&data)?;
+            Ok(vec![ContentFrame {
+                pixels: resample_to_grid(&rgb, w, h, grid_w, grid_h),
+                delay: Duration::ZERO,
+            }])
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Unsupported content type: {}", ext),
+        )),
+    }
+}
+
+/// Scan the watch directory, newest first.
+pub fn scan_content_dir(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let mut entries = Vec::new();
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return entries;
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        let is_content = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| CONTENT_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+            .unwrap_or(false);
+        if !is_content {
+            continue;
+        }
+        if let Ok(mtime) = entry.metadata().and_then(|m| m.modified()) {
+            entries.push((path, mtime));
+        }
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+    entries
+}
+
+/// Decode a content file's first frame and encode it as a PNG thumbnail
+/// fitted to THUMB_MAX_DIM.
+pub fn thumbnail_png(path: &Path) -> io::Result<Vec<u8>> {
+    let data = std::fs::read(path)?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let (width, height, rgb) = match ext.as_str() {
+        "gif" => {
+            let (w, h, mut frames) = decode_gif(&data)?;
+            (w, h, frames.remove(0).0)
+        }
+        "ppm" => decode_ppm(&data)?,
+        "bmp" => decode_bmp(&data)?,
+        "legrid" => {
+            let records = read_recording(path)?;
+            records
+                .iter()
+                .find_map(|(_, payload)| frame_payload_rgb(payload))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Recording has no frames"))?
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("No thumbnail support for: {}", ext),
+            ))
+        }
+    };
+
+    let scale = THUMB_MAX_DIM as f64 / width.max(height).max(1) as f64;
+    let thumb_w = ((width as f64 * scale).round() as usize).max(1);
+    let thumb_h = ((height as f64 * scale).round() as usize).max(1);
+    let pixels = resample_to_grid(&rgb, width, height, thumb_w, thumb_h);
+    let rgb_out: Vec<u8> = pixels.iter().flat_map(|p| [p.r, p.g, p.b]).collect();
+    Ok(encode_png(thumb_w, thumb_h, &rgb_out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_ppm_with_comment() {
+        let mut data = b"P6\n# test\n2 1\n255\n".to_vec();
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        let (w, h, rgb) = decode_ppm(&data).unwrap();
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(rgb, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn decodes_bottom_up_bmp() {
+        // 1x2, 24bpp: bottom row written first in the file.
+        let mut data = b"BM".to_vec();
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&54u32.to_le_bytes());
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&1i32.to_le_bytes());
+        data.extend_from_slice(&2i32.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&24u16.to_le_bytes());
+        data.extend_from_slice(&[0; 24]);
+        data.extend_from_slice(&[255, 0, 0, 0]); // bottom: blue in BGR + pad
+        data.extend_from_slice(&[0, 0, 255, 0]); // top: red in BGR + pad
+        let (w, h, rgb) = decode_bmp(&data).unwrap();
+        assert_eq!((w, h), (1, 2));
+        assert_eq!(rgb, vec![255, 0, 0, 0, 0, 255]);
+    }
+
+    // Build an LZW stream the lazy way: one code per index, resetting the
+    // dictionary with a clear code whenever it would grow past the width.
+    fn lzw_plain(indices: &[u8], min_code_size: usize) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let code_size = min_code_size + 1;
+        let mut out = Vec::new();
+        let (mut cur, mut nbits) = (0usize, 0usize);
+        let emit = |code: usize, cur: &mut usize, nbits: &mut usize, out: &mut Vec<u8>| {
+            *cur |= code << *nbits;
+            *nbits += code_size;
+            while *nbits >= 8 {
+                out.push((*cur & 0xFF) as u8);
+                *cur >>= 8;
+                *nbits -= 8;
+            }
+        };
+        emit(clear, &mut cur, &mut nbits, &mut out);
+        let mut ncodes = end + 1;
+        for &idx in indices {
+            emit(idx as usize, &mut cur, &mut nbits, &mut out);
+            ncodes += 1;
+            if ncodes == (1 << code_size) {
+                emit(clear, &mut cur, &mut nbits, &mut out);
+                ncodes = end + 1;
+            }
+        }
+        emit(end, &mut cur, &mut nbits, &mut out);
+        if nbits > 0 {
+            out.push((cur & 0xFF) as u8);
+        }
+        out
+    }
+
+    fn tiny_gif() -> Vec<u8> {
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&2u16.to_le_bytes());
+        gif.extend_from_slice(&2u16.to_le_bytes());
+        gif.extend_from_slice(&[0x91, 0, 0]); // GCT, 4 entries
+        gif.extend_from_slice(&[0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255]);
+        for indices in [[1u8, 1, 2, 2], [3, 3, 0, 0]] {
+            // Graphics control: 50ms delay.
+            gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00, 5, 0, 0x00, 0x00]);
+            gif.push(0x2C);
+            gif.extend_from_slice(&[0, 0, 0, 0]);
+            gif.extend_from_slice(&2u16.to_le_bytes());
+            gif.extend_from_slice(&2u16.to_le_bytes());
+            gif.push(0);
+            let lzw = lzw_plain(&indices, 2);
+            gif.push(2);
+            gif.push(lzw.len() as u8);
+            gif.extend_from_slice(&lzw);
+            gif.push(0);
+        }
+        gif.push(0x3B);
+        gif
+    }
+
+    #[test]
+    fn decodes_two_frame_gif() {
+        let (w, h, frames) = decode_gif(&tiny_gif()).unwrap();
+        assert_eq!((w, h), (2, 2));
+        assert_eq!(frames.len(), 2);
+        // First frame: red, red, green, green.
+        assert_eq!(&frames[0].0[..6], &[255, 0, 0, 255, 0, 0]);
+        assert_eq!(&frames[0].0[6..], &[0, 255, 0, 0, 255, 0]);
+        assert_eq!(frames[0].1, Duration::from_millis(50));
+        // Second frame: blue, blue, black, black.
+        assert_eq!(&frames[1].0[..6], &[0, 0, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn rejects_degenerate_dimensions() {
+        // 0x0 PPM: valid header, no pixels to sample from.
+        assert!(decode_ppm(b"P6\n0 0\n255\n").is_err());
+        // Dimensions whose byte count overflows usize.
+        let huge = format!("P6\n{} 2\n255\n", usize::MAX);
+        assert!(decode_ppm(huge.as_bytes()).is_err());
+        // GIF with a zero-size logical screen.
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0]);
+        gif.push(0x3B);
+        assert!(decode_gif(&gif).is_err());
+    }
+
+    #[test]
+    fn resampling_an_empty_source_yields_black() {
+        // Defense in depth: even if a decoder let a 0x0 image through,
+        // resampling must not index past the source.
+        assert_eq!(resample_to_grid(&[], 0, 0, 2, 2), vec![Pixel::BLACK; 4]);
+        assert_eq!(resample_to_grid(&[1, 2, 3], 2, 2, 1, 1), vec![Pixel::BLACK]);
+    }
+
+    #[test]
+    fn resample_upscales_nearest() {
+        let src = [10, 20, 30, 40, 50, 60]; // 2x1
+        let pixels = resample_to_grid(&src, 2, 1, 4, 1);
+        assert_eq!(pixels[0], pixels[1]);
+        assert_eq!(pixels[2], pixels[3]);
+        assert_eq!(pixels[0], Pixel { r: 10, g: 20, b: 30 });
+        assert_eq!(pixels[3], Pixel { r: 40, g: 50, b: 60 });
+    }
+}
@@ -0,0 +1,2437 @@
+//! The controller proper: owns the config, the pixel buffers, the pixel
+//! pipeline, and the output driver; turns host messages into driver calls
+//! and stats reports.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::alloc_stats::AllocSnapshot;
+use crate::chain::{hops_json, ChainForwarder};
+use crate::config::{Config, CONFIG_GRACE_PERIOD};
+use crate::driver::LedDriver;
+use crate::effects::IdleEffect;
+use crate::frame::{
+    FrameParser, Pixel, MSG_TYPE_CONTROL, MSG_TYPE_FRAME, MSG_TYPE_FRAME_HSV,
+    MSG_TYPE_FRAME_PALETTE, MSG_TYPE_FRAME_ROI, MSG_TYPE_OVERLAY,
+};
+use crate::metrics::Metrics;
+use crate::overlay::OverlayMode;
+use crate::pacing::FramePacer;
+use crate::pipeline::{InterpolateMode, PixelPipeline};
+use crate::profiles::TimeOfDayProfile;
+use crate::thermal::ThermalThrottle;
+use crate::tiling::TileMap;
+use crate::transitions::{Transition, TransitionKind};
+use crate::transport::send_message;
+use crate::watermark::WatermarkVerifier;
+
+/// A frame counts as sparse when no more than 1/this of the chain changed
+/// since the last latch; sparse frames take the partial-update path on
+/// backends that support it.
+const SPARSE_MAX_FRACTION: usize = 8;
+
+/// First retry delay after a driver render failure; doubles per failed
+/// reinitialization up to the cap, so a flaky connector gets hammered
+/// neither by the frame rate nor forever.
+const REINIT_BACKOFF_START: Duration = Duration::from_secs(1);
+const REINIT_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// How often the controller emits its own heartbeat message, and how long
+/// host heartbeats may go quiet before the peer counts as hung. The
+/// timeout is three intervals so one lost message doesn't blank the wall.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+pub const PEER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A config apply in its grace period: the config we would roll back to,
+/// when the grace period started, and how many frames have succeeded since.
+pub struct PendingConfig {
+    previous: Config,
+    applied_at: Instant,
+    frames_ok: u64,
+}
+
+/// Latency echo for version-2 frames: the host's send timestamp together
+/// with our receive and render timestamps, plus smoothed millisecond
+/// deltas. All wall-clock microseconds, comparable because the host runs
+/// on the same machine.
+struct LatencyEcho {
+    host_us: u64,
+    receive_us: u64,
+    render_us: Option<u64>,
+    ingest_ms: f64,
+    total_ms: f64,
+}
+
+fn epoch_us() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+pub struct LEDController {
+    pub config: Config,
+    pixels: Vec<Pixel>,
+    prev_pixels: Vec<Pixel>,
+    frame_count: u64,
+    last_frame_time: Option<Instant>,
+    fps: f64,
+    /// Smoothed interval between incoming frames, used as the blend window
+    /// for interpolation.
+    pub frame_interval: f64,
+    /// Master switch and brightness, driven by the MQTT light entity. Off
+    /// blanks the panel without touching the frame stream.
+    power_on: bool,
+    master_brightness: f64,
+    pending_config: Option<PendingConfig>,
+    config_generation: u64,
+    pacer: Option<FramePacer>,
+    alloc_snapshot: AllocSnapshot,
+    pipeline: PixelPipeline,
+    /// Present with --pipeline-b: a second chain run on the same input
+    /// each frame, for before/after evaluation of pipeline changes.
+    pipeline_b: Option<PixelPipeline>,
+    /// Backend the B chain renders to (typically a simulator); without it
+    /// the B output is only compared, not displayed.
+    driver_b: Option<Box<dyn LedDriver>>,
+    /// A/B delta accumulators: frames compared, summed per-frame mean
+    /// absolute channel delta, and the worst single-channel delta seen.
+    ab_frames: u64,
+    ab_mean_sum: f64,
+    ab_max: u8,
+    /// Present when the wall is tiled from multiple panels; reorders the
+    /// logical frame into physical chain order before the driver.
+    tile_map: Option<TileMap>,
+    /// The wire buffer as last latched, for sparse-frame diffing.
+    last_wire: Vec<Pixel>,
+    /// Frames that went out through the partial-update path.
+    pub sparse_renders: u64,
+    /// Backend-advertised refresh ceiling, enforced by the pacer; None
+    /// for backends without wire timing (simulators, mock).
+    safe_fps: Option<f64>,
+    /// Consecutive frames where the sender's rate exceeded safe_fps.
+    over_rate_frames: u64,
+    /// False after a render failure until a reinitialization succeeds;
+    /// frames are dropped (not errored) while recovery is pending.
+    driver_healthy: bool,
+    /// Successful driver reinitializations over the run.
+    driver_reinits: u64,
+    /// Current reinit retry delay and when the next attempt is due.
+    reinit_backoff: Duration,
+    next_reinit: Option<Instant>,
+    /// Sheds quality in tiers when the output path can't keep up.
+    degrade: crate::degrade::DegradationGovernor,
+    /// Drops every other output frame at the half-rate tier.
+    half_rate_toggle: bool,
+    /// Present with --audio-device --audio-modulate: incoming frames get
+    /// scaled by the live audio level.
+    pub audio: Option<crate::audio::SharedAnalysis>,
+    /// Present when running with --thermal-limit.
+    thermal: Option<ThermalThrottle>,
+    /// Present when running with --profiles.
+    profiles: Option<TimeOfDayProfile>,
+    /// Present when running with --shuffle; rotates the idle takeover
+    /// through a whitelist instead of a single effect.
+    pub shuffle: Option<crate::shuffle::ShuffleSpec>,
+    /// Present when running with --dnd; gates notification delivery and
+    /// one-shot effects.
+    dnd: Option<crate::profiles::DndSchedule>,
+    /// Present when running with --alarm; replaces the content layer
+    /// with the wake ramp while firing.
+    alarm: Option<crate::alarm::SunriseAlarm>,
+    /// Latched by the GPIO input monitor on any button press; dismisses
+    /// a firing alarm, set by run().
+    pub alarm_cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    /// Wall-clock source for the time-keyed features, with RTC fallback
+    /// and unset-clock detection for headless boots without NTP.
+    clock: crate::profiles::WallClock,
+    /// Present when running with --verify-watermark.
+    verifier: Option<WatermarkVerifier>,
+    /// Present once a version-2 (timestamped) frame has arrived.
+    latency: Option<LatencyEcho>,
+    /// In-flight source transition, if a `transition` command is active.
+    transition: Option<Transition>,
+    /// Effect-modifier chains for the main layer and the overlay
+    /// stream, applied ahead of the gain and pipeline stages.
+    modifiers: Vec<Box<dyn crate::modifiers::Modifier>>,
+    overlay_modifiers: Vec<Box<dyn crate::modifiers::Modifier>>,
+    /// Second source: the last overlay-stream frame, composited on top of
+    /// the main animation until cleared.
+    overlay: Option<Vec<Pixel>>,
+    overlay_mode: OverlayMode,
+    overlay_alpha: f64,
+    /// Widget layers, composited above the overlay stream in spec
+    /// order; set by run().
+    pub widget_layers: Vec<crate::widget::SharedLayer>,
+    /// Free/busy/focus indicator, composited above the widgets until
+    /// cleared or its countdown runs out.
+    status: Option<crate::status::StatusIndicator>,
+    /// Tempo lock for the idle effects; fed by `beat` commands, read by
+    /// the run loop so the animator keeps musical time.
+    pub beat: crate::beat::BeatClock,
+    /// Per-effect favorite settings, restored when an effect is
+    /// re-selected and persisted under the state dir.
+    favorites: crate::favorites::Favorites,
+    favorites_path: Option<std::path::PathBuf>,
+    /// Named palettes extracted from artwork; color fields in control
+    /// commands resolve `name:index` references against these.
+    palettes: crate::palette::PaletteRegistry,
+    palettes_path: Option<std::path::PathBuf>,
+    /// Outbound alert webhooks, fired on threshold events like frame
+    /// loss, thermal throttle, and driver resets.
+    pub webhooks: Option<crate::webhook::WebhookNotifier>,
+    /// Previous thermal-throttle state, for firing on the engage edge.
+    thermal_throttled: bool,
+    /// Built-in scrolling text, composited above the overlay stream.
+    marquee: Option<crate::text::Marquee>,
+    /// Standalone notification icon and when it expires, composited like
+    /// the marquee.
+    icon: Option<(Vec<Pixel>, Instant)>,
+    /// Queued notifications; the active one renders through the layers
+    /// below, above everything the sender streams.
+    notifications: crate::notify::NotificationQueue,
+    notification_marquee: Option<crate::text::Marquee>,
+    notification_icon: Option<Vec<Pixel>>,
+    /// When the last outbound heartbeat went to the host.
+    last_heartbeat_sent: Option<Instant>,
+    /// Set once the host sends its first heartbeat command; only then does
+    /// a silent peer count as hung rather than merely frameless, so
+    /// senders predating the handshake keep the old idle behavior.
+    peer_heartbeats: bool,
+    /// Last message of any kind from the host.
+    peer_last_seen: Option<Instant>,
+    /// The peer is considered hung; the panel has been blanked.
+    peer_lost: bool,
+    /// Last time the live frame was autosaved for --restore-last.
+    last_autosave: Option<Instant>,
+    /// Shared with the --snapshot-http endpoint when enabled.
+    pub snapshot: Option<crate::http::SharedFrame>,
+    /// The logical buffer as last displayed, captured as the starting
+    /// point when a transition command arrives.
+    last_displayed: Vec<Pixel>,
+    /// Shared with the metrics endpoint; updated regardless so enabling
+    /// --metrics-port never changes the hot path.
+    pub metrics: std::sync::Arc<Metrics>,
+    pub driver: Box<dyn LedDriver>,
+    /// Present when this instance is the head of a controller chain.
+    pub forwarder: Option<ChainForwarder>,
+    /// Failover role name for stats ("primary" / "standby"), set by run().
+    pub failover_role: Option<&'static str>,
+    /// Standby gate: while false the output path drops frames on the
+    /// floor; the monitor thread flips it on takeover.
+    pub standby_gate: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+}
+
+impl LEDController {
+    pub fn new(mut config: Config) -> io::Result<Self> {
+        let led_count = config.led_count;
+        let config_max_fps = config.max_fps;
+        let verify_watermark = config.verify_watermark;
+        let tile_map = build_tile_map(&config)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // A channel map replaces the single-pin driver with per-segment
+        // concurrent output; a tile map implies one, derived from the
+        // per-panel pins.
+        let mut driver: Box<dyn LedDriver> = match config.channels_spec.as_deref() {
+            Some(spec) => {
+                let channels = crate::driver::parse_channels(spec, config.led_count)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                crate::log_info!("controller", "Multi-channel output: {} segments", channels.len());
+                Box::new(crate::driver::MultiChannelDriver::new(channels))
+            }
+            None => match tile_map.as_ref() {
+                Some(map) => {
+                    let channels = map.channels();
+                    crate::log_info!("controller", "Tiled output: {} panels", channels.len());
+                    Box::new(crate::driver::MultiChannelDriver::new(channels))
+                }
+                None => config.driver.create(config.width as usize, config.height as usize)?,
+            },
+        };
+        if let Some(depth) = config.bcm_depth {
+            crate::log_info!("controller", "Subframe scanning: {}-bit BCM", depth.clamp(1, 8));
+            driver = Box::new(crate::driver::BcmDriver::new(driver, depth));
+        }
+        // Backends that know their wire timing cap the output rate; a
+        // higher --max-fps would just latch late every frame.
+        let safe_fps = driver.max_safe_fps(led_count);
+        let effective_max_fps = match safe_fps {
+            Some(safe) => {
+                if config_max_fps > safe || config_max_fps == 0.0 {
+                    if config_max_fps > safe {
+                        crate::log_warn!("controller",
+                            "--max-fps {:.1} exceeds the backend's safe {:.1} FPS; capping",
+                            config_max_fps, safe
+                        );
+                    } else {
+                        crate::log_info!("controller", "Backend refresh ceiling: {:.1} FPS", safe);
+                    }
+                    safe
+                } else {
+                    config_max_fps
+                }
+            }
+            None => config_max_fps,
+        };
+        let pipeline = build_pipeline(&config)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // The B chain shares the color order and blend space so the only
+        // difference between the two outputs is the stages under test.
+        let pipeline_b = match config.pipeline_b_spec.as_deref() {
+            Some(spec) => {
+                let mut b = PixelPipeline::with_stages(config.color_order, spec)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                b.blend_space = config.blend_space;
+                crate::log_info!("controller", "A/B comparison: B pipeline [{}]", b.stage_names().join(", "));
+                Some(b)
+            }
+            None => None,
+        };
+        let driver_b = match config.ab_driver {
+            Some(kind) if pipeline_b.is_some() => {
+                Some(kind.create(config.width as usize, config.height as usize)?)
+            }
+            Some(_) => {
+                crate::log_warn!("controller", "--ab-driver without --pipeline-b has no effect; ignoring");
+                None
+            }
+            None => None,
+        };
+        let thermal = match config.thermal_spec.as_deref() {
+            Some(spec) => {
+                let (warn, max) = crate::thermal::parse_thermal(spec)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                Some(ThermalThrottle::new(warn, max))
+            }
+            None => None,
+        };
+        let profiles = match config.profiles_spec.as_deref() {
+            Some(spec) => {
+                let points = crate::profiles::parse_profiles(spec)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                Some(TimeOfDayProfile::new(points, config.utc_offset, config.location()))
+            }
+            None => None,
+        };
+        let modifiers = match config.modifiers_spec.as_deref() {
+            Some(spec) => crate::modifiers::parse_modifiers(spec)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+            None => Vec::new(),
+        };
+        let overlay_modifiers = match config.overlay_modifiers_spec.as_deref() {
+            Some(spec) => crate::modifiers::parse_modifiers(spec)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+            None => Vec::new(),
+        };
+        let shuffle = match config.shuffle_spec.as_deref() {
+            Some(spec) => Some(
+                crate::shuffle::parse_shuffle(spec)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+            ),
+            None => None,
+        };
+        let clock = crate::profiles::WallClock::new(config.rtc_device.clone());
+        let alarm = match config.alarm_spec.as_deref() {
+            Some(spec) => {
+                let (wake, ramp) = crate::alarm::parse_alarm(spec)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                Some(crate::alarm::SunriseAlarm::new(wake, ramp, config.utc_offset))
+            }
+            None => None,
+        };
+        let favorites_path = if config.no_persist {
+            None
+        } else {
+            config.state_dir.as_ref().map(|dir| dir.join("favorites"))
+        };
+        let favorites = match favorites_path.as_deref() {
+            Some(path) => crate::favorites::Favorites::load(path),
+            None => crate::favorites::Favorites::default(),
+        };
+        let palettes_path = if config.no_persist {
+            None
+        } else {
+            config.state_dir.as_ref().map(|dir| dir.join("palettes"))
+        };
+        let palettes = match palettes_path.as_deref() {
+            Some(path) => crate::palette::PaletteRegistry::load(path),
+            None => crate::palette::PaletteRegistry::default(),
+        };
+        let webhooks = match config.webhooks_spec.as_deref() {
+            Some(spec) => Some(crate::webhook::WebhookNotifier::new(
+                crate::webhook::parse_webhooks(spec)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+            )),
+            None => None,
+        };
+        // Restarting counts as re-selecting the configured effect, so a
+        // tuned panel comes back tuned.
+        let mut master_brightness = 1.0;
+        if let Some(favorite) = favorites.get(config.idle_effect.name()) {
+            if config.idle_effect != IdleEffect::None {
+                if let Some(color) = favorite.color {
+                    config.idle_color = color;
+                }
+                if let Some(value) = favorite.brightness {
+                    master_brightness = (value / 255.0).clamp(0.0, 1.0);
+                }
+                crate::log_info!(
+                    "controller",
+                    "Restored favorites for idle effect {:?}",
+                    config.idle_effect
+                );
+            }
+        }
+        let dnd = match config.dnd_spec.as_deref() {
+            Some(spec) => {
+                let windows = crate::profiles::parse_dnd(spec)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                Some(crate::profiles::DndSchedule::new(windows, config.utc_offset))
+            }
+            None => None,
+        };
+        Ok(Self {
+            config,
+            pixels: vec![Pixel::BLACK; led_count],
+            prev_pixels: vec![Pixel::BLACK; led_count],
+            frame_count: 0,
+            last_frame_time: None,
+            fps: 0.0,
+            frame_interval: 0.0,
+            power_on: true,
+            master_brightness,
+            pending_config: None,
+            config_generation: 0,
+            pacer: if effective_max_fps > 0.0 {
+                Some(FramePacer::new(effective_max_fps))
+            } else {
+                None
+            },
+            alloc_snapshot: AllocSnapshot::take(),
+            pipeline,
+            pipeline_b,
+            driver_b,
+            ab_frames: 0,
+            ab_mean_sum: 0.0,
+            ab_max: 0,
+            tile_map,
+            last_wire: Vec::new(),
+            sparse_renders: 0,
+            safe_fps,
+            over_rate_frames: 0,
+            driver_healthy: true,
+            driver_reinits: 0,
+            reinit_backoff: REINIT_BACKOFF_START,
+            next_reinit: None,
+            degrade: crate::degrade::DegradationGovernor::default(),
+            half_rate_toggle: false,
+            audio: None,
+            thermal,
+            profiles,
+            shuffle,
+            dnd,
+            alarm,
+            alarm_cancel: None,
+            clock,
+            verifier: if verify_watermark {
+                Some(WatermarkVerifier::default())
+            } else {
+                None
+            },
+            latency: None,
+            transition: None,
+            last_displayed: Vec::new(),
+            modifiers,
+            overlay_modifiers,
+            overlay: None,
+            overlay_mode: OverlayMode::Alpha,
+            overlay_alpha: 1.0,
+            widget_layers: Vec::new(),
+            status: None,
+            beat: crate::beat::BeatClock::default(),
+            favorites,
+            favorites_path,
+            palettes,
+            palettes_path,
+            webhooks,
+            thermal_throttled: false,
+            marquee: None,
+            icon: None,
+            notifications: crate::notify::NotificationQueue::default(),
+            notification_marquee: None,
+            notification_icon: None,
+            last_heartbeat_sent: None,
+            peer_heartbeats: false,
+            peer_last_seen: None,
+            peer_lost: false,
+            last_autosave: None,
+            snapshot: None,
+            metrics: Metrics::new(),
+            driver,
+            forwarder: None,
+            failover_role: None,
+            standby_gate: None,
+        })
+    }
+
+    /// Wait for the next output slot if a --max-fps cap is configured.
+    pub fn pace_output(&mut self) {
+        if let Some(pacer) = self.pacer.as_mut() {
+            pacer.pace();
+        }
+    }
+
+    pub fn led_count(&self) -> usize {
+        self.config.led_count
+    }
+
+    /// Active degradation tier; 0 is full quality.
+    pub fn degrade_tier(&self) -> u8 {
+        self.degrade.tier
+    }
+
+    /// Stage 1 of a two-stage apply: switch to the new config but remember
+    /// the old one so we can roll back if health checks fail within the
+    /// grace period.
+    pub fn apply_config(&mut self, new_config: Config) {
+        crate::log_info!("controller", "Applying new config: {:?} (grace period {:?})", new_config, CONFIG_GRACE_PERIOD);
+        let previous = std::mem::replace(&mut self.config, new_config);
+        self.resize_buffers();
+        self.rebuild_pipeline();
+        self.pending_config = Some(PendingConfig {
+            previous,
+            applied_at: Instant::now(),
+            frames_ok: 0,
+        });
+        self.config_generation += 1;
+    }
+
+    pub fn rollback_config(&mut self, reason: &str) {
+        if let Some(pending) = self.pending_config.take() {
+            crate::log_warn!("controller", "Rolling back config ({}): restoring {:?}", reason, pending.previous);
+            self.config = pending.previous;
+            self.resize_buffers();
+            self.rebuild_pipeline();
+            self.config_generation += 1;
+        }
+    }
+
+    /// Stage 2: called after every successfully processed frame and on every
+    /// tick. Commits the pending config once it has survived the grace
+    /// period with at least one healthy frame, or rolls back if the grace
+    /// period expires without one.
+    pub fn check_config_health(&mut self) {
+        let Some(pending) = self.pending_config.as_ref() else {
+            return;
+        };
+        if pending.applied_at.elapsed() < CONFIG_GRACE_PERIOD {
+            return;
+        }
+        if pending.frames_ok > 0 {
+            crate::log_info!("controller", "Config committed after {} healthy frames", pending.frames_ok);
+            self.pending_config = None;
+        } else {
+            self.rollback_config("no healthy frames within grace period");
+        }
+    }
+
+    /// Rebuild the post-processor chain after a config change. A bad stage
+    /// spec keeps the previous chain rather than failing the whole apply.
+    fn rebuild_pipeline(&mut self) {
+        match build_pipeline(&self.config) {
+            Ok(pipeline) => self.pipeline = pipeline,
+            Err(e) => {
+                crate::log_warn!("controller", "Keeping previous pipeline, new spec is invalid: {}", e);
+                self.pipeline.color_order = self.config.color_order;
+            }
+        }
+        match build_tile_map(&self.config) {
+            Ok(tile_map) => self.tile_map = tile_map,
+            Err(e) => crate::log_warn!("controller", "Keeping previous tile map, new spec is invalid: {}", e),
+        }
+    }
+
+    fn resize_buffers(&mut self) {
+        let led_count = self.config.led_count;
+        self.pixels.resize(led_count, Pixel::BLACK);
+        self.prev_pixels.resize(led_count, Pixel::BLACK);
+    }
+
+    pub fn process_frame(&mut self, frame_data: &[u8]) -> io::Result<()> {
+        let frame = FrameParser::parse(frame_data)?;
+
+        // Keep the previous frame around as the starting point for
+        // interpolation.
+        std::mem::swap(&mut self.pixels, &mut self.prev_pixels);
+        self.pixels.copy_from_slice(&self.prev_pixels);
+        // Binned mode accepts logical-resolution frames and replicates
+        // them onto the physical grid; anything else copies straight in.
+        let bin = self.config.bin;
+        if bin > 1
+            && frame.width as usize * bin == self.config.width as usize
+            && frame.height as usize * bin == self.config.height as usize
+        {
+            let scaled = crate::degrade::upscale(
+                &frame.pixels,
+                frame.width as usize,
+                frame.height as usize,
+                bin,
+            );
+            let count = scaled.len().min(self.led_count());
+            self.pixels[..count].copy_from_slice(&scaled[..count]);
+        } else {
+            let count = frame.pixels.len().min(self.led_count());
+            self.pixels[..count].copy_from_slice(&frame.pixels[..count]);
+        }
+
+        if let Some(verifier) = self.verifier.as_mut() {
+            verifier.check(&frame.pixels);
+        }
+        if let Some(host_us) = frame.host_timestamp_us {
+            let receive_us = epoch_us();
+            let instant_ms = receive_us.saturating_sub(host_us) as f64 / 1000.0;
+            let ingest_ms = match self.latency.as_ref() {
+                Some(echo) => echo.ingest_ms * 0.8 + instant_ms * 0.2,
+                None => instant_ms,
+            };
+            let total_ms = self.latency.as_ref().map(|e| e.total_ms).unwrap_or(0.0);
+            self.latency = Some(LatencyEcho {
+                host_us,
+                receive_us,
+                render_us: None,
+                ingest_ms,
+                total_ms,
+            });
+        }
+        if self.config.watermark {
+            crate::watermark::embed_watermark(&mut self.pixels, frame.frame_id);
+        }
+
+        self.note_frame_processed();
+
+        Ok(())
+    }
+
+    /// Apply a region-of-interest update onto the retained frame. The
+    /// rectangle must fit inside the configured grid; everything outside
+    /// it keeps its current pixels.
+    pub fn process_roi(&mut self, data: &[u8]) -> io::Result<()> {
+        let roi = crate::frame::RoiParser::parse(data)?;
+        let (grid_w, grid_h) = (self.config.width as usize, self.config.height as usize);
+        let (x, y) = (roi.x as usize, roi.y as usize);
+        let (w, h) = (roi.width as usize, roi.height as usize);
+        if x + w > grid_w || y + h > grid_h {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("ROI {}x{}+{}+{} exceeds the {}x{} grid", w, h, x, y, grid_w, grid_h),
+            ));
+        }
+
+        // Same prev/current handoff as a full frame, so interpolation
+        // blends the patched region from what was displayed.
+        std::mem::swap(&mut self.pixels, &mut self.prev_pixels);
+        self.pixels.copy_from_slice(&self.prev_pixels);
+        for row in 0..h {
+            let dst = (y + row) * grid_w + x;
+            let src = row * w;
+            if dst + w <= self.pixels.len() {
+                self.pixels[dst..dst + w].copy_from_slice(&roi.pixels[src..src + w]);
+            }
+        }
+
+        self.note_frame_processed();
+
+        Ok(())
+    }
+
+    /// Per-frame statistics and health bookkeeping shared by the full and
+    /// partial frame paths.
+    fn note_frame_processed(&mut self) {
+        self.frame_count += 1;
+        self.metrics
+            .frames_processed
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let now = Instant::now();
+
+        if let Some(last_time) = self.last_frame_time {
+            let delta = now.duration_since(last_time).as_secs_f64();
+            if delta > 0.0 {
+                let instant_fps = 1.0 / delta;
+                self.fps = self.fps * 0.8 + instant_fps * 0.2;
+                self.frame_interval = if self.frame_interval > 0.0 {
+                    self.frame_interval * 0.8 + delta * 0.2
+                } else {
+                    delta
+                };
+            }
+        }
+
+        self.last_frame_time = Some(now);
+        self.metrics.set_fps(self.fps);
+
+        // Warn when the sender consistently outruns the backend, instead
+        // of silently latching late. One line per sustained burst, not per
+        // frame.
+        if let Some(safe) = self.safe_fps {
+            if self.fps > safe * 1.05 {
+                self.over_rate_frames += 1;
+                if self.over_rate_frames == 90 {
+                    crate::log_warn!("controller",
+                        "Sender is pushing {:.1} FPS but the backend tops out at {:.1}; frames will latch late",
+                        self.fps, safe
+                    );
+                    self.over_rate_frames = 0;
+                }
+            } else {
+                self.over_rate_frames = 0;
+            }
+        }
+
+        // A successfully processed frame counts towards committing a
+        // pending config apply.
+        if let Some(pending) = self.pending_config.as_mut() {
+            pending.frames_ok += 1;
+        }
+        self.check_config_health();
+    }
+
+    /// Store an overlay-stream frame. Overlay frames don't touch the main
+    /// buffers or the FPS statistics; they just replace the composited
+    /// layer at the next output.
+    pub fn process_overlay(&mut self, frame_data: &[u8]) -> io::Result<()> {
+        let frame = FrameParser::parse(frame_data)?;
+        self.overlay = Some(frame.pixels);
+        Ok(())
+    }
+
+    /// Handle a control message: header [version, type=2] followed by a JSON
+    /// payload. Currently supports {"command": "apply_config", ...} with any
+    /// subset of the config fields.
+    pub fn process_control(&mut self, payload: &[u8]) -> io::Result<()> {
+        let body = std::str::from_utf8(payload)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Control payload not UTF-8"))?;
+
+        match json_str_field(body, "command").as_deref() {
+            Some("apply_config") => {
+                let mut new_config = self.config.clone();
+                if let Some(v) = json_num_field(body, "width") {
+                    new_config.width = v as u16;
+                }
+                if let Some(v) = json_num_field(body, "height") {
+                    new_config.height = v as u16;
+                }
+                if let Some(v) = json_num_field(body, "led_pin") {
+                    new_config.led_pin = v as u8;
+                }
+                if let Some(v) = json_num_field(body, "led_count") {
+                    new_config.led_count = v as usize;
+                }
+                if let Some(v) = json_num_field(body, "output_fps") {
+                    new_config.output_fps = v;
+                }
+                self.apply_config(new_config);
+                Ok(())
+            }
+            Some("rollback_config") => {
+                self.rollback_config("requested by host");
+                Ok(())
+            }
+            Some("reload") => self.reload_config_file(),
+            Some("set_power") => {
+                self.power_on = json_bool_field(body, "on").unwrap_or(true);
+                crate::log_info!("controller", "Panel power {}", if self.power_on { "on" } else { "off" });
+                Ok(())
+            }
+            Some("set_brightness") => {
+                if let Some(value) = json_num_field(body, "value") {
+                    self.master_brightness = (value / 255.0).clamp(0.0, 1.0);
+                    crate::log_info!("controller", "Master brightness {:.0}%", self.master_brightness * 100.0);
+                }
+                Ok(())
+            }
+            Some("set_stage") => {
+                let stage = json_str_field(body, "stage").ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "set_stage without a stage name")
+                })?;
+                let enabled = json_bool_field(body, "enabled").unwrap_or(true);
+                // A timeout turns a disable into a temporary bypass that
+                // re-enables itself, so a muted power limit can't be
+                // forgotten after the troubleshooting session.
+                let reenable_after = json_num_field(body, "timeout")
+                    .filter(|t| *t > 0.0)
+                    .map(std::time::Duration::from_secs_f64);
+                self.pipeline
+                    .set_stage_enabled(&stage, enabled, reenable_after)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                crate::log_info!("controller",
+                    "Stage '{}' {}{}",
+                    stage,
+                    if enabled { "enabled" } else { "disabled" },
+                    match reenable_after {
+                        Some(after) => format!(" (re-enables in {:?})", after),
+                        None => String::new(),
+                    }
+                );
+                Ok(())
+            }
+            Some("snapshot") => {
+                let frame = if self.last_displayed.is_empty() {
+                    &self.pixels
+                } else {
+                    &self.last_displayed
+                };
+                let mut rgb = Vec::with_capacity(frame.len() * 3);
+                for p in frame {
+                    rgb.extend_from_slice(&[p.r, p.g, p.b]);
+                }
+                let png = crate::png::encode_png(
+                    self.config.width as usize,
+                    self.config.height as usize,
+                    &rgb,
+                );
+                send_message(&format!(
+                    "{{\"type\":\"snapshot\",\"width\":{},\"height\":{},\"png_base64\":\"{}\"}}",
+                    self.config.width,
+                    self.config.height,
+                    crate::transport::base64_encode(&png)
+                ))
+            }
+            Some("extract_palette") => {
+                let name = json_str_field(body, "name").unwrap_or_default();
+                let file = json_str_field(body, "file").unwrap_or_default();
+                if name.is_empty() || file.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "extract_palette needs \"name\" and \"file\"",
+                    ));
+                }
+                // Files come from the upload/content dir only; no paths.
+                if file.contains('/') || file.contains("..") {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("extract_palette: invalid file name {:?}", file),
+                    ));
+                }
+                let Some(dir) = self.config.content_dir.as_ref() else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "extract_palette needs --content-dir for the image source",
+                    ));
+                };
+                let count = (json_num_field(body, "colors").unwrap_or(5.0) as usize)
+                    .clamp(1, crate::palette::MAX_COLORS);
+                // Decode at a fixed working size: plenty of samples for
+                // dominant colors without holding full-size art.
+                let frames =
+                    crate::content::load_content(&dir.join(&file), 64, 64).map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("extract_palette: {} did not decode: {}", file, e),
+                        )
+                    })?;
+                let Some(frame) = frames.first() else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("extract_palette: {} decoded to no frames", file),
+                    ));
+                };
+                let colors = crate::ambient::dominant_colors(&frame.pixels, count);
+                if colors.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("extract_palette: {} has no usable colors (all dark?)", file),
+                    ));
+                }
+                self.palettes.register(&name, colors.clone());
+                self.save_palettes();
+                let hex: Vec<String> = colors
+                    .iter()
+                    .map(|c| format!("\"#{:02x}{:02x}{:02x}\"", c.r, c.g, c.b))
+                    .collect();
+                crate::log_info!("controller",
+                    "Palette '{}' extracted from {} ({} colors)", name, file, hex.len());
+                send_message(&format!(
+                    "{{\"type\":\"palette\",\"name\":\"{}\",\"colors\":[{}]}}",
+                    name,
+                    hex.join(",")
+                ))
+            }
+            Some("save_splash") => {
+                if self.config.no_persist {
+                    crate::log_info!("controller", "save_splash ignored: persistence is disabled");
+                    return Ok(());
+                }
+                let Some(path) = self.config.splash_path.clone() else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "save_splash requires starting with --splash",
+                    ));
+                };
+                let frame = if self.last_displayed.is_empty() {
+                    &self.pixels
+                } else {
+                    &self.last_displayed
+                };
+                crate::splash::save_frame(&path, frame, self.config.width, self.config.height)?;
+                crate::log_info!("controller", "Saved splash to {}", path.display());
+                Ok(())
+            }
+            Some("marquee") => {
+                let text = json_str_field(body, "text").unwrap_or_default();
+                if text.is_empty() {
+                    self.marquee = None;
+                    crate::log_info!("controller", "Marquee cleared");
+                } else {
+                    let color = json_str_field(body, "color")
+                        .and_then(|c| self.palettes.resolve(&c))
+                        .unwrap_or(Pixel { r: 255, g: 255, b: 255 });
+                    let speed = json_num_field(body, "speed").unwrap_or(10.0);
+                    let mut marquee = crate::text::Marquee::new(&text, color, speed);
+                    if let Some(direction) = json_str_field(body, "direction") {
+                        marquee.direction =
+                            crate::text::ScrollDirection::parse(&direction).ok_or_else(|| {
+                                io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    format!("Unknown scroll direction: {}", direction),
+                                )
+                            })?;
+                    }
+                    if let Some(pause) = json_num_field(body, "pause") {
+                        marquee.pause = pause.max(0.0);
+                    }
+                    if let Some(spans) = json_str_field(body, "spans") {
+                        let spans = crate::text::parse_color_spans(&spans)
+                            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                        marquee.set_spans(spans);
+                    }
+                    crate::log_info!("controller", "Marquee: \"{}\" at {:.0} px/s", text, speed);
+                    self.marquee = Some(marquee);
+                }
+                Ok(())
+            }
+            Some("notify") => {
+                let text = json_str_field(body, "text").unwrap_or_default();
+                let icon = json_str_field(body, "icon");
+                if text.is_empty() && icon.is_none() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "notify needs text or an icon",
+                    ));
+                }
+                let notification = crate::notify::Notification {
+                    icon,
+                    text,
+                    color: json_str_field(body, "color")
+                        .and_then(|c| self.palettes.resolve(&c))
+                        .unwrap_or(Pixel { r: 255, g: 255, b: 255 }),
+                    duration_s: json_num_field(body, "duration_s").unwrap_or(5.0),
+                    priority: json_num_field(body, "priority").unwrap_or(0.0) as i64,
+                };
+                crate::log_info!("controller",
+                    "Notification queued (priority {}, {} waiting)",
+                    notification.priority,
+                    self.notifications.waiting_len() + 1
+                );
+                self.notifications.enqueue(notification);
+                Ok(())
+            }
+            Some("status") => {
+                let state = json_str_field(body, "state").ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "status without a state")
+                })?;
+                if state == "clear" {
+                    self.status = None;
+                    crate::log_info!("controller", "Status indicator cleared");
+                    return Ok(());
+                }
+                let state = crate::status::StatusState::parse(&state).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unknown status state: {} (expected free|busy|focus|clear)", state),
+                    )
+                })?;
+                let minutes = json_num_field(body, "minutes");
+                let border = json_bool_field(body, "border").unwrap_or(false);
+                crate::log_info!("controller",
+                    "Status: {}{}{}",
+                    state.name(),
+                    minutes.map(|m| format!(" for {:.0} min", m)).unwrap_or_default(),
+                    if border { " (border)" } else { "" }
+                );
+                self.status = Some(crate::status::StatusIndicator::new(state, minutes, border));
+                Ok(())
+            }
+            Some("cancel_alarm") => {
+                let secs = self.clock.now_secs();
+                match (self.alarm.as_mut(), secs) {
+                    (Some(alarm), Some(secs)) => {
+                        alarm.cancel(secs);
+                        crate::log_info!("controller", "Alarm dismissed by command");
+                    }
+                    _ => crate::log_warn!("controller", "cancel_alarm: no alarm configured"),
+                }
+                Ok(())
+            }
+            Some("beat") => {
+                let now = Instant::now();
+                if json_bool_field(body, "tap").unwrap_or(false) {
+                    self.beat.tap(now);
+                } else if json_bool_field(body, "clear").unwrap_or(false) {
+                    self.beat.clear();
+                    crate::log_info!("controller", "Beat sync cleared");
+                } else if let Some(bpm) = json_num_field(body, "bpm") {
+                    // A phase (beats since the downbeat) aligns us to an
+                    // existing grid, e.g. an Ableton Link session.
+                    let result = match json_num_field(body, "phase") {
+                        Some(phase) => self.beat.set_bpm_at_phase(bpm, phase, now),
+                        None => self.beat.set_bpm(bpm, now),
+                    };
+                    match result {
+                        Ok(()) => crate::log_info!("controller", "Beat sync locked to {:.1} BPM", bpm),
+                        Err(e) => crate::log_warn!("controller", "Ignoring beat command: {}", e),
+                    }
+                }
+                Ok(())
+            }
+            Some("clear_notifications") => {
+                self.notifications.clear();
+                self.notification_icon = None;
+                self.notification_marquee = None;
+                crate::log_info!("controller", "Notification queue cleared");
+                Ok(())
+            }
+            Some("icon") => {
+                let name = json_str_field(body, "name").unwrap_or_default();
+                if name.is_empty() {
+                    self.icon = None;
+                    crate::log_info!("controller", "Icon cleared");
+                    return Ok(());
+                }
+                // One-shot effects are dropped (not deferred) in a quiet
+                // window; a stale icon hours later would only confuse.
+                if self.dnd_active() {
+                    crate::log_info!("controller", "Icon \"{}\" suppressed by do-not-disturb", name);
+                    return Ok(());
+                }
+                let color = json_str_field(body, "color")
+                    .and_then(|c| self.palettes.resolve(&c))
+                    .unwrap_or(Pixel { r: 255, g: 255, b: 255 });
+                let duration = json_num_field(body, "duration_s").unwrap_or(5.0).max(0.1);
+                let (width, height) = (self.config.width as usize, self.config.height as usize);
+                let layer = crate::text::render_icon(&name, color, width, height)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Unknown icon: {}", name),
+                        )
+                    })?;
+                crate::log_info!("controller", "Icon \"{}\" for {:.1}s", name, duration);
+                self.icon = Some((layer, Instant::now() + Duration::from_secs_f64(duration)));
+                Ok(())
+            }
+            Some("set_overlay") => {
+                if let Some(mode) = json_str_field(body, "mode") {
+                    self.overlay_mode = OverlayMode::parse(&mode).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Unknown overlay mode: {}", mode),
+                        )
+                    })?;
+                }
+                if let Some(alpha) = json_num_field(body, "alpha") {
+                    self.overlay_alpha = alpha.clamp(0.0, 1.0);
+                }
+                crate::log_info!("controller",
+                    "Overlay {} at alpha {:.2}",
+                    self.overlay_mode.name(),
+                    self.overlay_alpha
+                );
+                Ok(())
+            }
+            Some("clear_overlay") => {
+                self.overlay = None;
+                crate::log_info!("controller", "Overlay cleared");
+                Ok(())
+            }
+            Some("transition") => {
+                let kind = json_str_field(body, "kind")
+                    .and_then(|k| TransitionKind::parse(&k))
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidData, "transition without a known kind")
+                    })?;
+                let duration_ms = json_num_field(body, "duration_ms").unwrap_or(500.0).max(0.0);
+                self.transition = Some(Transition::new(
+                    kind,
+                    self.last_displayed.clone(),
+                    std::time::Duration::from_millis(duration_ms as u64),
+                ));
+                crate::log_info!("controller", "Transition {:?} over {:.0}ms", kind, duration_ms);
+                Ok(())
+            }
+            Some("heartbeat") => {
+                if !self.peer_heartbeats {
+                    crate::log_info!("controller", "Host heartbeats enabled; hung-peer detection armed");
+                }
+                self.peer_heartbeats = true;
+                self.note_peer_activity();
+                Ok(())
+            }
+            Some("set_idle_effect") => {
+                if let Some(effect) = json_str_field(body, "effect") {
+                    self.config.idle_effect = IdleEffect::parse(&effect);
+                    // Re-selecting an effect restores its favorites;
+                    // explicit fields below override them (and become
+                    // the new favorites).
+                    if let Some(favorite) = self.favorites.get(self.config.idle_effect.name()) {
+                        if let Some(color) = favorite.color {
+                            self.config.idle_color = color;
+                        }
+                        if let Some(value) = favorite.brightness {
+                            self.master_brightness = (value / 255.0).clamp(0.0, 1.0);
+                        }
+                    }
+                }
+                let effect_name = self.config.idle_effect.name();
+                let mut tuned = false;
+                if let Some(color) = json_str_field(body, "color").and_then(|c| self.palettes.resolve(&c)) {
+                    self.config.idle_color = color;
+                    self.favorites.remember_color(effect_name, color);
+                    tuned = true;
+                }
+                if let Some(value) = json_num_field(body, "brightness") {
+                    self.master_brightness = (value / 255.0).clamp(0.0, 1.0);
+                    self.favorites.remember_brightness(effect_name, value);
+                    tuned = true;
+                }
+                if tuned && self.config.idle_effect != IdleEffect::None {
+                    self.save_favorites();
+                }
+                if let Some(timeout) = json_num_field(body, "timeout") {
+                    self.config.idle_timeout = timeout;
+                }
+                crate::log_info!("controller", "Idle effect set to {:?}", self.config.idle_effect);
+                Ok(())
+            }
+            Some(other) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown control command: {}", other),
+            )),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, "Control message without command")),
+        }
+    }
+
+    /// Write the favorites file if persistence is configured; a failed
+    /// write costs the tuning, not the session.
+    fn save_favorites(&self) {
+        let Some(path) = self.favorites_path.as_ref() else { return };
+        if let Err(e) = self.favorites.save(path) {
+            crate::log_warn!("controller", "Favorites save failed: {}", e);
+        }
+    }
+
+    /// Fire an alert webhook if any are configured.
+    pub fn fire_webhook(&mut self, event: &str, detail: &str) {
+        if let Some(notifier) = self.webhooks.as_mut() {
+            notifier.fire(event, detail);
+        }
+    }
+
+    /// Write the palettes file if persistence is configured.
+    fn save_palettes(&self) {
+        let Some(path) = self.palettes_path.as_ref() else { return };
+        if let Err(e) = self.palettes.save(path) {
+            crate::log_warn!("controller", "Palette save failed: {}", e);
+        }
+    }
+
+    /// Whether a do-not-disturb window is active right now. An unset
+    /// clock (no NTP yet, no RTC) never suppresses anything: wrongly
+    /// quiet is worse at a venue than wrongly loud at home.
+    fn dnd_active(&mut self) -> bool {
+        let Some(dnd) = self.dnd.as_ref() else {
+            return false;
+        };
+        match self.clock.now_secs() {
+            Some(secs) => dnd.active_at(secs),
+            None => false,
+        }
+    }
+
+    /// What the idle takeover should show right now: the shuffle's pick
+    /// for the current dwell slot, or the configured single effect at
+    /// full scale. With no trustworthy clock the raw system time still
+    /// advances the slots; only the per-day seeding suffers.
+    pub fn idle_pick(&mut self) -> (IdleEffect, f64) {
+        if self.shuffle.is_none() {
+            return (self.config.idle_effect, 1.0);
+        }
+        let secs = self.clock.now_secs().unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0)
+        });
+        self.shuffle.as_ref().unwrap().pick_at(secs)
+    }
+
+    /// Current wake-ramp progress, if the alarm is firing. Checks the
+    /// GPIO dismiss latch first so a button press in the dark works even
+    /// when the host is gone.
+    fn alarm_progress(&mut self) -> Option<f64> {
+        self.alarm.as_ref()?;
+        let secs = self.clock.now_secs()?;
+        if let Some(latch) = self.alarm_cancel.as_ref() {
+            if latch.swap(false, std::sync::atomic::Ordering::Relaxed) {
+                let alarm = self.alarm.as_mut().unwrap();
+                if alarm.progress_at(secs).is_some() {
+                    crate::log_info!("controller", "Alarm dismissed by button");
+                    alarm.cancel(secs);
+                }
+            }
+        }
+        self.alarm.as_ref().unwrap().progress_at(secs)
+    }
+
+    /// Any message from the host proves it is alive, heartbeat or not.
+    fn note_peer_activity(&mut self) {
+        self.peer_last_seen = Some(Instant::now());
+        if self.peer_lost {
+            crate::log_info!("controller", "Host traffic resumed after hang");
+            self.peer_lost = false;
+        }
+    }
+
+    /// Periodic liveness work, called once per main-loop tick: emit our
+    /// own heartbeat when due, and blank the panel when a heartbeating
+    /// host has been silent past [`PEER_TIMEOUT`] — stale content looks
+    /// exactly like working content, so a hung host must not leave its
+    /// last frame up indefinitely.
+    pub fn heartbeat_tick(&mut self, now: Instant) -> io::Result<()> {
+        if self
+            .last_heartbeat_sent
+            .is_none_or(|t| now.duration_since(t) >= HEARTBEAT_INTERVAL)
+        {
+            self.last_heartbeat_sent = Some(now);
+            let msg = format!(
+                "{{\"type\":\"heartbeat\",\"ts_us\":{},\"frames\":{},\"fps\":{:.2},\"driver_healthy\":{}}}",
+                epoch_us(),
+                self.frame_count,
+                self.fps,
+                self.driver_healthy
+            );
+            if let Err(e) = send_message(&msg) {
+                crate::log_warn!("controller", "Error sending heartbeat: {}", e);
+            }
+        }
+
+        if self.peer_heartbeats
+            && !self.peer_lost
+            && self
+                .peer_last_seen
+                .is_none_or(|t| now.duration_since(t) >= PEER_TIMEOUT)
+        {
+            crate::log_warn!("controller",
+                "Host silent past {:?}; blanking the panel", PEER_TIMEOUT);
+            self.peer_lost = true;
+            self.fire_webhook(
+                "frame_loss",
+                &format!("host silent past {:?}; panel blanked", PEER_TIMEOUT),
+            );
+            let black = vec![Pixel::BLACK; self.led_count()];
+            self.send_to_hardware(&black)?;
+        }
+        Ok(())
+    }
+
+    /// Hot reload: re-read the --config file and apply it over the current
+    /// config through the usual two-stage apply, so a bad edit rolls back
+    /// instead of taking the panel down. Runs between frames only.
+    pub fn reload_config_file(&mut self) -> io::Result<()> {
+        let Some(path) = self.config.config_path.clone() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Reload requested but the controller was not started with --config",
+            ));
+        };
+        let contents = std::fs::read_to_string(&path)?;
+        let mut new_config = self.config.clone();
+        crate::config::apply_config_file(&mut new_config, &contents).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", path.display(), e))
+        })?;
+        crate::log_info!("controller", "Reloading config from {}", path.display());
+        self.apply_config(new_config);
+        Ok(())
+    }
+
+    /// Autosave the live frame for --restore-last, at most every 5s.
+    /// Called from the main loop; a failed write logs and stops retrying
+    /// until the next interval.
+    pub fn maybe_autosave_frame(&mut self) {
+        if self.config.no_persist
+            || !self.config.restore_last_frame
+            || self.last_displayed.is_empty()
+        {
+            return;
+        }
+        let Some(path) = self.config.splash_path.as_ref() else {
+            return;
+        };
+        if self.last_autosave.is_some_and(|t| t.elapsed() < std::time::Duration::from_secs(5)) {
+            return;
+        }
+        self.last_autosave = Some(Instant::now());
+        let path = path.with_extension("last");
+        if let Err(e) = crate::splash::save_frame(
+            &path,
+            &self.last_displayed,
+            self.config.width,
+            self.config.height,
+        ) {
+            crate::log_warn!("controller", "Frame autosave failed: {}", e);
+        }
+    }
+
+    /// Power is failing: get the panel dark and the state on disk in the
+    /// second or two the supply capacitors buy us. The blank goes straight
+    /// to the driver — no pipeline, no transition — because a stuck bright
+    /// frame is exactly what drains the remaining charge fastest.
+    pub fn safe_shutdown(&mut self) -> io::Result<()> {
+        let black = vec![Pixel::BLACK; self.led_count()];
+        let (width, height) = (self.config.width as usize, self.config.height as usize);
+        self.driver.render(&black, width, height)?;
+
+        if self.config.restore_last_frame && !self.config.no_persist && !self.last_displayed.is_empty()
+        {
+            if let Some(path) = self.config.splash_path.as_ref() {
+                let path = path.with_extension("last");
+                if let Err(e) = crate::splash::save_frame(
+                    &path,
+                    &self.last_displayed,
+                    self.config.width,
+                    self.config.height,
+                ) {
+                    crate::log_warn!("controller", "Frame save during shutdown failed: {}", e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Blend between the previous and current frame. t is clamped to [0, 1];
+    /// 0 shows the previous frame, 1 the current one.
+    pub fn interpolated_pixels(&self, mode: InterpolateMode, t: f64) -> Vec<Pixel> {
+        self.pipeline.blend(&self.prev_pixels, &self.pixels, mode, t)
+    }
+
+    pub fn send_to_hardware(&mut self, pixels: &[Pixel]) -> io::Result<()> {
+        // An un-promoted standby stays dark: its data line is switched away
+        // anyway, and latching would fight the primary's output.
+        if let Some(gate) = self.standby_gate.as_ref() {
+            if !gate.load(std::sync::atomic::Ordering::Relaxed) {
+                return Ok(());
+            }
+        }
+        // Driver recovery: while unhealthy, drop frames and retry the
+        // reinitialization on the backoff schedule instead of erroring on
+        // every frame.
+        if !self.driver_healthy {
+            if self.next_reinit.is_some_and(|at| Instant::now() < at) {
+                return Ok(());
+            }
+            match self.driver.reinit() {
+                Ok(()) => {
+                    crate::log_info!("controller", "Driver '{}' reinitialized", self.driver.name());
+                    self.driver_healthy = true;
+                    self.driver_reinits += 1;
+                    self.next_reinit = None;
+                    let detail = format!(
+                        "driver '{}' reinitialized after a render failure ({} over the run)",
+                        self.driver.name(),
+                        self.driver_reinits
+                    );
+                    self.fire_webhook("driver_reset", &detail);
+                    // Warm spare: re-latch the last good wire frame right
+                    // away, so the reset is invisible instead of leaving
+                    // whatever the strip happened to hold while it was
+                    // down. Failing here just re-enters recovery below.
+                    if !self.last_wire.is_empty() {
+                        let restore = self.last_wire.clone();
+                        let (width, height) =
+                            (self.config.width as usize, self.config.height as usize);
+                        if let Err(e) = self.driver.render(&restore, width, height) {
+                            crate::log_warn!("controller", "Re-latch after reinit failed: {}", e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    crate::log_warn!("controller",
+                        "Driver reinit failed ({}); next attempt in {:?}",
+                        e, self.reinit_backoff
+                    );
+                    self.next_reinit = Some(Instant::now() + self.reinit_backoff);
+                    self.reinit_backoff = (self.reinit_backoff * 2).min(REINIT_BACKOFF_MAX);
+                    return Ok(());
+                }
+            }
+        }
+        let frame_start = Instant::now();
+        // Half-rate tier: drop every other output frame.
+        if self.degrade.tier >= 3 {
+            self.half_rate_toggle = !self.half_rate_toggle;
+            if self.half_rate_toggle {
+                return Ok(());
+            }
+        }
+        let (width, height) = (self.config.width as usize, self.config.height as usize);
+        // A source transition mixes the incoming frame against the buffer
+        // captured when the command arrived, upstream of the gain and the
+        // pipeline so the blend sees raw frame data.
+        let blended: Vec<Pixel>;
+        let pixels = match self.transition.as_ref() {
+            Some(transition) if !transition.finished() => {
+                blended = transition.apply(pixels, width, self.pipeline.blend_space);
+                &blended[..]
+            }
+            Some(_) => {
+                crate::log_info!("controller", "Transition finished");
+                self.transition = None;
+                pixels
+            }
+            None => pixels,
+        };
+        // The main modifier chain transforms the content before anything
+        // composites on top, so text and widgets stay readable.
+        let modified: Vec<Pixel>;
+        let pixels = if self.modifiers.is_empty() {
+            pixels
+        } else {
+            let mut frame = pixels.to_vec();
+            crate::modifiers::apply_chain(&mut self.modifiers, &mut frame, width, height);
+            modified = frame;
+            &modified[..]
+        };
+        // A firing sunrise alarm replaces the content layer outright:
+        // in a bedroom the ramp is the point, whatever is streaming.
+        let alarm_layer: Vec<Pixel>;
+        let pixels = match self.alarm_progress() {
+            Some(progress) => {
+                alarm_layer = crate::alarm::ramp_frame(progress, pixels.len());
+                &alarm_layer[..]
+            }
+            None => pixels,
+        };
+        self.last_displayed = pixels.to_vec();
+        if let Some(slot) = self.snapshot.as_ref() {
+            *slot.lock().unwrap() = (self.config.width, self.config.height, pixels.to_vec());
+        }
+        // The overlay stream sits on top of the main layer (and above any
+        // transition, which only crossfades the layer underneath it),
+        // with its own modifier chain.
+        let modified_overlay: Option<Vec<Pixel>> = if self.overlay_modifiers.is_empty() {
+            None
+        } else {
+            self.overlay.clone().map(|mut frame| {
+                crate::modifiers::apply_chain(&mut self.overlay_modifiers, &mut frame, width, height);
+                frame
+            })
+        };
+        let composited: Vec<Pixel>;
+        let pixels = match modified_overlay.as_deref().or(self.overlay.as_deref()) {
+            Some(overlay) => {
+                composited =
+                    crate::overlay::composite(pixels, overlay, self.overlay_mode, self.overlay_alpha);
+                &composited[..]
+            }
+            None => pixels,
+        };
+        // Widget layers sit above the overlay stream; black is
+        // transparent, so only the drawn pixels land.
+        let with_widgets: Vec<Pixel>;
+        let pixels = if self.widget_layers.is_empty() {
+            pixels
+        } else {
+            let mut layer = pixels.to_vec();
+            for slot in &self.widget_layers {
+                if let Some(widget_layer) = slot.lock().unwrap().as_ref() {
+                    layer = crate::overlay::composite(&layer, widget_layer, OverlayMode::Alpha, 1.0);
+                }
+            }
+            with_widgets = layer;
+            &with_widgets[..]
+        };
+        // The status indicator sits above the widgets; an expired
+        // countdown clears it here so nobody stays "busy" forever.
+        if let Some(status) = self.status.as_ref() {
+            if status.fraction_at(Instant::now()).is_none() {
+                crate::log_info!("controller", "Status countdown finished; indicator cleared");
+                self.status = None;
+            }
+        }
+        let with_status: Vec<Pixel>;
+        let pixels = match self.status.as_ref() {
+            Some(status) => {
+                let fraction = status.fraction_at(Instant::now()).unwrap_or(0.0);
+                let layer =
+                    crate::status::render_status(status.state, fraction, status.border, width, height);
+                with_status = crate::overlay::composite(pixels, &layer, OverlayMode::Alpha, 1.0);
+                &with_status[..]
+            }
+            None => pixels,
+        };
+        // The marquee draws above everything; black is transparent, so
+        // only the glyph pixels land.
+        let with_text: Vec<Pixel>;
+        let pixels = match self.marquee.as_ref() {
+            Some(marquee) => {
+                let layer = marquee.render(width, height);
+                with_text = crate::overlay::composite(pixels, &layer, OverlayMode::Alpha, 1.0);
+                &with_text[..]
+            }
+            None => pixels,
+        };
+        // Notification icons sit above the marquee and clear themselves.
+        if self.icon.as_ref().is_some_and(|&(_, until)| Instant::now() >= until) {
+            self.icon = None;
+        }
+        let with_icon: Vec<Pixel>;
+        let pixels = match self.icon.as_ref() {
+            Some((layer, _)) => {
+                with_icon = crate::overlay::composite(pixels, layer, OverlayMode::Alpha, 1.0);
+                &with_icon[..]
+            }
+            None => pixels,
+        };
+        // The notification queue drives its own icon and text layers, on
+        // top of everything a sender controls directly. During a quiet
+        // window nothing new starts; queued notifications deliver once
+        // the window ends, and whatever is already showing finishes.
+        let quiet = self.dnd_active();
+        match if quiet && !self.notifications.is_active() {
+            crate::notify::QueuePoll::Idle
+        } else {
+            self.notifications.poll(Instant::now())
+        } {
+            crate::notify::QueuePoll::Start(n) => {
+                self.notification_icon = n
+                    .icon
+                    .as_deref()
+                    .and_then(|name| crate::text::render_icon(name, n.color, width, height));
+                self.notification_marquee = if n.text.is_empty() {
+                    None
+                } else {
+                    Some(crate::text::Marquee::new(&n.text, n.color, 10.0))
+                };
+            }
+            crate::notify::QueuePoll::Stop => {
+                self.notification_icon = None;
+                self.notification_marquee = None;
+            }
+            crate::notify::QueuePoll::Idle => {}
+        }
+        let with_notification: Vec<Pixel>;
+        let pixels = if self.notification_icon.is_some() || self.notification_marquee.is_some() {
+            let mut layer = pixels.to_vec();
+            if let Some(icon_layer) = self.notification_icon.as_ref() {
+                layer = crate::overlay::composite(&layer, icon_layer, OverlayMode::Alpha, 1.0);
+            }
+            if let Some(marquee) = self.notification_marquee.as_ref() {
+                let text_layer = marquee.render(width, height);
+                layer = crate::overlay::composite(&layer, &text_layer, OverlayMode::Alpha, 1.0);
+            }
+            with_notification = layer;
+            &with_notification[..]
+        } else {
+            pixels
+        };
+        // Master switch, MQTT brightness, and the thermal throttle all sit
+        // ahead of the pipeline so gamma and power limiting see what will
+        // actually be displayed.
+        let (thermal_scale, throttled) = match self.thermal.as_mut() {
+            Some(throttle) => {
+                throttle.update();
+                (throttle.scale, throttle.throttled())
+            }
+            None => (1.0, false),
+        };
+        if throttled != self.thermal_throttled {
+            self.thermal_throttled = throttled;
+            if throttled {
+                let detail = format!(
+                    "thermal throttle engaged; output scaled to {:.0}%",
+                    thermal_scale * 100.0
+                );
+                self.fire_webhook("thermal_throttle", &detail);
+            }
+        }
+        // Audio modulation: ride the level, snap to full on the beat.
+        let audio_scale = match self.audio.as_ref() {
+            Some(shared) => {
+                let analysis = shared.lock().unwrap();
+                if analysis.beat {
+                    1.0
+                } else {
+                    0.4 + 0.6 * analysis.level
+                }
+            }
+            None => 1.0,
+        };
+        let brightness = self.master_brightness * thermal_scale * audio_scale;
+        // Per-channel gain: the time-of-day profile tints as well as
+        // dims. With no trustworthy clock yet the profile sits out
+        // entirely — full brightness beats night mode at a guessed hour.
+        let mut gain = [brightness, brightness, brightness];
+        let now_secs = if self.profiles.is_some() { self.clock.now_secs() } else { None };
+        if let (Some(profile), Some(secs)) = (self.profiles.as_ref(), now_secs) {
+            let (b, white_point) = profile.current_at(secs);
+            for (g, wp) in gain.iter_mut().zip(white_point) {
+                *g *= b * wp;
+            }
+        }
+        let scaled: Vec<Pixel>;
+        let pixels = if !self.power_on {
+            scaled = vec![Pixel::BLACK; pixels.len()];
+            &scaled
+        } else if gain.iter().any(|g| *g < 1.0) {
+            scaled = pixels
+                .iter()
+                .map(|p| Pixel {
+                    r: (p.r as f64 * gain[0]).round() as u8,
+                    g: (p.g as f64 * gain[1]).round() as u8,
+                    b: (p.b as f64 * gain[2]).round() as u8,
+                })
+                .collect();
+            &scaled
+        } else {
+            pixels
+        };
+        // Resolution reduction: the configured --bin factor, bumped to at
+        // least 2x2 at the binned degradation tier.
+        let bin = if self.degrade.tier >= 4 {
+            self.config.bin.max(2)
+        } else {
+            self.config.bin
+        };
+        let binned: Vec<Pixel>;
+        let pixels = if bin > 1 {
+            let mut copy = pixels.to_vec();
+            crate::degrade::bin_average(&mut copy, width, height, bin);
+            binned = copy;
+            &binned[..]
+        } else {
+            pixels
+        };
+        let wire = self.pipeline.apply(pixels);
+        // A/B mode: run the B chain on the same input, record how far the
+        // two outputs diverge, and mirror B to its own backend if one is
+        // configured.
+        if let Some(pipeline_b) = self.pipeline_b.as_mut() {
+            let wire_b = pipeline_b.apply(pixels);
+            let (mean, max) = crate::pipeline::frame_delta(&wire, &wire_b);
+            self.ab_frames += 1;
+            self.ab_mean_sum += mean;
+            self.ab_max = self.ab_max.max(max);
+            if let Some(driver_b) = self.driver_b.as_mut() {
+                if let Err(e) = driver_b.render(&wire_b, width, height) {
+                    crate::log_warn!("controller", "B-pipeline render failed: {}", e);
+                }
+            }
+        }
+        self.metrics.set_power_scale(f64::from_bits(
+            crate::pipeline::LAST_POWER_SCALE_BITS.load(std::sync::atomic::Ordering::Relaxed),
+        ));
+        // Tiling runs last: the pipeline works in logical order, the
+        // chain wants physical order.
+        let chain = match self.tile_map.as_ref() {
+            Some(map) => map.route(&wire),
+            None => wire,
+        };
+        let started = Instant::now();
+        let result = self.latch(&chain, width, height);
+        self.metrics.record_render_latency(started.elapsed());
+        // Close out the latency measurement for the frame now on the panel.
+        if let Some(echo) = self.latency.as_mut() {
+            if echo.render_us.is_none() && result.is_ok() {
+                let render_us = epoch_us();
+                let instant_ms = render_us.saturating_sub(echo.host_us) as f64 / 1000.0;
+                echo.render_us = Some(render_us);
+                echo.total_ms = if echo.total_ms > 0.0 {
+                    echo.total_ms * 0.8 + instant_ms * 0.2
+                } else {
+                    instant_ms
+                };
+            }
+        }
+        match result.as_ref() {
+            Err(e) => {
+                self.metrics
+                    .driver_errors
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                // Enter recovery: drop frames until a reinit attempt is
+                // due, doubling the delay across repeated failure cycles.
+                crate::log_warn!("controller",
+                    "Driver {} render failed at frame {} ({}); reinitializing in {:?}",
+                    self.driver.name(), self.frame_count, e, self.reinit_backoff
+                );
+                self.driver_healthy = false;
+                self.next_reinit = Some(Instant::now() + self.reinit_backoff);
+                self.reinit_backoff = (self.reinit_backoff * 2).min(REINIT_BACKOFF_MAX);
+            }
+            Ok(()) => {
+                // A healthy latch resets the backoff ladder.
+                self.reinit_backoff = REINIT_BACKOFF_START;
+            }
+        }
+        self.last_wire = chain;
+
+        // Feed the degradation governor with this frame's busy fraction.
+        let budget = if self.frame_interval > 0.0 {
+            self.frame_interval
+        } else {
+            1.0 / 30.0
+        };
+        let busy = frame_start.elapsed().as_secs_f64() / budget;
+        let previous_tier = self.degrade.tier;
+        if let Some(tier) = self.degrade.step(busy) {
+            crate::log_warn!("controller",
+                "Degradation tier {} ({}) at load {:.2}",
+                tier,
+                crate::degrade::tier_name(tier),
+                self.degrade.load
+            );
+            // The dither stage toggles on the tier-2 boundary; missing
+            // stage is fine, not every pipeline has one.
+            if tier >= 2 && previous_tier < 2 {
+                let _ = self.pipeline.set_stage_enabled("dither", false, None);
+            } else if tier < 2 && previous_tier >= 2 {
+                let _ = self.pipeline.set_stage_enabled("dither", true, None);
+            }
+        }
+        result
+    }
+
+    /// Push a wire-ready chain buffer to the driver, via the partial-update
+    /// path when the backend supports it and the diff against the last
+    /// latch is sparse (starfields, clocks, mostly-dark content).
+    fn latch(&mut self, chain: &[Pixel], width: usize, height: usize) -> io::Result<()> {
+        if self.driver.supports_sparse() && self.last_wire.len() == chain.len() {
+            let changes: Vec<(usize, Pixel)> = chain
+                .iter()
+                .enumerate()
+                .filter(|&(i, &p)| p != self.last_wire[i])
+                .map(|(i, &p)| (i, p))
+                .collect();
+            if changes.len() <= chain.len() / SPARSE_MAX_FRACTION {
+                self.sparse_renders += 1;
+                return self.driver.render_sparse(&changes);
+            }
+        }
+        self.driver.render(chain, width, height)
+    }
+
+    pub fn send_stats(&mut self) -> io::Result<()> {
+        let (output_fps, jitter_us) = match self.pacer.as_ref() {
+            Some(p) => (p.actual_fps, p.jitter * 1_000_000.0),
+            None => (self.fps, 0.0),
+        };
+        let mut stats = format!(
+            concat!(
+                "{{\"frames_processed\":{},\"fps\":{:.1},\"hardware_type\":\"Rust\",",
+                "\"driver\":\"{}\",\"config_generation\":{},\"config_pending\":{},",
+                "\"output_fps\":{:.1},\"pacing_jitter_us\":{:.1},\"sparse_renders\":{},",
+                "\"degradation_tier\":{},\"degradation\":\"{}\",\"output_load\":{:.2},",
+                "\"driver_healthy\":{},\"driver_errors\":{},\"driver_reinits\":{}"
+            ),
+            self.frame_count, self.fps, self.driver.name(),
+            self.config_generation, self.pending_config.is_some(),
+            output_fps, jitter_us, self.sparse_renders,
+            self.degrade.tier, crate::degrade::tier_name(self.degrade.tier), self.degrade.load,
+            self.driver_healthy,
+            self.metrics.driver_errors.load(std::sync::atomic::Ordering::Relaxed),
+            self.driver_reinits);
+
+        if self.config.profile_alloc {
+            let snapshot = AllocSnapshot::take();
+            let elapsed = snapshot.taken_at.duration_since(self.alloc_snapshot.taken_at).as_secs_f64();
+            if elapsed > 0.0 {
+                let calls_per_sec = (snapshot.calls - self.alloc_snapshot.calls) as f64 / elapsed;
+                let bytes_per_sec = (snapshot.bytes - self.alloc_snapshot.bytes) as f64 / elapsed;
+                stats.push_str(&format!(
+                    ",\"alloc_calls_per_sec\":{:.0},\"alloc_bytes_per_sec\":{:.0},\"alloc_live_bytes\":{}",
+                    calls_per_sec, bytes_per_sec, AllocSnapshot::live_bytes()));
+            }
+            self.alloc_snapshot = snapshot;
+        }
+
+        // The effective config, so hot reloads are observable from the
+        // host without a round trip.
+        stats.push_str(&format!(
+            concat!(
+                ",\"config\":{{\"width\":{},\"height\":{},\"led_count\":{},",
+                "\"driver\":\"{}\",\"color_order\":\"{}\",\"max_fps\":{:.1},",
+                "\"safe_fps\":{},\"pipeline\":[{}]}}"
+            ),
+            self.config.width, self.config.height, self.config.led_count,
+            self.driver.name(), self.config.color_order.name(), self.config.max_fps,
+            self.safe_fps
+                .map(|f| format!("{:.1}", f))
+                .unwrap_or_else(|| "null".to_string()),
+            self.pipeline
+                .stage_names()
+                .iter()
+                .map(|n| format!("\"{}\"", n))
+                .collect::<Vec<_>>()
+                .join(",")));
+
+        if let Some(echo) = self.latency.as_ref() {
+            stats.push_str(&format!(
+                concat!(
+                    ",\"latency\":{{\"host_ts_us\":{},\"receive_ts_us\":{},\"render_ts_us\":{},",
+                    "\"ingest_ms\":{:.2},\"total_ms\":{:.2}}}"
+                ),
+                echo.host_us,
+                echo.receive_us,
+                echo.render_us
+                    .map(|us| us.to_string())
+                    .unwrap_or_else(|| "null".to_string()),
+                echo.ingest_ms,
+                echo.total_ms
+            ));
+        }
+
+        if let Some(throttle) = self.thermal.as_ref() {
+            stats.push_str(&format!(
+                ",\"thermal\":{{\"temperature_c\":{},\"scale\":{:.2},\"throttled\":{}}}",
+                throttle
+                    .last_temp
+                    .map(|t| format!("{:.1}", t))
+                    .unwrap_or_else(|| "null".to_string()),
+                throttle.scale,
+                throttle.throttled()
+            ));
+        }
+
+        if let Some(role) = self.failover_role {
+            let active = self
+                .standby_gate
+                .as_ref()
+                .is_none_or(|gate| gate.load(std::sync::atomic::Ordering::Relaxed));
+            stats.push_str(&format!(
+                ",\"failover\":{{\"role\":\"{}\",\"active\":{}}}",
+                role, active
+            ));
+        }
+
+        if let Some(pipeline_b) = self.pipeline_b.as_ref() {
+            let mean = if self.ab_frames > 0 {
+                self.ab_mean_sum / self.ab_frames as f64
+            } else {
+                0.0
+            };
+            stats.push_str(&format!(
+                ",\"ab\":{{\"frames\":{},\"mean_delta\":{:.3},\"max_delta\":{},\"pipeline_b\":\"{}\"}}",
+                self.ab_frames, mean, self.ab_max,
+                pipeline_b.stage_names().join(",")
+            ));
+        }
+
+        if self.dnd.is_some() {
+            let active = self.dnd_active();
+            stats.push_str(&format!(",\"dnd_active\":{}", active));
+        }
+
+        if self.notifications.is_active() || self.notifications.waiting_len() > 0
+            || self.notifications.shown > 0
+        {
+            stats.push_str(&format!(
+                ",\"notifications\":{{\"active\":{},\"queued\":{},\"shown\":{}}}",
+                self.notifications.is_active(),
+                self.notifications.waiting_len(),
+                self.notifications.shown
+            ));
+        }
+
+        if let Some(verifier) = self.verifier.as_ref() {
+            stats.push_str(&format!(
+                ",\"watermark\":{{\"verified\":{},\"invalid\":{},\"gaps\":{}}}",
+                verifier.verified, verifier.invalid, verifier.gaps
+            ));
+        }
+
+        if let Some(forwarder) = self.forwarder.as_ref() {
+            stats.push_str(&format!(",\"downstream\":{}", hops_json(&forwarder.hops())));
+        }
+
+        stats.push('}');
+        send_message(&stats)
+    }
+}
+
+/// Construct the pixel pipeline a config asks for.
+fn build_pipeline(config: &Config) -> Result<PixelPipeline, String> {
+    let mut pipeline = match config.pipeline_spec.as_deref() {
+        Some(spec) => PixelPipeline::with_stages(config.color_order, spec)?,
+        None => PixelPipeline::new(config.color_order),
+    };
+    pipeline.blend_space = config.blend_space;
+    if let Some(spec) = config.correction_spec.as_deref() {
+        let segments = crate::pipeline::parse_correction(spec)?;
+        pipeline.push_stage(Box::new(crate::pipeline::CorrectionStage { segments }));
+    }
+    Ok(pipeline)
+}
+
+/// Construct the tile map a config asks for, validated against the grid
+/// and the chain length so wiring mistakes fail at startup.
+fn build_tile_map(config: &Config) -> Result<Option<TileMap>, String> {
+    let Some(spec) = config.tiles_spec.as_deref() else {
+        return Ok(None);
+    };
+    let panels = crate::tiling::parse_tiles(spec)?;
+    let map = TileMap::build(panels, config.width as usize, config.height as usize)?;
+    if map.led_count() != config.led_count {
+        return Err(format!(
+            "tiles describe {} LEDs but led_count is {}",
+            map.led_count(),
+            config.led_count
+        ));
+    }
+    Ok(Some(map))
+}
+
+/// Machine-readable matrix of everything this binary can accept, so senders
+/// can feature-detect instead of keeping version tables per controller
+/// build. Reported over the handshake at startup and via --capabilities.
+pub fn capabilities_json(config: &Config) -> String {
+    format!(
+        concat!(
+            "{{\"type\":\"capabilities\",",
+            "\"protocol_versions\":[1,2],",
+            "\"frame_types\":[{frame},{control},{overlay},{hsv},{palette},{roi}],",
+            "\"pixel_formats\":[\"rgb888\",\"hsv888\",\"palette8\"],",
+            "\"compressions\":[\"none\"],",
+            "\"transports\":[\"stdio\"],",
+            "\"interpolation_modes\":[\"none\",\"linear\"],",
+            "\"drivers\":[{drivers}],",
+            "\"max_led_count\":{max_led_count},",
+            "\"heartbeat_interval_s\":{heartbeat},",
+            "\"width\":{width},\"height\":{height},\"led_count\":{led_count}}}"
+        ),
+        frame = MSG_TYPE_FRAME,
+        control = MSG_TYPE_CONTROL,
+        overlay = MSG_TYPE_OVERLAY,
+        hsv = MSG_TYPE_FRAME_HSV,
+        palette = MSG_TYPE_FRAME_PALETTE,
+        roi = MSG_TYPE_FRAME_ROI,
+        drivers = if cfg!(feature = "sim-window") {
+            "\"mock\",\"terminal\",\"window\""
+        } else {
+            "\"mock\",\"terminal\""
+        },
+        max_led_count = crate::frame::MAX_LED_COUNT,
+        heartbeat = HEARTBEAT_INTERVAL.as_secs(),
+        width = config.width,
+        height = config.height,
+        led_count = config.led_count,
+    )
+}
+
+/// The one-line readiness report sent on stdout once every subsystem is
+/// wired up: a config summary, the live endpoint map, and the same
+/// capability matrix as the handshake. The supervisor waits for this
+/// instead of parsing a free-text banner, which closes the race where
+/// frames arrived before the driver and endpoints existed.
+pub fn ready_json(controller: &LEDController) -> String {
+    let config = &controller.config;
+    let mut endpoints = Vec::new();
+    for (name, port) in [
+        ("upload", config.upload_port),
+        ("metrics", config.metrics_port),
+        ("snapshot", config.snapshot_port),
+    ] {
+        if let Some(port) = port {
+            endpoints.push(format!("\"{}\":{}", name, port));
+        }
+    }
+    format!(
+        concat!(
+            "{{\"type\":\"ready\",\"driver\":\"{driver}\",",
+            "\"width\":{width},\"height\":{height},\"led_count\":{led_count},",
+            "\"endpoints\":{{{endpoints}}},",
+            "\"capabilities\":{capabilities}}}"
+        ),
+        driver = controller.driver.name(),
+        width = config.width,
+        height = config.height,
+        led_count = config.led_count,
+        endpoints = endpoints.join(","),
+        capabilities = capabilities_json(config),
+    )
+}
+
+/// Route an incoming message by its type byte: pixel frames go through the
+/// frame path, control messages through the command handler. Returns
+/// whether the message was a displayable frame.
+pub fn dispatch_message(controller: &mut LEDController, data: &[u8]) -> io::Result<bool> {
+    if data.len() < 2 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Message too short"));
+    }
+    controller.note_peer_activity();
+    match data[1] {
+        MSG_TYPE_CONTROL => {
+            controller.process_control(&data[2..])?;
+            Ok(false)
+        }
+        MSG_TYPE_FRAME | MSG_TYPE_FRAME_HSV | MSG_TYPE_FRAME_PALETTE => {
+            controller.process_frame(data)?;
+            Ok(true)
+        }
+        MSG_TYPE_OVERLAY => {
+            controller.process_overlay(data)?;
+            Ok(true)
+        }
+        MSG_TYPE_FRAME_ROI => {
+            controller.process_roi(data)?;
+            Ok(true)
+        }
+        other => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown message type: {}", other),
+        )),
+    }
+}
+
+/// Minimal JSON field extraction for flat control payloads. Good enough for
+/// the simple messages the host sends; avoids pulling in a JSON library.
+pub fn json_str_field(body: &str, key: &str) -> Option<String> {
+    let needle = format!("\"{}\"", key);
+    let rest = &body[body.find(&needle)? + needle.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let rest = rest.strip_prefix('"')?;
+    Some(rest[..rest.find('"')?].to_string())
+}
+
+pub fn json_bool_field(body: &str, key: &str) -> Option<bool> {
+    let needle = format!("\"{}\"", key);
+    let rest = &body[body.find(&needle)? + needle.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    if rest.starts_with("true") {
+        Some(true)
+    } else if rest.starts_with("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+pub fn json_num_field(body: &str, key: &str) -> Option<f64> {
+    let needle = format!("\"{}\"", key);
+    let rest = &body[body.find(&needle)? + needle.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+' || c == 'e' || c == 'E'))
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_field_extraction() {
+        let body = "{\"command\": \"apply_config\", \"width\": 10, \"output_fps\": 2.5}";
+        assert_eq!(json_str_field(body, "command").as_deref(), Some("apply_config"));
+        assert_eq!(json_num_field(body, "width"), Some(10.0));
+        assert_eq!(json_num_field(body, "output_fps"), Some(2.5));
+        assert_eq!(json_num_field(body, "missing"), None);
+    }
+
+    #[test]
+    fn apply_config_control_resizes_and_health_checks() {
+        let mut config = Config::defaults();
+        config.width = 2;
+        config.height = 2;
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+
+        let body = b"{\"command\":\"apply_config\",\"led_count\":8}";
+        controller.process_control(body).unwrap();
+        assert_eq!(controller.led_count(), 8);
+        assert!(controller.pending_config.is_some());
+
+        controller.process_control(b"{\"command\":\"rollback_config\"}").unwrap();
+        assert_eq!(controller.led_count(), 4);
+        assert!(controller.pending_config.is_none());
+    }
+
+    #[test]
+    fn openapi_command_list_matches_the_handlers() {
+        let mut config = Config::defaults();
+        config.width = 2;
+        config.height = 2;
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+
+        // Every advertised command must reach a handler; missing fields
+        // may still error, but never as "unknown command".
+        for command in crate::openapi::CONTROL_COMMANDS {
+            let body = format!("{{\"command\":\"{}\"}}", command);
+            if let Err(e) = controller.process_control(body.as_bytes()) {
+                assert!(
+                    !e.to_string().contains("Unknown control command"),
+                    "openapi lists '{}' but process_control does not handle it",
+                    command
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn command_colors_resolve_palette_references() {
+        let mut config = Config::defaults();
+        config.width = 2;
+        config.height = 2;
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+        controller
+            .palettes
+            .register("brand", vec![Pixel { r: 9, g: 8, b: 7 }, Pixel { r: 1, g: 2, b: 3 }]);
+
+        controller
+            .process_control(br#"{"command":"set_idle_effect","effect":"solid","color":"brand:1"}"#)
+            .unwrap();
+        assert_eq!(controller.config.idle_color, Pixel { r: 1, g: 2, b: 3 });
+    }
+
+    #[test]
+    fn reselecting_an_effect_restores_its_favorites() {
+        let mut config = Config::defaults();
+        config.width = 2;
+        config.height = 2;
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+
+        // Tune rainbow, switch away, come back: the tuning follows.
+        controller
+            .process_control(br##"{"command":"set_idle_effect","effect":"rainbow","color":"#ff8800","brightness":128}"##)
+            .unwrap();
+        controller
+            .process_control(br##"{"command":"set_idle_effect","effect":"solid","color":"#0040ff"}"##)
+            .unwrap();
+        assert_eq!(controller.config.idle_color, Pixel { r: 0, g: 64, b: 255 });
+        controller
+            .process_control(br#"{"command":"set_idle_effect","effect":"rainbow"}"#)
+            .unwrap();
+        assert_eq!(controller.config.idle_color, Pixel { r: 255, g: 136, b: 0 });
+        assert!((controller.master_brightness - 128.0 / 255.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn beat_command_locks_and_clears_the_clock() {
+        let mut config = Config::defaults();
+        config.width = 2;
+        config.height = 2;
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+
+        controller.process_control(br#"{"command":"beat","bpm":128}"#).unwrap();
+        assert_eq!(controller.beat.bpm(), Some(128.0));
+        // Out-of-range tempi are logged and ignored, keeping the lock.
+        controller.process_control(br#"{"command":"beat","bpm":9000}"#).unwrap();
+        assert_eq!(controller.beat.bpm(), Some(128.0));
+        controller.process_control(br#"{"command":"beat","clear":true}"#).unwrap();
+        assert!(controller.beat.bpm().is_none());
+    }
+
+    #[test]
+    fn small_diffs_take_the_sparse_path() {
+        let mut config = Config::defaults();
+        config.width = 10;
+        config.height = 10;
+        config.led_count = 100;
+        let mut controller = LEDController::new(config).unwrap();
+
+        let mut pixels = vec![Pixel::BLACK; 100];
+        // First latch has no baseline, so it goes out full.
+        controller.send_to_hardware(&pixels).unwrap();
+        assert_eq!(controller.sparse_renders, 0);
+
+        // One changed pixel out of 100 is well under the threshold.
+        pixels[42] = Pixel { r: 255, g: 0, b: 0 };
+        controller.send_to_hardware(&pixels).unwrap();
+        assert_eq!(controller.sparse_renders, 1);
+
+        // A full repaint is not.
+        let white = vec![Pixel { r: 255, g: 255, b: 255 }; 100];
+        controller.send_to_hardware(&white).unwrap();
+        assert_eq!(controller.sparse_renders, 1);
+    }
+
+    #[test]
+    fn overlay_stream_composites_on_top() {
+        let mut config = Config::defaults();
+        config.width = 2;
+        config.height = 1;
+        config.led_count = 2;
+        let mut controller = LEDController::new(config).unwrap();
+
+        // Overlay frame: first pixel red, second transparent (black).
+        let mut overlay = vec![1u8, MSG_TYPE_OVERLAY, 0, 0, 0, 0, 2, 0, 1, 0];
+        overlay.extend_from_slice(&[255, 0, 0, 0, 0, 0]);
+        assert!(dispatch_message(&mut controller, &overlay).unwrap());
+
+        let base = vec![Pixel { r: 0, g: 0, b: 100 }; 2];
+        controller.send_to_hardware(&base).unwrap();
+        assert_eq!(controller.last_wire[0], Pixel { r: 255, g: 0, b: 0 });
+        assert_eq!(controller.last_wire[1], Pixel { r: 0, g: 0, b: 100 });
+
+        controller.process_control(b"{\"command\":\"clear_overlay\"}").unwrap();
+        controller.send_to_hardware(&base).unwrap();
+        assert_eq!(controller.last_wire[0], Pixel { r: 0, g: 0, b: 100 });
+    }
+
+    #[test]
+    fn transition_blends_from_the_captured_buffer() {
+        let mut config = Config::defaults();
+        config.width = 2;
+        config.height = 2;
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+
+        let white = vec![Pixel { r: 255, g: 255, b: 255 }; 4];
+        controller.send_to_hardware(&white).unwrap();
+
+        // A long crossfade right after the capture barely moves off white.
+        controller
+            .process_control(b"{\"command\":\"transition\",\"kind\":\"crossfade\",\"duration_ms\":10000}")
+            .unwrap();
+        controller.send_to_hardware(&[Pixel::BLACK; 4]).unwrap();
+        assert!(controller.last_wire[0].r > 250);
+
+        // An expired transition clears itself and shows the new source.
+        controller
+            .process_control(b"{\"command\":\"transition\",\"kind\":\"crossfade\",\"duration_ms\":1}")
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        controller.send_to_hardware(&[Pixel::BLACK; 4]).unwrap();
+        assert!(controller.transition.is_none());
+        assert_eq!(controller.last_wire[0], Pixel::BLACK);
+
+        assert!(controller
+            .process_control(b"{\"command\":\"transition\",\"kind\":\"iris\"}")
+            .is_err());
+    }
+
+    #[test]
+    fn render_failure_enters_backoff_and_recovers() {
+        struct FlakyDriver {
+            failures_left: u32,
+        }
+        impl LedDriver for FlakyDriver {
+            fn name(&self) -> &'static str {
+                "flaky"
+            }
+            fn render(&mut self, _: &[Pixel], _: usize, _: usize) -> io::Result<()> {
+                if self.failures_left > 0 {
+                    self.failures_left -= 1;
+                    return Err(io::Error::new(io::ErrorKind::BrokenPipe, "device gone"));
+                }
+                Ok(())
+            }
+        }
+
+        let mut config = Config::defaults();
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+        controller.driver = Box::new(FlakyDriver { failures_left: 1 });
+
+        let frame = vec![Pixel::BLACK; 4];
+        assert!(controller.send_to_hardware(&frame).is_err());
+        assert!(!controller.driver_healthy);
+
+        // While the retry timer runs, frames are dropped without erroring.
+        assert!(controller.send_to_hardware(&frame).is_ok());
+        assert!(!controller.driver_healthy);
+
+        // Once the retry is due, the reinit succeeds and output resumes.
+        controller.next_reinit = Some(Instant::now());
+        assert!(controller.send_to_hardware(&frame).is_ok());
+        assert!(controller.driver_healthy);
+        assert_eq!(controller.driver_reinits, 1);
+        assert_eq!(controller.reinit_backoff, REINIT_BACKOFF_START);
+    }
+
+    #[test]
+    fn recovery_relatches_the_last_good_frame() {
+        use std::sync::{Arc, Mutex};
+
+        struct CapturingDriver {
+            calls: u32,
+            fail_on: u32,
+            latched: Arc<Mutex<Vec<Vec<Pixel>>>>,
+        }
+        impl LedDriver for CapturingDriver {
+            fn name(&self) -> &'static str {
+                "capturing"
+            }
+            fn render(&mut self, pixels: &[Pixel], _: usize, _: usize) -> io::Result<()> {
+                self.calls += 1;
+                if self.calls == self.fail_on {
+                    return Err(io::Error::new(io::ErrorKind::BrokenPipe, "device gone"));
+                }
+                self.latched.lock().unwrap().push(pixels.to_vec());
+                Ok(())
+            }
+        }
+
+        let mut config = Config::defaults();
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+        let latched = Arc::new(Mutex::new(Vec::new()));
+        controller.driver = Box::new(CapturingDriver {
+            calls: 0,
+            fail_on: 2,
+            latched: latched.clone(),
+        });
+
+        let red = vec![Pixel { r: 200, g: 0, b: 0 }; 4];
+        let blue = vec![Pixel { r: 0, g: 0, b: 200 }; 4];
+        controller.send_to_hardware(&red).unwrap();
+        assert!(controller.send_to_hardware(&red).is_err());
+
+        // The recovery latch replays the last good frame before the new
+        // one, so the strip never shows its power-on garbage.
+        controller.next_reinit = Some(Instant::now());
+        controller.send_to_hardware(&blue).unwrap();
+        let frames = latched.lock().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1], frames[0]);
+        assert_eq!(frames[2][0], Pixel { r: 0, g: 0, b: 200 });
+    }
+
+    #[test]
+    fn roi_update_patches_the_retained_buffer() {
+        let mut config = Config::defaults();
+        config.width = 4;
+        config.height = 2;
+        config.led_count = 8;
+        let mut controller = LEDController::new(config).unwrap();
+
+        // Full blue frame first, then a 2x1 red patch at (1, 1).
+        let mut frame = vec![1u8, MSG_TYPE_FRAME, 0, 0, 0, 0, 4, 0, 2, 0];
+        frame.extend_from_slice(&[0, 0, 50].repeat(8));
+        assert!(dispatch_message(&mut controller, &frame).unwrap());
+
+        let mut roi = vec![1u8, MSG_TYPE_FRAME_ROI, 1, 0, 0, 0];
+        roi.extend_from_slice(&1u16.to_le_bytes());
+        roi.extend_from_slice(&1u16.to_le_bytes());
+        roi.extend_from_slice(&2u16.to_le_bytes());
+        roi.extend_from_slice(&1u16.to_le_bytes());
+        roi.extend_from_slice(&[200, 0, 0, 200, 0, 0]);
+        assert!(dispatch_message(&mut controller, &roi).unwrap());
+
+        assert_eq!(controller.pixels[4], Pixel { r: 0, g: 0, b: 50 });
+        assert_eq!(controller.pixels[5], Pixel { r: 200, g: 0, b: 0 });
+        assert_eq!(controller.pixels[6], Pixel { r: 200, g: 0, b: 0 });
+        assert_eq!(controller.pixels[7], Pixel { r: 0, g: 0, b: 50 });
+        assert_eq!(controller.frame_count, 2);
+
+        // A rectangle that hangs off the grid is rejected.
+        roi[6] = 3;
+        assert!(dispatch_message(&mut controller, &roi).is_err());
+    }
+
+    #[test]
+    fn ready_message_lists_endpoints_and_capabilities() {
+        let mut config = Config::defaults();
+        config.led_count = 4;
+        config.metrics_port = Some(9100);
+        let controller = LEDController::new(config).unwrap();
+        let ready = ready_json(&controller);
+        assert!(ready.starts_with("{\"type\":\"ready\""), "{}", ready);
+        assert!(ready.contains("\"driver\":\"mock\""));
+        assert!(ready.contains("\"metrics\":9100"));
+        assert!(!ready.contains("\"upload\":"));
+        assert!(ready.contains("\"capabilities\":{\"type\":\"capabilities\""));
+    }
+
+    #[test]
+    fn hung_heartbeating_host_blanks_the_panel() {
+        let mut config = Config::defaults();
+        config.led_count = 4;
+        let mut controller = LEDController::new(config).unwrap();
+        let start = Instant::now();
+
+        // Senders that never heartbeat keep the old idle-only behavior:
+        // silence alone does not blank.
+        controller.heartbeat_tick(start + PEER_TIMEOUT * 2).unwrap();
+        assert!(!controller.peer_lost);
+
+        // Once the host heartbeats, going quiet past the timeout blanks.
+        controller.process_control(br#"{"command":"heartbeat"}"#).unwrap();
+        controller.heartbeat_tick(start + PEER_TIMEOUT * 2).unwrap();
+        assert!(controller.peer_lost);
+        assert!(controller.last_wire.iter().all(|p| *p == Pixel::BLACK));
+
+        // Any traffic at all marks the peer alive again.
+        controller.process_control(br#"{"command":"heartbeat"}"#).unwrap();
+        assert!(!controller.peer_lost);
+    }
+
+    #[test]
+    fn dnd_defers_notifications_and_drops_icons() {
+        let mut config = Config::defaults();
+        config.width = 8;
+        config.height = 8;
+        config.led_count = 64;
+        // Two windows that together cover the whole day, so the quiet
+        // period is always active for the test.
+        config.dnd_spec = Some("00:00-23:59;23:59-00:01".to_string());
+        let mut controller = LEDController::new(config).unwrap();
+
+        controller
+            .process_control(br#"{"command":"notify","text":"hi"}"#)
+            .unwrap();
+        let frame = vec![Pixel::BLACK; 64];
+        controller.send_to_hardware(&frame).unwrap();
+        assert!(controller.notification_marquee.is_none());
+        assert_eq!(controller.notifications.waiting_len(), 1);
+
+        // One-shot icons are dropped outright, not queued.
+        controller
+            .process_control(br#"{"command":"icon","name":"heart"}"#)
+            .unwrap();
+        assert!(controller.icon.is_none());
+    }
+
+    #[test]
+    fn ab_mode_tracks_how_far_the_pipelines_diverge() {
+        let mut config = Config::defaults();
+        config.led_count = 4;
+        config.pipeline_b_spec = Some("brightness:0.5".to_string());
+        let mut controller = LEDController::new(config).unwrap();
+
+        // A is a pass-through, B halves: every channel differs by 100.
+        let frame = vec![Pixel { r: 200, g: 200, b: 200 }; 4];
+        controller.send_to_hardware(&frame).unwrap();
+        assert_eq!(controller.ab_frames, 1);
+        assert!((controller.ab_mean_sum - 100.0).abs() < 1e-9);
+        assert_eq!(controller.ab_max, 100);
+        // The displayed output is still the A chain's.
+        assert_eq!(controller.last_wire[0], Pixel { r: 200, g: 200, b: 200 });
+    }
+
+    #[test]
+    fn dispatch_routes_by_type_byte() {
+        let mut config = Config::defaults();
+        config.led_count = 1;
+        let mut controller = LEDController::new(config).unwrap();
+
+        let mut frame = vec![1u8, MSG_TYPE_FRAME, 0, 0, 0, 0, 1, 0, 1, 0];
+        frame.extend_from_slice(&[9, 8, 7]);
+        assert!(dispatch_message(&mut controller, &frame).unwrap());
+        assert_eq!(controller.pixels[0], Pixel { r: 9, g: 8, b: 7 });
+
+        assert!(dispatch_message(&mut controller, &[1, 99]).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Chain-length estimation from supply current.
+//!
+//! With a current sensor on the PSU rail (any hwmon driver exposing
+//! `curr1_input`, e.g. an INA219), lighting progressively longer prefixes
+//! of the chain steps the current by a fixed amount per connected LED.
+//! Where the steps stop, the chain ends — which catches the classic
+//! mismatch between `--led-count` and what's actually soldered on.
+
+use std::fs;
+
+/// Read the supply current in milliamps from the first hwmon device that
+/// exposes one. None when no sensor is attached.
+pub fn read_current_ma() -> Option<f64> {
+    let entries = fs::read_dir("/sys/class/hwmon").ok()?;
+    for entry in entries.flatten() {
+        let path = entry.path().join("curr1_input");
+        if let Ok(raw) = fs::read_to_string(&path) {
+            if let Ok(ma) = raw.trim().parse::<f64>() {
+                return Some(ma);
+            }
+        }
+    }
+    None
+}
+
+/// Estimate the connected LED count from (lit prefix, current) samples.
+/// The first sample must be the dark baseline. The per-LED draw is taken
+/// from the steepest step (prefixes inside the chain), and the plateau
+/// current divided by it gives the count.
+pub fn estimate_led_count(samples: &[(usize, f64)]) -> Result<usize, String> {
+    if samples.len() < 3 {
+        return Err("need at least a baseline and two lit samples".to_string());
+    }
+    let baseline = samples[0].1;
+    let per_led = samples
+        .windows(2)
+        .filter(|w| w[1].0 > w[0].0)
+        .map(|w| (w[1].1 - w[0].1) / (w[1].0 - w[0].0) as f64)
+        .fold(0.0f64, f64::max);
+    if per_led <= 0.0 {
+        return Err("current never rose; is the sensor on the LED rail?".to_string());
+    }
+    let plateau = samples.last().unwrap().1;
+    Ok(((plateau - baseline) / per_led).round().max(0.0) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_a_short_chain_from_the_plateau() {
+        // Configured for 400 but only 200 connected: current stops
+        // stepping halfway through the sweep.
+        let samples = [
+            (0, 120.0),
+            (100, 620.0),
+            (200, 1120.0),
+            (300, 1120.0),
+            (400, 1120.0),
+        ];
+        assert_eq!(estimate_led_count(&samples).unwrap(), 200);
+    }
+
+    #[test]
+    fn matches_a_fully_connected_chain() {
+        let samples = [(0, 100.0), (150, 850.0), (300, 1600.0)];
+        assert_eq!(estimate_led_count(&samples).unwrap(), 300);
+    }
+
+    #[test]
+    fn rejects_flat_readings() {
+        let samples = [(0, 100.0), (100, 100.0), (200, 100.0)];
+        assert!(estimate_led_count(&samples).is_err());
+        assert!(estimate_led_count(&samples[..2]).is_err());
+    }
+}
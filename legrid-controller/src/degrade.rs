@@ -0,0 +1,196 @@
+//! Graceful degradation under overload.
+//!
+//! When the output path can't keep up with the frame budget on weak
+//! hardware, quality is shed in explicit tiers instead of latching late:
+//!
+//!   1. drop frame interpolation
+//!   2. drop dithering
+//!   3. halve the output rate
+//!   4. bin the frame 2x2
+//!
+//! The governor watches the busy fraction (render time over frame budget)
+//! and walks the tiers with hysteresis, so a single slow frame doesn't
+//! flap quality up and down. The active tier is reported in stats.
+
+use crate::frame::Pixel;
+
+/// Highest tier; 0 is full quality.
+pub const TIER_MAX: u8 = 4;
+/// Consecutive overloaded samples before escalating one tier.
+pub const ESCALATE_SAMPLES: u32 = 60;
+/// Consecutive comfortable samples before recovering one tier.
+pub const RECOVER_SAMPLES: u32 = 300;
+/// Busy fraction above which a sample counts as overloaded.
+pub const OVERLOAD_THRESHOLD: f64 = 0.9;
+/// Busy fraction below which a sample counts towards recovery.
+pub const RECOVER_THRESHOLD: f64 = 0.5;
+
+pub fn tier_name(tier: u8) -> &'static str {
+    match tier {
+        0 => "full",
+        1 => "no-interpolation",
+        2 => "no-dither",
+        3 => "half-rate",
+        _ => "binned",
+    }
+}
+
+/// Walks the degradation tiers from per-frame busy samples.
+pub struct DegradationGovernor {
+    pub tier: u8,
+    /// EMA of the busy fraction, for stats.
+    pub load: f64,
+    over: u32,
+    under: u32,
+}
+
+impl Default for DegradationGovernor {
+    fn default() -> Self {
+        Self { tier: 0, load: 0.0, over: 0, under: 0 }
+    }
+}
+
+impl DegradationGovernor {
+    /// Feed one frame's busy fraction. Returns the new tier when it
+    /// changes, None otherwise.
+    pub fn step(&mut self, busy: f64) -> Option<u8> {
+        self.load = self.load * 0.9 + busy * 0.1;
+        if busy > OVERLOAD_THRESHOLD {
+            self.over += 1;
+            self.under = 0;
+        } else if busy < RECOVER_THRESHOLD {
+            self.under += 1;
+            self.over = 0;
+        } else {
+            self.over = 0;
+            self.under = 0;
+        }
+        if self.over >= ESCALATE_SAMPLES && self.tier < TIER_MAX {
+            self.tier += 1;
+            self.over = 0;
+            return Some(self.tier);
+        }
+        if self.under >= RECOVER_SAMPLES && self.tier > 0 {
+            self.tier -= 1;
+            self.under = 0;
+            return Some(self.tier);
+        }
+        None
+    }
+}
+
+/// Average each NxN block and write it back over the block, reducing the
+/// effective resolution without changing the wire format. Also serves the
+/// standalone --bin mode for low-resolution content on dense panels.
+pub fn bin_average(pixels: &mut [Pixel], width: usize, height: usize, bin: usize) {
+    if bin < 2 {
+        return;
+    }
+    let mut y = 0;
+    while y < height {
+        let mut x = 0;
+        while x < width {
+            let (mut r, mut g, mut b, mut n) = (0u32, 0u32, 0u32, 0u32);
+            for dy in 0..bin.min(height - y) {
+                for dx in 0..bin.min(width - x) {
+                    let p = pixels[(y + dy) * width + (x + dx)];
+                    r += p.r as u32;
+                    g += p.g as u32;
+                    b += p.b as u32;
+                    n += 1;
+                }
+            }
+            let avg = Pixel { r: (r / n) as u8, g: (g / n) as u8, b: (b / n) as u8 };
+            for dy in 0..bin.min(height - y) {
+                for dx in 0..bin.min(width - x) {
+                    pixels[(y + dy) * width + (x + dx)] = avg;
+                }
+            }
+            x += bin;
+        }
+        y += bin;
+    }
+}
+
+/// Expand a logical low-resolution frame onto the physical grid by
+/// replicating each logical pixel over its NxN block.
+pub fn upscale(logical: &[Pixel], logical_width: usize, logical_height: usize, bin: usize) -> Vec<Pixel> {
+    let (width, height) = (logical_width * bin, logical_height * bin);
+    let mut out = vec![Pixel::BLACK; width * height];
+    for ly in 0..logical_height {
+        for lx in 0..logical_width {
+            let p = logical[ly * logical_width + lx];
+            for dy in 0..bin {
+                for dx in 0..bin {
+                    out[(ly * bin + dy) * width + (lx * bin + dx)] = p;
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sustained_overload_escalates_one_tier_at_a_time() {
+        let mut governor = DegradationGovernor::default();
+        for _ in 0..ESCALATE_SAMPLES - 1 {
+            assert_eq!(governor.step(1.2), None);
+        }
+        assert_eq!(governor.step(1.2), Some(1));
+        // A comfortable sample resets the escalation counter.
+        governor.step(0.3);
+        for _ in 0..ESCALATE_SAMPLES - 1 {
+            assert_eq!(governor.step(1.2), None);
+        }
+        assert_eq!(governor.step(1.2), Some(2));
+    }
+
+    #[test]
+    fn recovery_needs_a_long_quiet_stretch() {
+        let mut governor = DegradationGovernor { tier: 2, ..Default::default() };
+        for _ in 0..RECOVER_SAMPLES - 1 {
+            assert_eq!(governor.step(0.1), None);
+        }
+        assert_eq!(governor.step(0.1), Some(1));
+    }
+
+    #[test]
+    fn tier_never_leaves_its_bounds() {
+        let mut governor = DegradationGovernor { tier: TIER_MAX, ..Default::default() };
+        for _ in 0..ESCALATE_SAMPLES * 2 {
+            assert_eq!(governor.step(2.0), None);
+        }
+        assert_eq!(governor.tier, TIER_MAX);
+    }
+
+    #[test]
+    fn binning_averages_blocks_and_keeps_odd_edges() {
+        let mut pixels = vec![
+            Pixel { r: 100, g: 0, b: 0 }, Pixel { r: 200, g: 0, b: 0 }, Pixel { r: 40, g: 0, b: 0 },
+            Pixel { r: 0, g: 0, b: 0 }, Pixel { r: 100, g: 0, b: 0 }, Pixel { r: 80, g: 0, b: 0 },
+        ];
+        bin_average(&mut pixels, 3, 2, 2);
+        assert_eq!(pixels[0].r, 100);
+        assert_eq!(pixels[0], pixels[4]);
+        // The odd right column averages on its own.
+        assert_eq!(pixels[2].r, 60);
+        assert_eq!(pixels[2], pixels[5]);
+    }
+
+    #[test]
+    fn upscale_replicates_logical_pixels_over_blocks() {
+        let logical = vec![Pixel { r: 10, g: 0, b: 0 }, Pixel { r: 20, g: 0, b: 0 }];
+        let out = upscale(&logical, 2, 1, 2);
+        assert_eq!(out.len(), 8);
+        // 4x2 grid: left 2x2 block from the first pixel, right from the second.
+        assert_eq!(out[0].r, 10);
+        assert_eq!(out[1].r, 10);
+        assert_eq!(out[2].r, 20);
+        assert_eq!(out[6].r, 20);
+        assert_eq!(out[4].r, 10);
+    }
+}
@@ -0,0 +1,172 @@
+//! Public-transport departure board widget.
+//!
+//! `--widgets "departures=https://stop.example/next.json"` polls a JSON
+//! endpoint and renders the next departures as static text lines — line
+//! name left, minutes right — turning an idle hallway panel into a
+//! departure board. The endpoint is expected to answer with objects
+//! carrying `line` and `minutes` fields, the lowest common denominator
+//! a GTFS-RT proxy or a home-automation script can emit:
+//!
+//! ```json
+//! {"departures":[{"line":"12","minutes":4},{"line":"N3","minutes":11}]}
+//! ```
+
+use std::io;
+use std::time::Duration;
+
+use crate::frame::Pixel;
+use crate::text::{glyph, GLYPH_ADVANCE, GLYPH_HEIGHT};
+use crate::widget::Widget;
+
+/// One upcoming departure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Departure {
+    pub line: String,
+    pub minutes: i64,
+}
+
+/// Pull the departures out of an endpoint response: every object with a
+/// `line` string and a `minutes` number, in document order.
+pub fn parse_departures(body: &str) -> Vec<Departure> {
+    let mut departures = Vec::new();
+    // Objects don't nest in this schema, so scanning for braces is
+    // enough — the same bet the control-message parser makes.
+    let mut rest = body;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else { break };
+        let object = &rest[start + 1..start + 1 + len];
+        if let (Some(line), Some(minutes)) = (
+            crate::controller::json_str_field(object, "line"),
+            crate::controller::json_num_field(object, "minutes"),
+        ) {
+            departures.push(Departure { line, minutes: minutes as i64 });
+        }
+        rest = &rest[start + 1 + len..];
+    }
+    departures
+}
+
+/// Render departures as static rows, one per text line: the line name
+/// at the left edge, minutes right-aligned. Rows that don't fit the
+/// panel height are dropped; no departures renders nothing.
+pub fn render_board(departures: &[Departure], width: usize, height: usize) -> Option<Vec<Pixel>> {
+    if departures.is_empty() {
+        return None;
+    }
+    const ROW: usize = GLYPH_HEIGHT + 1;
+    let mut layer = vec![Pixel::BLACK; width * height];
+    let rows = height / ROW;
+    let amber = Pixel { r: 255, g: 160, b: 0 };
+    let white = Pixel { r: 220, g: 220, b: 220 };
+    for (row, dep) in departures.iter().take(rows).enumerate() {
+        let y0 = row * ROW;
+        draw_text(&mut layer, width, height, 0, y0, &dep.line, amber);
+        let minutes = if dep.minutes >= 100 { "99".to_string() } else { dep.minutes.to_string() };
+        let x0 = width.saturating_sub(minutes.len() * GLYPH_ADVANCE);
+        draw_text(&mut layer, width, height, x0, y0, &minutes, white);
+    }
+    Some(layer)
+}
+
+/// Plot a string at a fixed position, clipped to the layer.
+fn draw_text(
+    layer: &mut [Pixel],
+    width: usize,
+    height: usize,
+    x0: usize,
+    y0: usize,
+    text: &str,
+    color: Pixel,
+) {
+    for (i, c) in text.chars().enumerate() {
+        let columns = glyph(c);
+        for (col, &bits) in columns.iter().enumerate() {
+            let x = x0 + i * GLYPH_ADVANCE + col;
+            if x >= width {
+                return;
+            }
+            for row in 0..GLYPH_HEIGHT {
+                let y = y0 + row;
+                if y < height && bits >> row & 1 == 1 {
+                    layer[y * width + x] = color;
+                }
+            }
+        }
+    }
+}
+
+/// The departure-board widget; see the module docs.
+pub struct DeparturesWidget {
+    url: String,
+    departures: Vec<Departure>,
+}
+
+impl DeparturesWidget {
+    pub fn new(url: String) -> Self {
+        Self { url, departures: Vec::new() }
+    }
+}
+
+impl Widget for DeparturesWidget {
+    fn name(&self) -> &'static str {
+        "departures"
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn refresh(&mut self) -> io::Result<()> {
+        let body = crate::widget::fetch_url(&self.url)?;
+        self.departures = parse_departures(&body);
+        Ok(())
+    }
+
+    fn render(&self, width: usize, height: usize) -> Option<Vec<Pixel>> {
+        render_board(&self.departures, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_line_and_minutes_pairs() {
+        let body = r#"{"departures":[{"line":"12","minutes":4},{"line":"N3","minutes":11},{"route":"x"}]}"#;
+        let deps = parse_departures(body);
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0], Departure { line: "12".to_string(), minutes: 4 });
+        assert_eq!(deps[1].line, "N3");
+        assert!(parse_departures("not json").is_empty());
+    }
+
+    #[test]
+    fn board_lays_out_rows_and_right_aligns_minutes() {
+        let deps = vec![
+            Departure { line: "A".to_string(), minutes: 5 },
+            Departure { line: "B".to_string(), minutes: 12 },
+        ];
+        let (w, h) = (25, 24);
+        let layer = render_board(&deps, w, h).unwrap();
+        let row_lit = |y0: usize| {
+            (y0 * w..(y0 + GLYPH_HEIGHT) * w).any(|i| layer[i] != Pixel::BLACK)
+        };
+        assert!(row_lit(0));
+        assert!(row_lit(GLYPH_HEIGHT + 1));
+        // The minutes column touches the right edge region.
+        let right_lit = (0..h)
+            .any(|y| (w - GLYPH_ADVANCE..w).any(|x| layer[y * w + x] != Pixel::BLACK));
+        assert!(right_lit);
+        assert!(render_board(&[], w, h).is_none());
+    }
+
+    #[test]
+    fn overflowing_rows_are_dropped() {
+        let deps: Vec<Departure> = (0..10)
+            .map(|i| Departure { line: "X".to_string(), minutes: i })
+            .collect();
+        // A panel two rows tall shows two departures and stays in bounds.
+        assert!(render_board(&deps, 10, 16).is_some());
+    }
+}
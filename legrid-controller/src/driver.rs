@@ -0,0 +1,507 @@
+//! Output drivers.
+//!
+//! The output stage is behind a small trait so the same pipeline can feed
+//! real hardware, the mock logger, or the simulators.
+
+use std::io::{self, Write};
+
+use crate::frame::Pixel;
+
+pub trait LedDriver {
+    fn name(&self) -> &'static str;
+    fn render(&mut self, pixels: &[Pixel], width: usize, height: usize) -> io::Result<()>;
+
+    /// Whether this backend can latch a subset of the chain (serial
+    /// protocols with offsets, DDP forwarding). Backends that repaint
+    /// everything anyway leave this false and never see render_sparse.
+    fn supports_sparse(&self) -> bool {
+        false
+    }
+
+    /// Latch only the given (index, pixel) updates. Only called when
+    /// supports_sparse() is true and the frame diff is small.
+    fn render_sparse(&mut self, _changes: &[(usize, Pixel)]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The highest refresh rate this backend can sustain without latching
+    /// late, derived from chain length and protocol timing. None means
+    /// unbounded (simulators, logging backends).
+    fn max_safe_fps(&self, _led_count: usize) -> Option<f64> {
+        None
+    }
+
+    /// Tear down and re-open the underlying device after a render failure
+    /// (SPI node gone, DMA init lost). Backends with nothing to re-open
+    /// succeed trivially; the controller drives retries with backoff.
+    fn reinit(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// WS2812-class wire timing: 24 bits at 1.25µs each per LED, plus the
+/// reset gap that latches the chain.
+const WS2812_US_PER_LED: f64 = 30.0;
+const WS2812_RESET_US: f64 = 300.0;
+
+/// Maximum refresh rate a single WS2812 chain of this length can sustain.
+pub fn ws2812_max_fps(led_count: usize) -> f64 {
+    1_000_000.0 / (led_count.max(1) as f64 * WS2812_US_PER_LED + WS2812_RESET_US)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DriverKind {
+    Mock,
+    Terminal,
+    /// Graphical simulator window; only present when built with the
+    /// `sim-window` feature (pulls in minifb).
+    #[cfg(feature = "sim-window")]
+    Window,
+}
+
+impl DriverKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "mock" => Some(DriverKind::Mock),
+            "terminal" => Some(DriverKind::Terminal),
+            #[cfg(feature = "sim-window")]
+            "window" => Some(DriverKind::Window),
+            #[cfg(not(feature = "sim-window"))]
+            "window" => {
+                crate::log_warn!("driver", "The window driver requires a build with the sim-window feature");
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn create(self, width: usize, height: usize) -> io::Result<Box<dyn LedDriver>> {
+        #[cfg(not(feature = "sim-window"))]
+        let _ = (width, height);
+        match self {
+            DriverKind::Mock => Ok(Box::new(MockDriver { frames: 0 })),
+            DriverKind::Terminal => Ok(Box::new(TerminalDriver { initialized: false })),
+            #[cfg(feature = "sim-window")]
+            DriverKind::Window => Ok(Box::new(sim_window::WindowDriver::new(width, height)?)),
+        }
+    }
+}
+
+/// Stand-in for real hardware: logs what would have been latched.
+pub struct MockDriver {
+    frames: u64,
+}
+
+impl LedDriver for MockDriver {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn render(&mut self, pixels: &[Pixel], _width: usize, _height: usize) -> io::Result<()> {
+        self.frames += 1;
+        let lit_count = pixels.iter().filter(|p| p.r > 0 || p.g > 0 || p.b > 0).count();
+        crate::log_debug!("driver", "Frame {}: {}/{} pixels lit", self.frames, lit_count, pixels.len());
+        Ok(())
+    }
+
+    fn supports_sparse(&self) -> bool {
+        true
+    }
+
+    fn render_sparse(&mut self, changes: &[(usize, Pixel)]) -> io::Result<()> {
+        self.frames += 1;
+        crate::log_debug!("driver", "Frame {}: sparse update, {} pixels", self.frames, changes.len());
+        Ok(())
+    }
+}
+
+/// One output channel of a multi-strip setup: a GPIO pin and the span of
+/// the logical chain it drives (inclusive indices, as written in the
+/// `--channels` spec).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSpec {
+    pub pin: u8,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Parse a `"pin:start-end,pin:start-end"` channel map and validate it
+/// against the chain length: segments must not overlap and together must
+/// cover every LED, so a bad map fails at startup instead of leaving part
+/// of the panel dark.
+pub fn parse_channels(spec: &str, led_count: usize) -> Result<Vec<ChannelSpec>, String> {
+    let mut channels = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        let (pin, range) = part
+            .split_once(':')
+            .ok_or_else(|| format!("channel '{}': expected pin:start-end", part))?;
+        let pin: u8 = pin.trim().parse().map_err(|_| format!("channel '{}': bad pin", part))?;
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| format!("channel '{}': expected pin:start-end", part))?;
+        let start: usize = start.trim().parse().map_err(|_| format!("channel '{}': bad range", part))?;
+        let end: usize = end.trim().parse().map_err(|_| format!("channel '{}': bad range", part))?;
+        if start > end || end >= led_count {
+            return Err(format!(
+                "channel '{}': range outside the {}-LED chain",
+                part, led_count
+            ));
+        }
+        channels.push(ChannelSpec { pin, start, end });
+    }
+
+    // Coverage check: sort by start, require contiguous non-overlapping
+    // segments from 0 to led_count - 1.
+    let mut sorted = channels.clone();
+    sorted.sort_by_key(|c| c.start);
+    let mut expected = 0usize;
+    for channel in &sorted {
+        if channel.start != expected {
+            return Err(if channel.start < expected {
+                format!("channel pin {} overlaps the previous segment", channel.pin)
+            } else {
+                format!("gap before LED {}: every LED needs a channel", channel.start)
+            });
+        }
+        expected = channel.end + 1;
+    }
+    if expected != led_count {
+        return Err(format!("channels stop at LED {} but the chain has {}", expected, led_count));
+    }
+    Ok(channels)
+}
+
+/// Drives the chain as several independent segments, one per GPIO pin,
+/// each latched from its own thread so the whole panel refreshes in the
+/// time of the longest segment instead of the full chain.
+pub struct MultiChannelDriver {
+    channels: Vec<ChannelSpec>,
+    frames: u64,
+}
+
+impl MultiChannelDriver {
+    pub fn new(channels: Vec<ChannelSpec>) -> Self {
+        Self { channels, frames: 0 }
+    }
+}
+
+impl LedDriver for MultiChannelDriver {
+    fn name(&self) -> &'static str {
+        "multi-channel"
+    }
+
+    fn max_safe_fps(&self, _led_count: usize) -> Option<f64> {
+        // Segments latch concurrently, so the longest one sets the pace.
+        self.channels
+            .iter()
+            .map(|c| c.end - c.start + 1)
+            .max()
+            .map(ws2812_max_fps)
+    }
+
+    fn render(&mut self, pixels: &[Pixel], _width: usize, _height: usize) -> io::Result<()> {
+        self.frames += 1;
+        let frame = self.frames;
+        std::thread::scope(|scope| {
+            for channel in &self.channels {
+                let segment = &pixels[channel.start..=channel.end.min(pixels.len() - 1)];
+                scope.spawn(move || {
+                    // Hardware latch goes here; the mock logs what each pin
+                    // would have pushed.
+                    let lit = segment.iter().filter(|p| p.r > 0 || p.g > 0 || p.b > 0).count();
+                    crate::log_debug!("driver",
+                        "Frame {}: pin {} latched LEDs {}-{} ({}/{} lit)",
+                        frame, channel.pin, channel.start, channel.end, lit, segment.len()
+                    );
+                });
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Decompose a frame into binary-coded-modulation planes for backends
+/// with on/off-only color control (HUB75 scanning, PWM expanders). Each
+/// plane holds one of the top `depth` bits of every channel together with
+/// its display weight; showing plane k for 2^k time slices reproduces a
+/// `depth`-bit approximation of the frame.
+pub fn bcm_planes(pixels: &[Pixel], depth: u8) -> Vec<(Vec<Pixel>, u32)> {
+    let depth = depth.clamp(1, 8);
+    (0..depth)
+        .map(|k| {
+            let bit = 8 - depth + k;
+            let plane = pixels
+                .iter()
+                .map(|p| {
+                    let on = |v: u8| if v >> bit & 1 == 1 { 255 } else { 0 };
+                    Pixel { r: on(p.r), g: on(p.g), b: on(p.b) }
+                })
+                .collect();
+            (plane, 1u32 << k)
+        })
+        .collect()
+}
+
+/// Wraps a backend in subframe scanning: every frame goes out as `depth`
+/// binary planes held for weighted slices, trading refresh rate for color
+/// fidelity on backends that can only switch LEDs fully on or off.
+pub struct BcmDriver {
+    inner: Box<dyn LedDriver>,
+    depth: u8,
+    /// How long the weight-1 plane is held; plane k is held 2^k of these.
+    slice: std::time::Duration,
+    /// Subframes pushed to the inner backend, for stats and tests.
+    pub subframes: u64,
+}
+
+impl BcmDriver {
+    pub fn new(inner: Box<dyn LedDriver>, depth: u8) -> Self {
+        Self {
+            inner,
+            depth: depth.clamp(1, 8),
+            slice: std::time::Duration::from_micros(50),
+            subframes: 0,
+        }
+    }
+}
+
+impl LedDriver for BcmDriver {
+    fn name(&self) -> &'static str {
+        "bcm"
+    }
+
+    fn max_safe_fps(&self, led_count: usize) -> Option<f64> {
+        // Every frame goes out as 2^depth - 1 weighted slices of the
+        // inner backend's refresh.
+        let slices = (1u32 << self.depth) as f64 - 1.0;
+        self.inner.max_safe_fps(led_count).map(|fps| fps / slices)
+    }
+
+    fn render(&mut self, pixels: &[Pixel], width: usize, height: usize) -> io::Result<()> {
+        // Most-significant plane first, so an error part-way leaves the
+        // frame closest to correct.
+        for (plane, weight) in bcm_planes(pixels, self.depth).into_iter().rev() {
+            self.inner.render(&plane, width, height)?;
+            self.subframes += 1;
+            std::thread::sleep(self.slice * weight);
+        }
+        Ok(())
+    }
+
+    fn reinit(&mut self) -> io::Result<()> {
+        self.inner.reinit()
+    }
+}
+
+/// Renders the grid in the terminal with ANSI truecolor half-block
+/// characters, two pixel rows per text row, redrawing in place. Goes to
+/// stderr so the stdout stats protocol stays clean.
+pub struct TerminalDriver {
+    initialized: bool,
+}
+
+impl LedDriver for TerminalDriver {
+    fn name(&self) -> &'static str {
+        "terminal"
+    }
+
+    fn reinit(&mut self) -> io::Result<()> {
+        // Repaint from scratch: the terminal may have been resized or
+        // cleared out from under us.
+        self.initialized = false;
+        Ok(())
+    }
+
+    fn render(&mut self, pixels: &[Pixel], width: usize, height: usize) -> io::Result<()> {
+        let mut out = String::new();
+        if !self.initialized {
+            // Clear screen and hide the cursor once, then redraw in place.
+            out.push_str("\x1b[2J\x1b[?25l");
+            self.initialized = true;
+        }
+        out.push_str("\x1b[H");
+
+        let width = width.max(1);
+        let px = |x: usize, y: usize| -> Pixel { pixels.get(y * width + x).copied().unwrap_or(Pixel::BLACK) };
+
+        let mut y = 0;
+        while y < height {
+            for x in 0..width {
+                let top = px(x, y);
+                let bottom = if y + 1 < height { px(x, y + 1) } else { Pixel::BLACK };
+                out.push_str(&format!(
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                    top.r, top.g, top.b, bottom.r, bottom.g, bottom.b
+                ));
+            }
+            out.push_str("\x1b[0m\r\n");
+            y += 2;
+        }
+
+        let mut stderr = io::stderr().lock();
+        stderr.write_all(out.as_bytes())?;
+        stderr.flush()
+    }
+}
+
+impl Drop for TerminalDriver {
+    fn drop(&mut self) {
+        if self.initialized {
+            eprint!("\x1b[0m\x1b[?25h");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_two_channel_map() {
+        let channels = parse_channels("18:0-299,13:300-599", 600).unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0], ChannelSpec { pin: 18, start: 0, end: 299 });
+        assert_eq!(channels[1], ChannelSpec { pin: 13, start: 300, end: 599 });
+    }
+
+    #[test]
+    fn rejects_overlapping_and_gapped_maps() {
+        let err = parse_channels("18:0-300,13:300-599", 600).unwrap_err();
+        assert!(err.contains("overlap"), "{}", err);
+        let err = parse_channels("18:0-100,13:300-599", 600).unwrap_err();
+        assert!(err.contains("gap"), "{}", err);
+        let err = parse_channels("18:0-599", 700).unwrap_err();
+        assert!(err.contains("chain has 700"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        assert!(parse_channels("18=0-299", 300).is_err());
+        assert!(parse_channels("pin:0-299", 300).is_err());
+        assert!(parse_channels("18:299-0", 300).is_err());
+    }
+
+    #[test]
+    fn bcm_planes_carry_weighted_bits() {
+        // 0b1010_0000 at depth 4 keeps bits 7 and 5: planes (weight 8)
+        // and (weight 2) are lit, the others dark.
+        let pixels = vec![Pixel { r: 0xA0, g: 0, b: 0 }];
+        let planes = bcm_planes(&pixels, 4);
+        assert_eq!(planes.len(), 4);
+        let lit: Vec<u32> = planes
+            .iter()
+            .filter(|(plane, _)| plane[0].r == 255)
+            .map(|&(_, weight)| weight)
+            .collect();
+        assert_eq!(lit, vec![2, 8]);
+    }
+
+    #[test]
+    fn bcm_driver_latches_one_subframe_per_bit() {
+        let mut driver = BcmDriver::new(Box::new(MockDriver { frames: 0 }), 3);
+        driver.slice = std::time::Duration::ZERO;
+        driver.render(&[Pixel { r: 128, g: 64, b: 32 }], 1, 1).unwrap();
+        assert_eq!(driver.subframes, 3);
+        driver.render(&[Pixel::BLACK], 1, 1).unwrap();
+        assert_eq!(driver.subframes, 6);
+    }
+
+    #[test]
+    fn wire_timing_caps_the_refresh_rate() {
+        // 600 LEDs on one pin: 18.3ms of data, ~54 FPS.
+        let fps = ws2812_max_fps(600);
+        assert!((54.0..55.0).contains(&fps), "{}", fps);
+
+        // Split across two pins, the longest segment sets the pace.
+        let driver = MultiChannelDriver::new(parse_channels("18:0-299,13:300-599", 600).unwrap());
+        let split = driver.max_safe_fps(600).unwrap();
+        assert!(split > fps * 1.9, "{}", split);
+
+        // BCM multiplies the wire time by its slice count.
+        let bcm = BcmDriver::new(Box::new(driver), 2);
+        let sub = bcm.max_safe_fps(600).unwrap();
+        assert!((sub - split / 3.0).abs() < 0.01, "{}", sub);
+
+        // Simulators advertise no ceiling.
+        assert!(MockDriver { frames: 0 }.max_safe_fps(600).is_none());
+    }
+
+    #[test]
+    fn multi_channel_renders_every_segment() {
+        let channels = parse_channels("18:0-1,13:2-3", 4).unwrap();
+        let mut driver = MultiChannelDriver::new(channels);
+        let pixels = vec![Pixel { r: 1, g: 2, b: 3 }; 4];
+        driver.render(&pixels, 4, 1).unwrap();
+        assert_eq!(driver.frames, 1);
+    }
+}
+
+/// Graphical simulator: draws the grid as scaled squares in a window. The
+/// driver sits behind the same pipeline as the hardware backends, so what
+/// the window shows matches the physical panel pixel-for-pixel.
+#[cfg(feature = "sim-window")]
+mod sim_window {
+    use super::{io, LedDriver, Pixel};
+
+    // Size of one LED cell in window pixels, including a 1px gap.
+    const CELL: usize = 16;
+
+    pub struct WindowDriver {
+        window: minifb::Window,
+        buffer: Vec<u32>,
+        buf_w: usize,
+        buf_h: usize,
+    }
+
+    impl WindowDriver {
+        pub fn new(width: usize, height: usize) -> io::Result<Self> {
+            let buf_w = width.max(1) * CELL;
+            let buf_h = height.max(1) * CELL;
+            let window = minifb::Window::new(
+                "legrid simulator",
+                buf_w,
+                buf_h,
+                minifb::WindowOptions {
+                    resize: true,
+                    scale_mode: minifb::ScaleMode::AspectRatioStretch,
+                    ..minifb::WindowOptions::default()
+                },
+            )
+            .map_err(|e| io::Error::other(format!("minifb: {}", e)))?;
+            Ok(Self {
+                window,
+                buffer: vec![0u32; buf_w * buf_h],
+                buf_w,
+                buf_h,
+            })
+        }
+    }
+
+    impl LedDriver for WindowDriver {
+        fn name(&self) -> &'static str {
+            "window"
+        }
+
+        fn render(&mut self, pixels: &[Pixel], width: usize, height: usize) -> io::Result<()> {
+            if !self.window.is_open() {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Simulator window closed"));
+            }
+
+            let width = width.max(1);
+            for (i, px) in pixels.iter().enumerate().take(width * height) {
+                let (gx, gy) = (i % width, i / width);
+                let color = ((px.r as u32) << 16) | ((px.g as u32) << 8) | px.b as u32;
+                for y in gy * CELL..(gy + 1) * CELL - 1 {
+                    for x in gx * CELL..(gx + 1) * CELL - 1 {
+                        self.buffer[y * self.buf_w + x] = color;
+                    }
+                }
+            }
+
+            self.window
+                .update_with_buffer(&self.buffer, self.buf_w, self.buf_h)
+                .map_err(|e| io::Error::other(format!("minifb: {}", e)))
+        }
+    }
+}
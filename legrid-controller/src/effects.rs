@@ -0,0 +1,394 @@
+//! Built-in frame generators: the idle animation engine and the bring-up
+//! test patterns. Everything here is self-contained and dependency-free.
+
+use std::time::Instant;
+
+use crate::frame::Pixel;
+
+/// Built-in animation shown when the host stops sending frames, so the
+/// panel doesn't freeze on stale content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdleEffect {
+    None,
+    Rainbow,
+    Breathing,
+    Sparkle,
+    Solid,
+}
+
+impl IdleEffect {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "rainbow" => IdleEffect::Rainbow,
+            "breathing" => IdleEffect::Breathing,
+            "sparkle" => IdleEffect::Sparkle,
+            "solid" => IdleEffect::Solid,
+            _ => IdleEffect::None,
+        }
+    }
+
+    /// The wire/config name; the inverse of [`parse`](Self::parse).
+    pub fn name(self) -> &'static str {
+        match self {
+            IdleEffect::None => "none",
+            IdleEffect::Rainbow => "rainbow",
+            IdleEffect::Breathing => "breathing",
+            IdleEffect::Sparkle => "sparkle",
+            IdleEffect::Solid => "solid",
+        }
+    }
+}
+
+/// Small xorshift PRNG so the sparkle effect doesn't need a dependency.
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed | 1 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+}
+
+pub fn hsv_to_rgb(h: f64, s: f64, v: f64) -> Pixel {
+    let h = h.rem_euclid(360.0);
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match h as u32 {
+        0..=59 => (c, x, 0.0),
+        60..=119 => (x, c, 0.0),
+        120..=179 => (0.0, c, x),
+        180..=239 => (0.0, x, c),
+        240..=299 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    Pixel {
+        r: ((r + m) * 255.0).round() as u8,
+        g: ((g + m) * 255.0).round() as u8,
+        b: ((b + m) * 255.0).round() as u8,
+    }
+}
+
+/// Renders the configured idle effect. Owns its own clock and RNG; the
+/// main loop drives it once per output tick while no frames are arriving.
+pub struct IdleAnimator {
+    started: Instant,
+    rng: XorShift,
+    sparkle_levels: Vec<u8>,
+}
+
+impl Default for IdleAnimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdleAnimator {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            rng: XorShift::new(0x9e37_79b9_7f4a_7c15),
+            sparkle_levels: Vec::new(),
+        }
+    }
+
+    pub fn render(&mut self, effect: IdleEffect, color: Pixel, width: u16, led_count: usize) -> Vec<Pixel> {
+        let t = self.started.elapsed().as_secs_f64();
+        self.render_at(effect, color, width, led_count, t)
+    }
+
+    /// Like [`render`](Self::render), but with an explicit animation
+    /// clock — the beat sync feeds beats here so effects run in musical
+    /// time instead of wall time.
+    pub fn render_at(
+        &mut self,
+        effect: IdleEffect,
+        color: Pixel,
+        width: u16,
+        led_count: usize,
+        t: f64,
+    ) -> Vec<Pixel> {
+        match effect {
+            IdleEffect::None => vec![Pixel::BLACK; led_count],
+            IdleEffect::Solid => vec![color; led_count],
+            IdleEffect::Rainbow => {
+                let width = width.max(1) as usize;
+                (0..led_count)
+                    .map(|i| {
+                        let x = (i % width) as f64 / width as f64;
+                        hsv_to_rgb(x * 360.0 + t * 60.0, 1.0, 0.6)
+                    })
+                    .collect()
+            }
+            IdleEffect::Breathing => {
+                let level = 0.5 - 0.5 * (t * std::f64::consts::PI / 2.0).cos();
+                let scale = 0.05 + 0.95 * level;
+                vec![
+                    Pixel {
+                        r: (color.r as f64 * scale) as u8,
+                        g: (color.g as f64 * scale) as u8,
+                        b: (color.b as f64 * scale) as u8,
+                    };
+                    led_count
+                ]
+            }
+            IdleEffect::Sparkle => {
+                self.sparkle_levels.resize(led_count, 0);
+                for level in self.sparkle_levels.iter_mut() {
+                    // Decay existing sparkles, occasionally ignite new ones.
+                    *level = level.saturating_sub(12);
+                    if self.rng.next_u64() % 1000 < 8 {
+                        *level = 255;
+                    }
+                }
+                self.sparkle_levels
+                    .iter()
+                    .map(|&level| {
+                        let scale = level as f64 / 255.0;
+                        Pixel {
+                            r: (color.r as f64 * scale) as u8,
+                            g: (color.g as f64 * scale) as u8,
+                            b: (color.b as f64 * scale) as u8,
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Built-in patterns for bring-up of new hardware: verify wiring, color
+/// order, and pixel mapping before the host side exists. Selected with
+/// --test-pattern; runs without any stdin input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestPattern {
+    ColorWipe,
+    ChannelSweep,
+    PixelIndex,
+    Gradient,
+    Checkerboard,
+    BitFlip,
+    ChannelRamp,
+    Coordinates,
+}
+
+impl TestPattern {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "wipe" | "color-wipe" => Some(TestPattern::ColorWipe),
+            "sweep" | "channel-sweep" => Some(TestPattern::ChannelSweep),
+            "index" | "pixel-index" => Some(TestPattern::PixelIndex),
+            "gradient" => Some(TestPattern::Gradient),
+            "checkerboard" => Some(TestPattern::Checkerboard),
+            "bitflip" | "bit-flip" => Some(TestPattern::BitFlip),
+            "ramp" | "channel-ramp" => Some(TestPattern::ChannelRamp),
+            "coords" | "coordinates" => Some(TestPattern::Coordinates),
+            _ => None,
+        }
+    }
+
+    /// What the operator should look for while the pattern runs; printed
+    /// when the pattern starts so bring-up doesn't need the docs open.
+    pub fn guidance(self) -> &'static str {
+        match self {
+            TestPattern::ColorWipe =>
+                "Pixels fill one by one in red, then green, then blue. Gaps or out-of-order \
+                 filling indicate a wiring/mapping problem; wrong colors indicate color order.",
+            TestPattern::ChannelSweep =>
+                "The whole panel shows one channel per second: red, green, blue. If the \
+                 sequence looks different, set the matching color order.",
+            TestPattern::PixelIndex =>
+                "A single white pixel walks the chain with dim markers every 10th LED. Use it \
+                 to count positions and verify --led-count and the physical map.",
+            TestPattern::Gradient =>
+                "A static hue gradient across x. Banding, flicker, or tearing points at \
+                 data-line signal integrity (level shifter, cable length, ground).",
+            TestPattern::Checkerboard =>
+                "Max-contrast checkerboard inverting once per second. Smearing or pixels \
+                 lagging a frame behind indicates latch timing problems.",
+            TestPattern::BitFlip =>
+                "All channels alternate 0xAA/0x55 every frame, the worst case for the data \
+                 line. Random sparkles or wrong pixels mean marginal signal integrity.",
+            TestPattern::ChannelRamp =>
+                "A 0..255 brightness ramp along x, one channel at a time (2s each): red, \
+                 green, blue. A ramp in the wrong color pins down the exact color-order error.",
+            TestPattern::Coordinates =>
+                "Coordinate overlay: the origin blinks white, the x axis is red, the y axis is \
+                 green, and every 5th row/column shows dim gridlines. Blue markers sit on the \
+                 right edge of odd rows, where those rows start under serpentine wiring (even \
+                 rows start on the green axis); markers elsewhere mean the physical map or \
+                 orientation is wrong.",
+        }
+    }
+}
+
+/// Render one step of a test pattern. `step` advances once per output tick.
+pub fn render_test_pattern(pattern: TestPattern, step: u64, width: u16, led_count: usize) -> Vec<Pixel> {
+    let black = Pixel::BLACK;
+    let width = width.max(1) as usize;
+    match pattern {
+        TestPattern::ColorWipe => {
+            // Fill the chain one pixel per step, cycling R -> G -> B so a
+            // wrong color order is immediately visible.
+            let pos = (step as usize) % led_count;
+            let cycle = (step as usize / led_count) % 3;
+            let color = match cycle {
+                0 => Pixel { r: 255, g: 0, b: 0 },
+                1 => Pixel { r: 0, g: 255, b: 0 },
+                _ => Pixel { r: 0, g: 0, b: 255 },
+            };
+            (0..led_count).map(|i| if i <= pos { color } else { black }).collect()
+        }
+        TestPattern::ChannelSweep => {
+            // Whole panel in a single channel, one channel per second.
+            let color = match (step / 30) % 3 {
+                0 => Pixel { r: 255, g: 0, b: 0 },
+                1 => Pixel { r: 0, g: 255, b: 0 },
+                _ => Pixel { r: 0, g: 0, b: 255 },
+            };
+            vec![color; led_count]
+        }
+        TestPattern::PixelIndex => {
+            // A single bright pixel walking the chain, with dim markers on
+            // every 10th position so miscounts are easy to spot.
+            let pos = (step as usize / 3) % led_count;
+            (0..led_count)
+                .map(|i| {
+                    if i == pos {
+                        Pixel { r: 255, g: 255, b: 255 }
+                    } else if i % 10 == 0 {
+                        Pixel { r: 16, g: 16, b: 16 }
+                    } else {
+                        black
+                    }
+                })
+                .collect()
+        }
+        TestPattern::Gradient => {
+            // Static hue gradient across the x axis; banding or tearing
+            // here points at data-line signal problems.
+            (0..led_count)
+                .map(|i| hsv_to_rgb((i % width) as f64 / width as f64 * 360.0, 1.0, 0.5))
+                .collect()
+        }
+        TestPattern::BitFlip => {
+            // Alternate 0xAA / 0x55 on every channel each frame: flips
+            // every bit on the data line at the fastest possible rate.
+            let value = if step.is_multiple_of(2) { 0xAA } else { 0x55 };
+            vec![Pixel { r: value, g: value, b: value }; led_count]
+        }
+        TestPattern::ChannelRamp => {
+            // Brightness ramp along x on a single channel, switching
+            // channel every two seconds.
+            let channel = (step / 60) % 3;
+            (0..led_count)
+                .map(|i| {
+                    let level = ((i % width) * 255 / width.max(2).saturating_sub(1)).min(255) as u8;
+                    match channel {
+                        0 => Pixel { r: level, g: 0, b: 0 },
+                        1 => Pixel { r: 0, g: level, b: 0 },
+                        _ => Pixel { r: 0, g: 0, b: level },
+                    }
+                })
+                .collect()
+        }
+        TestPattern::Coordinates => {
+            // Orientation overlay: origin (blinking white), axes, 5th-line
+            // grid, and the serpentine start-of-row markers.
+            let origin_on = (step / 15).is_multiple_of(2);
+            (0..led_count)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let serp_start = if y % 2 == 0 { 0 } else { width - 1 };
+                    if x == 0 && y == 0 {
+                        if origin_on {
+                            Pixel { r: 255, g: 255, b: 255 }
+                        } else {
+                            black
+                        }
+                    } else if y == 0 {
+                        Pixel { r: 200, g: 0, b: 0 }
+                    } else if x == 0 {
+                        Pixel { r: 0, g: 200, b: 0 }
+                    } else if x == serp_start {
+                        Pixel { r: 0, g: 0, b: 200 }
+                    } else if x % 5 == 0 && y % 5 == 0 {
+                        Pixel { r: 60, g: 60, b: 0 }
+                    } else if x % 5 == 0 {
+                        Pixel { r: 40, g: 0, b: 0 }
+                    } else if y % 5 == 0 {
+                        Pixel { r: 0, g: 40, b: 0 }
+                    } else {
+                        black
+                    }
+                })
+                .collect()
+        }
+        TestPattern::Checkerboard => {
+            // Max-contrast alternating grid, inverted once per second, to
+            // stress latch timing and reveal mapping errors.
+            let invert = (step / 30) % 2 == 1;
+            (0..led_count)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let on = (x + y) % 2 == 0;
+                    if on != invert {
+                        Pixel { r: 255, g: 255, b: 255 }
+                    } else {
+                        black
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hsv_primaries() {
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), Pixel { r: 255, g: 0, b: 0 });
+        assert_eq!(hsv_to_rgb(120.0, 1.0, 1.0), Pixel { r: 0, g: 255, b: 0 });
+        assert_eq!(hsv_to_rgb(240.0, 1.0, 1.0), Pixel { r: 0, g: 0, b: 255 });
+    }
+
+    #[test]
+    fn checkerboard_alternates_neighbours() {
+        let frame = render_test_pattern(TestPattern::Checkerboard, 0, 4, 8);
+        assert_ne!(frame[0], frame[1]);
+        assert_eq!(frame[0], frame[2]);
+    }
+
+    #[test]
+    fn coordinates_mark_origin_axes_and_serpentine_starts() {
+        let frame = render_test_pattern(TestPattern::Coordinates, 0, 5, 15);
+        // Origin blinks white at step 0.
+        assert_eq!(frame[0], Pixel { r: 255, g: 255, b: 255 });
+        // x axis red, y axis green.
+        assert_eq!(frame[2], Pixel { r: 200, g: 0, b: 0 });
+        assert_eq!(frame[2 * 5], Pixel { r: 0, g: 200, b: 0 });
+        // Odd rows start on the right under serpentine wiring.
+        assert_eq!(frame[5 + 4], Pixel { r: 0, g: 0, b: 200 });
+    }
+
+    #[test]
+    fn color_wipe_fills_progressively() {
+        let lit = |frame: &[Pixel]| frame.iter().filter(|p| **p != Pixel::BLACK).count();
+        let early = render_test_pattern(TestPattern::ColorWipe, 0, 4, 8);
+        let later = render_test_pattern(TestPattern::ColorWipe, 5, 4, 8);
+        assert_eq!(lit(&early), 1);
+        assert_eq!(lit(&later), 6);
+    }
+}
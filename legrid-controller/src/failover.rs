@@ -0,0 +1,156 @@
+//! Primary/standby failover.
+//!
+//! Two controllers receive the same frame stream (directly or via the
+//! forwarding relay). The primary (`--failover primary:host:port`) sends a
+//! UDP heartbeat; the standby (`--failover standby:port`) processes frames
+//! but keeps its output gated until the heartbeat disappears, then flips
+//! the data-line switch GPIO and takes over. Once promoted it stays
+//! active — flapping back and forth mid-show is worse than a manual reset.
+
+use std::io;
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(500);
+pub const FAILOVER_TIMEOUT: Duration = Duration::from_secs(2);
+const HEARTBEAT_MAGIC: &[u8] = b"LEGRID-HB1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailoverRole {
+    /// Send heartbeats to the standby at this host:port.
+    Primary { peer: String },
+    /// Listen for heartbeats on this port; take over when they stop.
+    Standby { port: u16 },
+}
+
+/// Parse a `--failover` spec: `primary:host:port` or `standby:port`.
+pub fn parse_failover(spec: &str) -> Result<FailoverRole, String> {
+    match spec.split_once(':') {
+        Some(("primary", peer)) if peer.contains(':') => Ok(FailoverRole::Primary {
+            peer: peer.to_string(),
+        }),
+        Some(("standby", port)) => port
+            .parse()
+            .map(|port| FailoverRole::Standby { port })
+            .map_err(|_| format!("failover '{}': bad port", spec)),
+        _ => Err(format!(
+            "failover '{}': expected primary:host:port or standby:port",
+            spec
+        )),
+    }
+}
+
+/// Primary side: fire heartbeats at the standby forever. Send errors are
+/// logged and retried — a dead standby must not take the primary down.
+pub fn spawn_heartbeat_sender(peer: String) -> io::Result<()> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    crate::log_info!("failover", "Failover primary: heartbeats to {}", peer);
+    std::thread::spawn(move || loop {
+        if let Err(e) = socket.send_to(HEARTBEAT_MAGIC, &peer) {
+            crate::log_warn!("failover", "Heartbeat send to {} failed: {}", peer, e);
+        }
+        std::thread::sleep(HEARTBEAT_INTERVAL);
+    });
+    Ok(())
+}
+
+/// Standby side: returns the shared flag the output path gates on. A
+/// monitor thread sets it once the primary has been silent for `timeout`,
+/// driving the takeover GPIO on the way.
+pub fn spawn_standby_monitor(
+    port: u16,
+    takeover_gpio: Option<u8>,
+    timeout: Duration,
+) -> io::Result<Arc<AtomicBool>> {
+    let socket = UdpSocket::bind(("0.0.0.0", port))?;
+    socket.set_read_timeout(Some(HEARTBEAT_INTERVAL))?;
+    crate::log_info!("failover", "Failover standby: watching for heartbeats on port {}", port);
+
+    let active = Arc::new(AtomicBool::new(false));
+    let flag = active.clone();
+    std::thread::spawn(move || {
+        let mut last_heartbeat = Instant::now();
+        let mut buf = [0u8; 32];
+        loop {
+            match socket.recv_from(&mut buf) {
+                Ok((n, _)) if &buf[..n] == HEARTBEAT_MAGIC => {
+                    if flag.load(Ordering::Relaxed) {
+                        // Promoted already; note the return but hold the output.
+                        crate::log_info!("failover", "Primary heartbeat returned; staying active");
+                    }
+                    last_heartbeat = Instant::now();
+                }
+                _ => {}
+            }
+            if !flag.load(Ordering::Relaxed) && last_heartbeat.elapsed() > timeout {
+                crate::log_info!("failover",
+                    "Primary silent for {:?}, taking over the output",
+                    last_heartbeat.elapsed()
+                );
+                if let Some(pin) = takeover_gpio {
+                    set_takeover_gpio(pin);
+                }
+                flag.store(true, Ordering::Relaxed);
+            }
+        }
+    });
+    Ok(active)
+}
+
+/// Drive the PSU-relay / data-line-switch GPIO high through sysfs. Best
+/// effort: on a dev machine without /sys/class/gpio this just logs.
+fn set_takeover_gpio(pin: u8) {
+    let base = std::path::Path::new("/sys/class/gpio");
+    let pin_dir = base.join(format!("gpio{}", pin));
+    if !pin_dir.exists() {
+        if let Err(e) = std::fs::write(base.join("export"), pin.to_string()) {
+            crate::log_warn!("failover", "Cannot export GPIO {} (running without hardware?): {}", pin, e);
+            return;
+        }
+    }
+    let result = std::fs::write(pin_dir.join("direction"), "out")
+        .and_then(|_| std::fs::write(pin_dir.join("value"), "1"));
+    match result {
+        Ok(()) => crate::log_info!("failover", "Takeover GPIO {} driven high", pin),
+        Err(e) => crate::log_warn!("failover", "Cannot drive GPIO {}: {}", pin, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_roles() {
+        assert_eq!(
+            parse_failover("primary:10.0.0.2:7901").unwrap(),
+            FailoverRole::Primary { peer: "10.0.0.2:7901".to_string() }
+        );
+        assert_eq!(
+            parse_failover("standby:7901").unwrap(),
+            FailoverRole::Standby { port: 7901 }
+        );
+        assert!(parse_failover("primary:7901").is_err());
+        assert!(parse_failover("watcher:7901").is_err());
+    }
+
+    #[test]
+    fn standby_promotes_only_after_heartbeats_stop() {
+        let port = 47911;
+        let active = spawn_standby_monitor(port, None, Duration::from_millis(300)).unwrap();
+        let sender = UdpSocket::bind("0.0.0.0:0").unwrap();
+
+        // Keep the heartbeat alive past the timeout; no promotion.
+        for _ in 0..4 {
+            sender.send_to(HEARTBEAT_MAGIC, ("127.0.0.1", port)).unwrap();
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        assert!(!active.load(Ordering::Relaxed));
+
+        // Silence promotes the standby.
+        std::thread::sleep(Duration::from_millis(700));
+        assert!(active.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,140 @@
+//! Persistent per-effect favorite settings.
+//!
+//! Every time an operator tunes an effect through `set_idle_effect`
+//! (color, brightness), the values are remembered under that effect's
+//! name and written to `favorites` in the state dir. Re-selecting the
+//! effect — or restarting the controller — restores them, so effects
+//! don't need re-tuning after every switch. The file is a flat
+//! `effect.key = value` list in the same spirit as the config file.
+
+use std::io;
+use std::path::Path;
+
+use crate::config::parse_hex_color;
+use crate::frame::Pixel;
+
+/// The remembered tuning for one effect. Fields are optional so a
+/// favorite can hold just the values the operator actually set.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EffectFavorite {
+    pub color: Option<Pixel>,
+    /// Master brightness on the 0..=255 command scale.
+    pub brightness: Option<f64>,
+}
+
+/// All remembered effects, in first-seen order so the file stays diffable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Favorites {
+    entries: Vec<(String, EffectFavorite)>,
+}
+
+impl Favorites {
+    /// Parse the favorites file format. Malformed lines are skipped —
+    /// a corrupt favorites file should cost the tuning, not the boot.
+    pub fn parse(contents: &str) -> Self {
+        let mut favorites = Self::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let Some((effect, field)) = key.trim().split_once('.') else { continue };
+            let value = value.trim().trim_matches('"');
+            let entry = favorites.entry(effect.trim());
+            match field.trim() {
+                "color" => entry.color = parse_hex_color(value),
+                "brightness" => entry.brightness = value.parse().ok().filter(|v| (0.0..=255.0).contains(v)),
+                _ => {}
+            }
+        }
+        favorites
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        for (effect, favorite) in &self.entries {
+            if let Some(color) = favorite.color {
+                out.push_str(&format!(
+                    "{}.color = \"#{:02x}{:02x}{:02x}\"\n",
+                    effect, color.r, color.g, color.b
+                ));
+            }
+            if let Some(brightness) = favorite.brightness {
+                out.push_str(&format!("{}.brightness = {}\n", effect, brightness));
+            }
+        }
+        out
+    }
+
+    /// Load from disk; a missing or unreadable file is an empty set.
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.serialize())
+    }
+
+    pub fn get(&self, effect: &str) -> Option<&EffectFavorite> {
+        self.entries.iter().find(|(name, _)| name == effect).map(|(_, f)| f)
+    }
+
+    fn entry(&mut self, effect: &str) -> &mut EffectFavorite {
+        if !self.entries.iter().any(|(name, _)| name == effect) {
+            self.entries.push((effect.to_string(), EffectFavorite::default()));
+        }
+        let index = self.entries.iter().position(|(name, _)| name == effect).unwrap();
+        &mut self.entries[index].1
+    }
+
+    pub fn remember_color(&mut self, effect: &str, color: Pixel) {
+        self.entry(effect).color = Some(color);
+    }
+
+    pub fn remember_brightness(&mut self, effect: &str, value: f64) {
+        self.entry(effect).brightness = Some(value.clamp(0.0, 255.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialization_round_trips() {
+        let mut favorites = Favorites::default();
+        favorites.remember_color("rainbow", Pixel { r: 255, g: 136, b: 0 });
+        favorites.remember_brightness("rainbow", 200.0);
+        favorites.remember_color("solid", Pixel { r: 0, g: 64, b: 255 });
+        let parsed = Favorites::parse(&favorites.serialize());
+        assert_eq!(parsed, favorites);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let favorites = Favorites::parse(
+            "# comment\n\
+             rainbow.color = \"#ff8800\"\n\
+             not a line\n\
+             sparkle.brightness = plenty\n\
+             solid.sheen = \"glossy\"\n",
+        );
+        assert_eq!(
+            favorites.get("rainbow").and_then(|f| f.color),
+            Some(Pixel { r: 255, g: 136, b: 0 })
+        );
+        assert_eq!(favorites.get("sparkle").and_then(|f| f.brightness), None);
+    }
+
+    #[test]
+    fn remembering_overwrites_the_previous_value() {
+        let mut favorites = Favorites::default();
+        favorites.remember_brightness("breathing", 80.0);
+        favorites.remember_brightness("breathing", 120.0);
+        assert_eq!(favorites.get("breathing").unwrap().brightness, Some(120.0));
+    }
+}
@@ -0,0 +1,349 @@
+//! The binary frame protocol shared with the host.
+//!
+//! Every message is length-prefixed on the wire (handled by the
+//! transport); the payload starts with a version byte and a type byte.
+//! Full frames carry `<Version:1><Type:1><FrameID:4><Width:2><Height:2>`
+//! followed by pixel data whose encoding depends on the type byte (RGB,
+//! HSV, or palette-indexed), all little-endian. Version 2 inserts a
+//! host send timestamp `<HostTimestampUs:8>` (microseconds since the Unix
+//! epoch) between the header and the pixels, which the controller echoes
+//! back in stats for end-to-end latency measurement.
+
+use std::io;
+
+/// Message types shared with the host protocol.
+pub const MSG_TYPE_FRAME: u8 = 1;
+pub const MSG_TYPE_CONTROL: u8 = 2;
+/// Second frame stream, composited on top of the main one.
+pub const MSG_TYPE_OVERLAY: u8 = 3;
+/// Full frame carrying HSV triples instead of RGB; the controller does
+/// the color conversion so the host can animate in hue space cheaply.
+pub const MSG_TYPE_FRAME_HSV: u8 = 4;
+/// Palette-indexed frame: a 256-entry RGB palette followed by one index
+/// byte per pixel — a third of the bandwidth of rgb888.
+pub const MSG_TYPE_FRAME_PALETTE: u8 = 5;
+
+/// Bytes in the palette block of a palette-indexed frame (256 × RGB).
+pub const PALETTE_LEN: usize = 256 * 3;
+
+/// Region-of-interest update: a rectangle's pixels applied onto the
+/// retained frame, for senders that redraw a corner clock, not the wall.
+pub const MSG_TYPE_FRAME_ROI: u8 = 6;
+
+/// ROI header: `<Version:1><Type:1><FrameID:4><X:2><Y:2><W:2><H:2>`.
+pub const ROI_HEADER_LEN: usize = 14;
+
+/// Largest chain any frame header may claim. Guards the pixel allocation
+/// against hostile headers and is advertised in the capabilities
+/// handshake so senders can size panels without trial and error.
+pub const MAX_LED_COUNT: usize = 65_536;
+
+/// Size of the version-1 full-frame header in bytes.
+pub const FRAME_HEADER_LEN: usize = 10;
+/// Version-2 header: version 1 plus the 8-byte host timestamp.
+pub const FRAME_HEADER_V2_LEN: usize = FRAME_HEADER_LEN + 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pixel {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Pixel {
+    pub const BLACK: Pixel = Pixel { r: 0, g: 0, b: 0 };
+}
+
+/// HSV to RGB, all channels 0..=255. Hue wraps around the full byte range
+/// (so 0 and 255 are both red-ish), split into the usual six sectors.
+pub fn hsv_to_rgb(h: u8, s: u8, v: u8) -> Pixel {
+    let h = h as f64 / 255.0 * 6.0;
+    let s = s as f64 / 255.0;
+    let v = v as f64 / 255.0;
+    let sector = (h.floor() as usize) % 6;
+    let f = h - h.floor();
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+    let (r, g, b) = match sector {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+    Pixel {
+        r: (r * 255.0).round() as u8,
+        g: (g * 255.0).round() as u8,
+        b: (b * 255.0).round() as u8,
+    }
+}
+
+/// A decoded full frame.
+#[derive(Debug, Clone)]
+pub struct ParsedFrame {
+    pub version: u8,
+    pub frame_id: u32,
+    pub width: u16,
+    pub height: u16,
+    /// Host send time (µs since the Unix epoch); version 2 frames only.
+    pub host_timestamp_us: Option<u64>,
+    pub pixels: Vec<Pixel>,
+}
+
+/// Parses full-frame messages. Stateless; exists as a type so senders of
+/// other message kinds can't accidentally hit the frame path.
+pub struct FrameParser;
+
+impl FrameParser {
+    pub fn parse(frame_data: &[u8]) -> io::Result<ParsedFrame> {
+        if frame_data.len() < FRAME_HEADER_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Frame too short"));
+        }
+        let msg_type = frame_data[1];
+        if !matches!(
+            msg_type,
+            MSG_TYPE_FRAME | MSG_TYPE_OVERLAY | MSG_TYPE_FRAME_HSV | MSG_TYPE_FRAME_PALETTE
+        ) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a frame message"));
+        }
+
+        let version = frame_data[0];
+        let frame_id = u32::from_le_bytes([frame_data[2], frame_data[3], frame_data[4], frame_data[5]]);
+        let width = u16::from_le_bytes([frame_data[6], frame_data[7]]);
+        let height = u16::from_le_bytes([frame_data[8], frame_data[9]]);
+
+        let (host_timestamp_us, header_len) = if version >= 2 {
+            if frame_data.len() < FRAME_HEADER_V2_LEN {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Frame too short"));
+            }
+            let mut ts = [0u8; 8];
+            ts.copy_from_slice(&frame_data[FRAME_HEADER_LEN..FRAME_HEADER_V2_LEN]);
+            (Some(u64::from_le_bytes(ts)), FRAME_HEADER_V2_LEN)
+        } else {
+            (None, FRAME_HEADER_LEN)
+        };
+
+        let pixel_data = &frame_data[header_len..];
+        let expected_pixels = width as usize * height as usize;
+        if expected_pixels > MAX_LED_COUNT {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Frame dimensions too large"));
+        }
+        let pixels = match msg_type {
+            MSG_TYPE_FRAME_HSV => {
+                if pixel_data.len() < expected_pixels * 3 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Insufficient pixel data"));
+                }
+                pixel_data[..expected_pixels * 3]
+                    .chunks_exact(3)
+                    .map(|c| hsv_to_rgb(c[0], c[1], c[2]))
+                    .collect()
+            }
+            MSG_TYPE_FRAME_PALETTE => {
+                if pixel_data.len() < PALETTE_LEN + expected_pixels {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Insufficient pixel data"));
+                }
+                let (palette, indices) = pixel_data.split_at(PALETTE_LEN);
+                indices[..expected_pixels]
+                    .iter()
+                    .map(|&i| {
+                        let at = i as usize * 3;
+                        Pixel { r: palette[at], g: palette[at + 1], b: palette[at + 2] }
+                    })
+                    .collect()
+            }
+            _ => {
+                if pixel_data.len() < expected_pixels * 3 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Insufficient pixel data"));
+                }
+                pixel_data[..expected_pixels * 3]
+                    .chunks_exact(3)
+                    .map(|c| Pixel { r: c[0], g: c[1], b: c[2] })
+                    .collect()
+            }
+        };
+
+        Ok(ParsedFrame {
+            version,
+            frame_id,
+            width,
+            height,
+            host_timestamp_us,
+            pixels,
+        })
+    }
+}
+
+/// A decoded region-of-interest update. Coordinates are in grid space;
+/// validation against the configured grid happens where it is applied.
+#[derive(Debug, Clone)]
+pub struct ParsedRoi {
+    pub frame_id: u32,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub pixels: Vec<Pixel>,
+}
+
+/// Parses region-of-interest messages.
+pub struct RoiParser;
+
+impl RoiParser {
+    pub fn parse(data: &[u8]) -> io::Result<ParsedRoi> {
+        if data.len() < ROI_HEADER_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "ROI frame too short"));
+        }
+        if data[1] != MSG_TYPE_FRAME_ROI {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not an ROI message"));
+        }
+        let frame_id = u32::from_le_bytes([data[2], data[3], data[4], data[5]]);
+        let x = u16::from_le_bytes([data[6], data[7]]);
+        let y = u16::from_le_bytes([data[8], data[9]]);
+        let width = u16::from_le_bytes([data[10], data[11]]);
+        let height = u16::from_le_bytes([data[12], data[13]]);
+        let pixel_data = &data[ROI_HEADER_LEN..];
+        let expected = width as usize * height as usize;
+        if pixel_data.len() < expected * 3 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Insufficient pixel data"));
+        }
+        let pixels = pixel_data[..expected * 3]
+            .chunks_exact(3)
+            .map(|c| Pixel { r: c[0], g: c[1], b: c[2] })
+            .collect();
+        Ok(ParsedRoi { frame_id, x, y, width, height, pixels })
+    }
+}
+
+/// Extract a frame payload's pixel data as raw RGB, e.g. for thumbnails
+/// of recorded sessions.
+pub fn frame_payload_rgb(payload: &[u8]) -> Option<(usize, usize, Vec<u8>)> {
+    if payload.len() < FRAME_HEADER_LEN || payload[1] != MSG_TYPE_FRAME {
+        return None;
+    }
+    let width = u16::from_le_bytes([payload[6], payload[7]]) as usize;
+    let height = u16::from_le_bytes([payload[8], payload[9]]) as usize;
+    let header_len = if payload[0] >= 2 { FRAME_HEADER_V2_LEN } else { FRAME_HEADER_LEN };
+    let need = width * height * 3;
+    let pixel_data = payload.get(header_len..header_len + need)?;
+    Some((width, height, pixel_data.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_bytes(width: u16, height: u16, rgb: &[u8]) -> Vec<u8> {
+        let mut data = vec![1, MSG_TYPE_FRAME, 42, 0, 0, 0];
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(rgb);
+        data
+    }
+
+    #[test]
+    fn parses_a_full_frame() {
+        let data = frame_bytes(2, 1, &[255, 0, 0, 0, 0, 255]);
+        let frame = FrameParser::parse(&data).unwrap();
+        assert_eq!(frame.version, 1);
+        assert_eq!(frame.frame_id, 42);
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_eq!(frame.pixels[0], Pixel { r: 255, g: 0, b: 0 });
+        assert_eq!(frame.pixels[1], Pixel { r: 0, g: 0, b: 255 });
+    }
+
+    #[test]
+    fn version_2_carries_a_host_timestamp() {
+        let mut data = vec![2, MSG_TYPE_FRAME, 7, 0, 0, 0, 1, 0, 1, 0];
+        data.extend_from_slice(&123_456_789u64.to_le_bytes());
+        data.extend_from_slice(&[9, 8, 7]);
+        let frame = FrameParser::parse(&data).unwrap();
+        assert_eq!(frame.host_timestamp_us, Some(123_456_789));
+        assert_eq!(frame.pixels[0], Pixel { r: 9, g: 8, b: 7 });
+
+        // A version-1 frame has no timestamp, and a truncated version-2
+        // header is rejected.
+        let v1 = frame_bytes(1, 1, &[1, 2, 3]);
+        assert_eq!(FrameParser::parse(&v1).unwrap().host_timestamp_us, None);
+        assert!(FrameParser::parse(&data[..12]).is_err());
+    }
+
+    #[test]
+    fn hsv_frames_convert_to_rgb() {
+        // Hue 0 full-saturation is red; zero saturation is grey at v.
+        let mut data = vec![1, MSG_TYPE_FRAME_HSV, 0, 0, 0, 0, 2, 0, 1, 0];
+        data.extend_from_slice(&[0, 255, 255, 0, 0, 128]);
+        let frame = FrameParser::parse(&data).unwrap();
+        assert_eq!(frame.pixels[0], Pixel { r: 255, g: 0, b: 0 });
+        assert_eq!(frame.pixels[1], Pixel { r: 128, g: 128, b: 128 });
+
+        // A third of the way around the wheel lands on green.
+        let p = hsv_to_rgb(85, 255, 255);
+        assert!(p.g == 255 && p.r < 10 && p.b < 10, "got {:?}", p);
+    }
+
+    #[test]
+    fn palette_frames_look_up_indices() {
+        let mut data = vec![1, MSG_TYPE_FRAME_PALETTE, 0, 0, 0, 0, 2, 0, 1, 0];
+        let mut palette = vec![0u8; PALETTE_LEN];
+        palette[3..6].copy_from_slice(&[10, 20, 30]); // entry 1
+        data.extend_from_slice(&palette);
+        data.extend_from_slice(&[1, 0]);
+        let frame = FrameParser::parse(&data).unwrap();
+        assert_eq!(frame.pixels[0], Pixel { r: 10, g: 20, b: 30 });
+        assert_eq!(frame.pixels[1], Pixel::BLACK);
+
+        // Truncating the palette block is an error, not a short read.
+        assert!(FrameParser::parse(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn roi_frames_carry_a_rectangle() {
+        let mut data = vec![1, MSG_TYPE_FRAME_ROI, 5, 0, 0, 0];
+        data.extend_from_slice(&3u16.to_le_bytes()); // x
+        data.extend_from_slice(&1u16.to_le_bytes()); // y
+        data.extend_from_slice(&2u16.to_le_bytes()); // w
+        data.extend_from_slice(&1u16.to_le_bytes()); // h
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        let roi = RoiParser::parse(&data).unwrap();
+        assert_eq!((roi.x, roi.y, roi.width, roi.height), (3, 1, 2, 1));
+        assert_eq!(roi.pixels[1], Pixel { r: 4, g: 5, b: 6 });
+
+        assert!(RoiParser::parse(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_dimensions() {
+        // 60000 x 60000 would be a 10 GB allocation; the header is
+        // rejected before the pixel data is even looked at.
+        let data = frame_bytes(60_000, 60_000, &[]);
+        assert!(FrameParser::parse(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_short_frames() {
+        assert!(FrameParser::parse(&[1, MSG_TYPE_FRAME, 0]).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_message_type() {
+        let mut data = frame_bytes(1, 1, &[0, 0, 0]);
+        data[1] = MSG_TYPE_CONTROL;
+        assert!(FrameParser::parse(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_pixel_data() {
+        let data = frame_bytes(2, 2, &[255, 0, 0]);
+        assert!(FrameParser::parse(&data).is_err());
+    }
+
+    #[test]
+    fn extracts_raw_rgb_from_payload() {
+        let data = frame_bytes(1, 1, &[7, 8, 9]);
+        let (w, h, rgb) = frame_payload_rgb(&data).unwrap();
+        assert_eq!((w, h), (1, 1));
+        assert_eq!(rgb, vec![7, 8, 9]);
+    }
+}
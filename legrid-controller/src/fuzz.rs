@@ -0,0 +1,66 @@
+//! Fuzzing entry points (feature `fuzz-entry`).
+//!
+//! Thin wrappers over the input-handling surface — the frame parser, the
+//! message dispatcher, and the config parsers — shaped for cargo-fuzz/AFL
+//! harnesses: take arbitrary bytes, never panic by contract, return
+//! nothing. The controller behind the dispatcher runs the mock driver, so
+//! no hardware, sockets, or files are touched; anything a command would
+//! print to stdout is harmless noise under a fuzzer.
+
+use crate::config::Config;
+use crate::controller::{dispatch_message, LEDController};
+use crate::frame::FrameParser;
+
+/// A controller suitable for fuzzing: mock driver, small grid, no optional
+/// subsystems.
+fn harness_controller() -> LEDController {
+    let mut config = Config::defaults();
+    config.width = 16;
+    config.height = 16;
+    config.led_count = 256;
+    LEDController::new(config).expect("mock-driver controller must construct")
+}
+
+/// Feed one message payload (version byte onward, no length prefix)
+/// through the full dispatch path: frame decode, overlay, control-command
+/// JSON. Errors are the expected outcome for most inputs; panics are bugs.
+pub fn fuzz_message(data: &[u8]) {
+    let mut controller = harness_controller();
+    let _ = dispatch_message(&mut controller, data);
+}
+
+/// Feed arbitrary bytes through the frame parser alone, which is the
+/// hottest decode path and the one most often extended.
+pub fn fuzz_frame(data: &[u8]) {
+    let _ = FrameParser::parse(data);
+}
+
+/// Feed arbitrary bytes through every content decoder and the resampler.
+/// The decoders are driven directly rather than through `load_content`,
+/// which keeps the file system out of the loop; whichever decoder accepts
+/// the input, its output is resampled the way watch mode would.
+pub fn fuzz_content(data: &[u8]) {
+    for decoded in [
+        crate::content::decode_ppm(data).ok(),
+        crate::content::decode_bmp(data).ok(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let (w, h, rgb) = decoded;
+        let _ = crate::content::resample_to_grid(&rgb, w, h, 16, 16);
+    }
+    if let Ok((w, h, frames)) = crate::content::decode_gif(data) {
+        for (rgb, _delay) in &frames {
+            let _ = crate::content::resample_to_grid(rgb, w, h, 16, 16);
+        }
+    }
+}
+
+/// Feed arbitrary bytes through the TOML-subset config parser.
+pub fn fuzz_config(data: &[u8]) {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let mut config = Config::defaults();
+        let _ = crate::config::apply_config_file(&mut config, text);
+    }
+}
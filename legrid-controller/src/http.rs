@@ -0,0 +1,264 @@
+//! HTTP upload endpoint for content files.
+//!
+//! A deliberately small HTTP/1.1 server on std::net: enough to accept
+//! authenticated uploads into the content directory, list what's stored,
+//! and serve PNG thumbnails. Not a general web server.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::content::thumbnail_png;
+
+/// Everything watch mode can display, plus plain-text sidecars for future
+/// cue lists.
+pub const UPLOAD_EXTENSIONS: &[&str] = &["gif", "ppm", "bmp", "cue", "txt"];
+
+pub const UPLOAD_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+pub struct UploadServer {
+    pub port: u16,
+    pub token: String,
+    pub content_dir: PathBuf,
+}
+
+impl UploadServer {
+    pub fn spawn(self) -> io::Result<()> {
+        let listener = match crate::systemd::activated_listener(self.port) {
+            Some(listener) => listener,
+            None => TcpListener::bind(("0.0.0.0", self.port))?,
+        };
+        crate::log_info!("http", "Upload endpoint listening on port {} (content dir {})",
+                  self.port, self.content_dir.display());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                if let Err(e) = self.handle_client(stream) {
+                    crate::log_warn!("http", "Upload request failed: {}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn handle_client(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+        // Read the request head (request line + headers).
+        let mut head = Vec::new();
+        let mut byte = [0u8; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            if head.len() > 16 * 1024 {
+                return http_respond(&mut stream, 431, "{\"error\":\"headers too large\"}");
+            }
+            match stream.read(&mut byte) {
+                Ok(1) => head.push(byte[0]),
+                _ => return Ok(()),
+            }
+        }
+        let head = String::from_utf8_lossy(&head).to_string();
+        let mut lines = head.lines();
+        let request_line = lines.next().unwrap_or_default();
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+
+        let mut content_length = 0usize;
+        let mut auth = String::new();
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else { continue };
+            match name.to_ascii_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                "authorization" => auth = value.trim().to_string(),
+                _ => {}
+            }
+        }
+
+        // The API description is served without the token, so explorers
+        // and client generators can bootstrap before they have one.
+        if method == "GET" && path == "/openapi.json" {
+            return http_respond(&mut stream, 200, &crate::openapi::openapi_json());
+        }
+
+        if auth != format!("Bearer {}", self.token) {
+            return http_respond(&mut stream, 401, "{\"error\":\"missing or invalid token\"}");
+        }
+
+        // GET /content lists stored files with thumbnail links.
+        if method == "GET" && path == "/content" {
+            let mut entries = Vec::new();
+            if let Ok(read_dir) = std::fs::read_dir(&self.content_dir) {
+                for entry in read_dir.flatten() {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if name.starts_with('.') {
+                        continue;
+                    }
+                    let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    entries.push(format!(
+                        "{{\"name\":\"{}\",\"bytes\":{},\"thumb\":\"/thumb/{}\"}}",
+                        name, bytes, name
+                    ));
+                }
+            }
+            return http_respond(&mut stream, 200, &format!("[{}]", entries.join(",")));
+        }
+
+        // GET /thumb/<filename> returns a PNG preview of the first frame.
+        if method == "GET" {
+            if let Some(filename) = path.strip_prefix("/thumb/") {
+                if filename.is_empty() || filename.contains('/') || filename.contains("..") {
+                    return http_respond(&mut stream, 400, "{\"error\":\"invalid filename\"}");
+                }
+                // The decoders validate their inputs, but every request
+                // shares this one listener thread — if one slips through
+                // on a pathological file, answer 415 rather than letting
+                // the unwind take the whole upload server with it.
+                let file = self.content_dir.join(filename);
+                let thumb = std::panic::catch_unwind(|| thumbnail_png(&file))
+                    .unwrap_or_else(|_| {
+                        Err(io::Error::new(io::ErrorKind::InvalidData, "failed to decode"))
+                    });
+                return match thumb {
+                    Ok(png) => http_respond_bytes(&mut stream, 200, "image/png", &png),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                        http_respond(&mut stream, 404, "{\"error\":\"no such file\"}")
+                    }
+                    Err(e) => http_respond(
+                        &mut stream,
+                        415,
+                        &format!("{{\"error\":\"{}\"}}", e),
+                    ),
+                };
+            }
+        }
+
+        if method != "PUT" && method != "POST" {
+            return http_respond(&mut stream, 405, "{\"error\":\"use PUT or POST\"}");
+        }
+
+        // Expect /content/<filename>; reject anything that could escape
+        // the content directory.
+        let Some(filename) = path.strip_prefix("/content/") else {
+            return http_respond(&mut stream, 404, "{\"error\":\"unknown path\"}");
+        };
+        if filename.is_empty() || filename.contains('/') || filename.contains("..") || filename.contains('\\') {
+            return http_respond(&mut stream, 400, "{\"error\":\"invalid filename\"}");
+        }
+        let ext = filename.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+        if !UPLOAD_EXTENSIONS.contains(&ext.as_str()) {
+            return http_respond(&mut stream, 415, "{\"error\":\"unsupported file type\"}");
+        }
+        if content_length == 0 || content_length > UPLOAD_MAX_BYTES {
+            return http_respond(&mut stream, 413, "{\"error\":\"missing or oversized body\"}");
+        }
+
+        let mut body = vec![0u8; content_length];
+        stream.read_exact(&mut body)?;
+
+        // Write to a temp name first so watch mode never picks up a
+        // half-written file.
+        std::fs::create_dir_all(&self.content_dir)?;
+        let final_path = self.content_dir.join(filename);
+        let tmp_path = self.content_dir.join(format!(".{}.uploading", filename));
+        std::fs::write(&tmp_path, &body)?;
+        std::fs::rename(&tmp_path, &final_path)?;
+
+        crate::log_info!("http", "Upload accepted: {} ({} bytes)", final_path.display(), content_length);
+        http_respond(
+            &mut stream,
+            200,
+            &format!("{{\"stored\":\"{}\",\"bytes\":{}}}", filename, content_length),
+        )
+    }
+}
+
+/// The frame most recently pushed to the panel, shared between the
+/// output path and the snapshot endpoint.
+pub type SharedFrame = std::sync::Arc<std::sync::Mutex<(u16, u16, Vec<crate::frame::Pixel>)>>;
+
+/// Live preview endpoint: `GET /snapshot.png` returns the currently
+/// displayed buffer encoded as PNG, for dashboards without a camera.
+pub struct SnapshotServer {
+    pub port: u16,
+    pub latest: SharedFrame,
+}
+
+impl SnapshotServer {
+    pub fn spawn(self) -> io::Result<()> {
+        let listener = match crate::systemd::activated_listener(self.port) {
+            Some(listener) => listener,
+            None => TcpListener::bind(("0.0.0.0", self.port))?,
+        };
+        crate::log_info!("http", "Snapshot endpoint listening on port {}", self.port);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+                // Only the request line matters; drain the head quietly.
+                let mut head = Vec::new();
+                let mut byte = [0u8; 1];
+                while !head.ends_with(b"\r\n\r\n") && head.len() < 16 * 1024 {
+                    match stream.read(&mut byte) {
+                        Ok(1) => head.push(byte[0]),
+                        _ => break,
+                    }
+                }
+                let request_line = String::from_utf8_lossy(&head);
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let result = if request_line.starts_with("GET")
+                    && (path == "/snapshot.png" || path == "/")
+                {
+                    let (width, height, pixels) = self.latest.lock().unwrap().clone();
+                    let mut rgb = Vec::with_capacity(pixels.len() * 3);
+                    for p in &pixels {
+                        rgb.extend_from_slice(&[p.r, p.g, p.b]);
+                    }
+                    let png = crate::png::encode_png(width as usize, height as usize, &rgb);
+                    http_respond_bytes(&mut stream, 200, "image/png", &png)
+                } else {
+                    http_respond(&mut stream, 404, "{\"error\":\"not found\"}")
+                };
+                if let Err(e) = result {
+                    crate::log_warn!("http", "Snapshot request failed: {}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+pub fn http_respond_bytes(
+    stream: &mut TcpStream,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    let reason = if status == 200 { "OK" } else { "Error" };
+    let header = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, reason, content_type, body.len()
+    );
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(body)
+}
+
+pub fn http_respond(stream: &mut TcpStream, status: u16, body: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        431 => "Request Header Fields Too Large",
+        _ => "Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason, body.len(), body
+    );
+    stream.write_all(response.as_bytes())
+}
@@ -0,0 +1,320 @@
+//! GPIO input events: physical buttons and rotary encoders on the frame.
+//!
+//! `--inputs "button:17;button:27;encoder:22,23"` names the wired pins;
+//! a monitor thread polls them through sysfs, debounces, and reports
+//! typed events upstream over the stats channel
+//! (`{"type":"input","event":"press","pin":17}`), so the host can switch
+//! patterns from the hardware without its own GPIO access. Buttons are
+//! assumed active-low behind a pull-up, the usual wiring for a bare
+//! switch to ground.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::transport::send_message;
+
+/// Edges closer together than this are contact bounce, not presses.
+const DEBOUNCE: Duration = Duration::from_millis(10);
+/// Holding past this emits a long-press instead of a press.
+const LONG_PRESS: Duration = Duration::from_millis(800);
+/// Sysfs poll cadence; fast enough for a hand-turned encoder.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// One configured input device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSpec {
+    Button { pin: u8 },
+    /// A quadrature encoder on two pins (the A and B phases).
+    Encoder { a: u8, b: u8 },
+}
+
+/// Parse `kind:pin[,pin]` entries separated by ';'.
+pub fn parse_inputs(spec: &str) -> Result<Vec<InputSpec>, String> {
+    let mut inputs = Vec::new();
+    for part in spec.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let bad = || format!("input '{}': expected button:pin or encoder:pinA,pinB", part);
+        let (kind, pins) = part.split_once(':').ok_or_else(bad)?;
+        match kind.trim() {
+            "button" => {
+                let pin: u8 = pins.trim().parse().map_err(|_| bad())?;
+                inputs.push(InputSpec::Button { pin });
+            }
+            "encoder" => {
+                let (a, b) = pins.split_once(',').ok_or_else(bad)?;
+                inputs.push(InputSpec::Encoder {
+                    a: a.trim().parse().map_err(|_| bad())?,
+                    b: b.trim().parse().map_err(|_| bad())?,
+                });
+            }
+            _ => return Err(bad()),
+        }
+    }
+    if inputs.is_empty() {
+        return Err("input spec is empty".to_string());
+    }
+    Ok(inputs)
+}
+
+/// What a button did, decided at the debounced edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+    Press,
+    LongPress,
+}
+
+/// Debounce and press/long-press classification for one button. Pure so
+/// the timing rules are testable with a fake clock; the monitor thread
+/// feeds it levels.
+#[derive(Debug, Default)]
+pub struct ButtonTracker {
+    pressed: bool,
+    changed_at: Option<Instant>,
+    long_sent: bool,
+}
+
+impl ButtonTracker {
+    /// Feed the current (already polarity-corrected) level. A short press
+    /// reports on release, so it can still become a long-press while
+    /// held; a long-press reports the moment the threshold passes.
+    pub fn poll(&mut self, pressed: bool, now: Instant) -> Option<ButtonEvent> {
+        if pressed != self.pressed {
+            if self
+                .changed_at
+                .is_some_and(|t| now.duration_since(t) < DEBOUNCE)
+            {
+                return None; // bounce; keep the accepted state
+            }
+            self.pressed = pressed;
+            self.changed_at = Some(now);
+            if pressed {
+                self.long_sent = false;
+                return None;
+            }
+            return if self.long_sent { None } else { Some(ButtonEvent::Press) };
+        }
+        if self.pressed
+            && !self.long_sent
+            && self
+                .changed_at
+                .is_some_and(|t| now.duration_since(t) >= LONG_PRESS)
+        {
+            self.long_sent = true;
+            return Some(ButtonEvent::LongPress);
+        }
+        None
+    }
+}
+
+/// Gray-code transitions of the quadrature state (prev A,B -> new A,B):
+/// +1 for one quarter-step clockwise, -1 counter-clockwise, 0 for no
+/// movement or an illegal two-bit jump (noise).
+const QUADRATURE: [i32; 16] = [0, 1, -1, 0, -1, 0, 0, 1, 1, 0, 0, -1, 0, -1, 1, 0];
+
+/// Decodes a quadrature encoder into whole detents (four quarter-steps).
+#[derive(Debug, Default)]
+pub struct EncoderTracker {
+    prev: u8,
+    quarters: i32,
+}
+
+impl EncoderTracker {
+    /// Feed the current phase levels; returns the detents completed by
+    /// this transition (-1, 0, or 1).
+    pub fn poll(&mut self, a: bool, b: bool) -> i32 {
+        let state = (a as u8) << 1 | b as u8;
+        let step = QUADRATURE[(self.prev << 2 | state) as usize];
+        self.prev = state;
+        self.quarters += step;
+        let detents = self.quarters / 4;
+        self.quarters -= detents * 4;
+        detents
+    }
+}
+
+/// Export a pin for input through sysfs and return its value file. Best
+/// effort, like the failover takeover pin: on a dev machine without
+/// /sys/class/gpio this logs and the input is skipped.
+fn export_input_pin(pin: u8) -> Option<PathBuf> {
+    let base = std::path::Path::new("/sys/class/gpio");
+    let pin_dir = base.join(format!("gpio{}", pin));
+    if !pin_dir.exists() {
+        if let Err(e) = std::fs::write(base.join("export"), pin.to_string()) {
+            crate::log_warn!("input", "Cannot export GPIO {} (running without hardware?): {}", pin, e);
+            return None;
+        }
+    }
+    if let Err(e) = std::fs::write(pin_dir.join("direction"), "in") {
+        crate::log_warn!("input", "Cannot configure GPIO {} as input: {}", pin, e);
+        return None;
+    }
+    Some(pin_dir.join("value"))
+}
+
+/// Sysfs '0' is pressed for an active-low button.
+fn read_level(path: &PathBuf) -> Option<bool> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    Some(raw.trim() == "1")
+}
+
+/// Start the polling thread for the configured inputs. Events go
+/// upstream as they happen; the thread runs for the life of the process.
+/// The returned latch flips on any button press (and is cleared by its
+/// consumer), so local features like alarm dismissal work without the
+/// host round-tripping the event.
+pub fn spawn_input_monitor(
+    specs: Vec<InputSpec>,
+) -> io::Result<std::sync::Arc<std::sync::atomic::AtomicBool>> {
+    enum Device {
+        Button { pin: u8, value: PathBuf, tracker: ButtonTracker },
+        Encoder { pin: u8, value_a: PathBuf, value_b: PathBuf, tracker: EncoderTracker },
+    }
+
+    let mut devices = Vec::new();
+    for spec in &specs {
+        match *spec {
+            InputSpec::Button { pin } => {
+                if let Some(value) = export_input_pin(pin) {
+                    devices.push(Device::Button { pin, value, tracker: ButtonTracker::default() });
+                }
+            }
+            InputSpec::Encoder { a, b } => {
+                if let (Some(value_a), Some(value_b)) = (export_input_pin(a), export_input_pin(b)) {
+                    devices.push(Device::Encoder {
+                        pin: a,
+                        value_a,
+                        value_b,
+                        tracker: EncoderTracker::default(),
+                    });
+                }
+            }
+        }
+    }
+    let pressed = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    if devices.is_empty() {
+        crate::log_warn!("input", "No usable input pins out of {} configured", specs.len());
+        return Ok(pressed);
+    }
+    crate::log_info!("input", "Watching {} GPIO input(s)", devices.len());
+
+    let latch = pressed.clone();
+    std::thread::spawn(move || loop {
+        let now = Instant::now();
+        for device in devices.iter_mut() {
+            match device {
+                Device::Button { pin, value, tracker } => {
+                    let Some(level) = read_level(value) else { continue };
+                    let event = match tracker.poll(!level, now) {
+                        Some(ButtonEvent::Press) => "press",
+                        Some(ButtonEvent::LongPress) => "long_press",
+                        None => continue,
+                    };
+                    latch.store(true, std::sync::atomic::Ordering::Relaxed);
+                    let msg = format!("{{\"type\":\"input\",\"event\":\"{}\",\"pin\":{}}}", event, pin);
+                    if let Err(e) = send_message(&msg) {
+                        crate::log_warn!("input", "Error sending input event: {}", e);
+                    }
+                }
+                Device::Encoder { pin, value_a, value_b, tracker } => {
+                    let (Some(a), Some(b)) = (read_level(value_a), read_level(value_b)) else {
+                        continue;
+                    };
+                    let delta = tracker.poll(a, b);
+                    if delta == 0 {
+                        continue;
+                    }
+                    let msg = format!(
+                        "{{\"type\":\"input\",\"event\":\"rotate\",\"pin\":{},\"delta\":{}}}",
+                        pin, delta
+                    );
+                    if let Err(e) = send_message(&msg) {
+                        crate::log_warn!("input", "Error sending input event: {}", e);
+                    }
+                }
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+    Ok(pressed)
+}
+
+/// Watch a UPS "power failing" pin (sysfs '1' means the mains is gone)
+/// and latch the returned flag when it fires. The main loop polls the
+/// flag and runs the safe-shutdown path; the monitor itself only ever
+/// reads, so it cannot make things worse on a dying supply.
+pub fn spawn_power_loss_monitor(pin: u8) -> std::sync::Arc<std::sync::atomic::AtomicBool> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let Some(value) = export_input_pin(pin) else {
+        return flag;
+    };
+    crate::log_info!("input", "Watching GPIO {} for power-loss signal", pin);
+    let watched = flag.clone();
+    std::thread::spawn(move || loop {
+        if read_level(&value) == Some(true) {
+            crate::log_error!("input", "Power-loss signal on GPIO {}", pin);
+            watched.store(true, Ordering::Relaxed);
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    });
+    flag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_buttons_and_encoders() {
+        let inputs = parse_inputs("button:17; encoder:22,23").unwrap();
+        assert_eq!(inputs[0], InputSpec::Button { pin: 17 });
+        assert_eq!(inputs[1], InputSpec::Encoder { a: 22, b: 23 });
+        assert!(parse_inputs("button:").is_err());
+        assert!(parse_inputs("dial:5").is_err());
+        assert!(parse_inputs("encoder:22").is_err());
+    }
+
+    #[test]
+    fn button_debounces_and_classifies_presses() {
+        let mut button = ButtonTracker::default();
+        let t0 = Instant::now();
+
+        // Press, bounce inside the debounce window, release: one press.
+        assert_eq!(button.poll(true, t0), None);
+        assert_eq!(button.poll(false, t0 + Duration::from_millis(2)), None);
+        assert_eq!(
+            button.poll(false, t0 + Duration::from_millis(100)),
+            Some(ButtonEvent::Press)
+        );
+
+        // Held past the threshold: a long-press fires once, and the
+        // release afterwards stays silent.
+        let t1 = t0 + Duration::from_secs(10);
+        assert_eq!(button.poll(true, t1), None);
+        assert_eq!(button.poll(true, t1 + LONG_PRESS), Some(ButtonEvent::LongPress));
+        assert_eq!(button.poll(true, t1 + LONG_PRESS * 2), None);
+        assert_eq!(button.poll(false, t1 + LONG_PRESS * 3), None);
+    }
+
+    #[test]
+    fn encoder_counts_whole_detents() {
+        let mut encoder = EncoderTracker::default();
+        // One clockwise detent: 00 -> 01 -> 11 -> 10 -> 00.
+        assert_eq!(encoder.poll(false, true), 0);
+        assert_eq!(encoder.poll(true, true), 0);
+        assert_eq!(encoder.poll(true, false), 0);
+        assert_eq!(encoder.poll(false, false), 1);
+        // And back the other way.
+        assert_eq!(encoder.poll(true, false), 0);
+        assert_eq!(encoder.poll(true, true), 0);
+        assert_eq!(encoder.poll(false, true), 0);
+        assert_eq!(encoder.poll(false, false), -1);
+    }
+}
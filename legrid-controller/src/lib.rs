@@ -0,0 +1,70 @@
+//! Local LED controller for the legrid server.
+//!
+//! The host (the Elixir side, or any other sender) streams length-prefixed
+//! binary frames over a transport; this crate parses them, runs them
+//! through the pixel pipeline, and hands them to an output driver. The
+//! `local_controller` binary wires the pieces together; everything else is
+//! reusable from here.
+
+pub mod alarm;
+pub mod alloc_stats;
+pub mod ambient;
+pub mod audio;
+pub mod beat;
+pub mod bench;
+pub mod calibrate;
+pub mod camera;
+pub mod chain;
+pub mod config;
+pub mod content;
+pub mod controller;
+pub mod current;
+pub mod degrade;
+pub mod departures;
+pub mod driver;
+pub mod effects;
+pub mod failover;
+pub mod favorites;
+pub mod frame;
+#[cfg(feature = "fuzz-entry")]
+pub mod fuzz;
+pub mod http;
+pub mod input;
+#[cfg(feature = "ableton-link")]
+pub mod link;
+pub mod log;
+pub mod metrics;
+pub mod modifiers;
+pub mod mqtt;
+pub mod notify;
+pub mod openapi;
+pub mod overlay;
+pub mod pacing;
+pub mod palette;
+pub mod pipeline;
+pub mod png;
+pub mod profiles;
+pub mod record;
+pub mod run;
+pub mod shuffle;
+pub mod simulate;
+pub mod solar;
+pub mod splash;
+pub mod status;
+pub mod systemd;
+pub mod text;
+pub mod thermal;
+pub mod tiling;
+pub mod transcode;
+pub mod transitions;
+pub mod transport;
+pub mod watermark;
+pub mod weather;
+pub mod webhook;
+pub mod widget;
+
+pub use config::Config;
+pub use controller::LEDController;
+pub use driver::{DriverKind, LedDriver};
+pub use frame::{FrameParser, ParsedFrame, Pixel};
+pub use pipeline::{ColorOrder, InterpolateMode, PixelPipeline};
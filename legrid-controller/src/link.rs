@@ -0,0 +1,70 @@
+//! Ableton Link session membership (feature `ableton-link`).
+//!
+//! Joins the Link session on the local network and mirrors its tempo and
+//! beat phase into the controller's own beat clock, so the idle effects
+//! stay phase-aligned with whatever DJ or live-performance software is
+//! driving the room. The bridge is deliberately thin: it owns the Link
+//! instance on its own thread and speaks to the controller through the
+//! same `beat` control messages MQTT and the host use, so the
+//! [`crate::beat::BeatClock`] stays the single source of musical time.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use rusty_link::{AblLink, SessionState};
+
+use crate::frame::MSG_TYPE_CONTROL;
+
+/// Bar length used when asking Link for the beat phase. The beat clock
+/// only needs within-beat phase, so any quantum works; one beat keeps
+/// the numbers small.
+const QUANTUM: f64 = 1.0;
+/// How often the session state is polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Phase is re-announced this often even when the tempo holds steady, so
+/// the beat clock can't drift from the session between tempo changes.
+const RESYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Run the Link bridge on its own thread: join the session and relay
+/// tempo/phase into `tx` as `beat` control messages until the controller
+/// goes away.
+pub fn spawn_link_bridge(tx: mpsc::Sender<Vec<u8>>) {
+    std::thread::spawn(move || {
+        let link = AblLink::new(120.0);
+        link.enable(true);
+        crate::log_info!("link", "Joining Ableton Link session");
+
+        let mut state = SessionState::new();
+        let mut last_tempo = 0.0_f64;
+        let mut last_peers: Option<u64> = None;
+        let mut since_resync = Duration::ZERO;
+        loop {
+            link.capture_app_session_state(&mut state);
+            let peers = link.num_peers();
+            if last_peers != Some(peers) {
+                crate::log_info!("link", "Link session has {} peer(s)", peers);
+                last_peers = Some(peers);
+            }
+
+            let tempo = state.tempo();
+            if (tempo - last_tempo).abs() > 0.01 || since_resync >= RESYNC_INTERVAL {
+                let phase = state.phase_at_time(link.clock_micros(), QUANTUM);
+                let json = format!(
+                    "{{\"command\":\"beat\",\"bpm\":{},\"phase\":{}}}",
+                    tempo, phase
+                );
+                let mut msg = vec![1u8, MSG_TYPE_CONTROL];
+                msg.extend_from_slice(json.as_bytes());
+                if tx.send(msg).is_err() {
+                    break; // controller is gone
+                }
+                last_tempo = tempo;
+                since_resync = Duration::ZERO;
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+            since_resync += POLL_INTERVAL;
+        }
+        link.enable(false);
+    });
+}
@@ -0,0 +1,25 @@
+//! Thin binary around the legrid_controller library: installs the counting
+//! global allocator (wrapping the build's chosen base allocator) and hands
+//! off to the library's run loop.
+
+use std::io;
+
+use legrid_controller::alloc_stats::CountingAllocator;
+
+#[cfg(all(not(feature = "jemalloc"), not(feature = "mimalloc")))]
+#[global_allocator]
+static GLOBAL: CountingAllocator<std::alloc::System> = CountingAllocator::new(std::alloc::System);
+
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: CountingAllocator<tikv_jemallocator::Jemalloc> =
+    CountingAllocator::new(tikv_jemallocator::Jemalloc);
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+#[global_allocator]
+static GLOBAL: CountingAllocator<mimalloc::MiMalloc> = CountingAllocator::new(mimalloc::MiMalloc);
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    legrid_controller::run::run(&args)
+}
@@ -0,0 +1,218 @@
+//! Prometheus metrics endpoint.
+//!
+//! `--metrics-port` serves a text-format `/metrics` page with the counters
+//! Grafana wants: fps, frames processed and dropped, a render latency
+//! histogram, the power-limit scale, driver errors, and the SoC
+//! temperature. Same hand-rolled HTTP/1.1 as the upload endpoint; no
+//! client libraries.
+
+use std::io::{BufRead, BufReader};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::http::http_respond_bytes;
+
+/// Render latency histogram bucket bounds, in microseconds. WS2812-class
+/// latching lands in the low milliseconds; the tail catches stalls.
+pub const LATENCY_BUCKETS_US: [u64; 8] = [100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000];
+
+/// Shared metric counters. Everything is atomic so the render loop can
+/// update without locks; floats are stored as their bit patterns, the same
+/// trick the allocator stats use for lock-free counters.
+pub struct Metrics {
+    pub frames_processed: AtomicU64,
+    pub frames_dropped: AtomicU64,
+    pub driver_errors: AtomicU64,
+    fps_bits: AtomicU64,
+    power_scale_bits: AtomicU64,
+    /// Per-bucket counts, plus one overflow slot for +Inf.
+    latency_counts: [AtomicU64; LATENCY_BUCKETS_US.len() + 1],
+    latency_sum_us: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            frames_processed: AtomicU64::new(0),
+            frames_dropped: AtomicU64::new(0),
+            driver_errors: AtomicU64::new(0),
+            fps_bits: AtomicU64::new(0),
+            power_scale_bits: AtomicU64::new(1.0f64.to_bits()),
+            latency_counts: std::array::from_fn(|_| AtomicU64::new(0)),
+            latency_sum_us: AtomicU64::new(0),
+        })
+    }
+
+    pub fn set_fps(&self, fps: f64) {
+        self.fps_bits.store(fps.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn set_power_scale(&self, scale: f64) {
+        self.power_scale_bits.store(scale.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn record_render_latency(&self, latency: Duration) {
+        let us = latency.as_micros() as u64;
+        let bucket = LATENCY_BUCKETS_US
+            .iter()
+            .position(|&bound| us <= bound)
+            .unwrap_or(LATENCY_BUCKETS_US.len());
+        self.latency_counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    /// Render the Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counter = |out: &mut String, name: &str, help: &str, value: u64| {
+            out.push_str(&format!(
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}\n"
+            ));
+        };
+        let gauge = |out: &mut String, name: &str, help: &str, value: f64| {
+            out.push_str(&format!(
+                "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n"
+            ));
+        };
+
+        gauge(
+            &mut out,
+            "legrid_fps",
+            "Smoothed incoming frame rate.",
+            f64::from_bits(self.fps_bits.load(Ordering::Relaxed)),
+        );
+        counter(
+            &mut out,
+            "legrid_frames_processed_total",
+            "Frames accepted and displayed.",
+            self.frames_processed.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "legrid_frames_dropped_total",
+            "Frames rejected by the parser or command handler.",
+            self.frames_dropped.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "legrid_driver_errors_total",
+            "Render calls the output driver failed.",
+            self.driver_errors.load(Ordering::Relaxed),
+        );
+        gauge(
+            &mut out,
+            "legrid_power_limit_scale",
+            "Scale last applied by the power-limit stage (1 = unconstrained).",
+            f64::from_bits(self.power_scale_bits.load(Ordering::Relaxed)),
+        );
+
+        out.push_str("# HELP legrid_render_latency_seconds Time spent in the output driver per frame.\n");
+        out.push_str("# TYPE legrid_render_latency_seconds histogram\n");
+        let mut cumulative = 0u64;
+        for (i, &bound) in LATENCY_BUCKETS_US.iter().enumerate() {
+            cumulative += self.latency_counts[i].load(Ordering::Relaxed);
+            out.push_str(&format!(
+                "legrid_render_latency_seconds_bucket{{le=\"{}\"}} {}\n",
+                bound as f64 / 1e6,
+                cumulative
+            ));
+        }
+        cumulative += self.latency_counts[LATENCY_BUCKETS_US.len()].load(Ordering::Relaxed);
+        out.push_str(&format!(
+            "legrid_render_latency_seconds_bucket{{le=\"+Inf\"}} {}\n",
+            cumulative
+        ));
+        out.push_str(&format!(
+            "legrid_render_latency_seconds_sum {}\n",
+            self.latency_sum_us.load(Ordering::Relaxed) as f64 / 1e6
+        ));
+        out.push_str(&format!("legrid_render_latency_seconds_count {}\n", cumulative));
+
+        if let Some(temp) = read_cpu_temperature() {
+            gauge(
+                &mut out,
+                "legrid_temperature_celsius",
+                "SoC temperature from the thermal zone.",
+                temp,
+            );
+        }
+        out
+    }
+}
+
+/// SoC temperature as the Pi exposes it: millidegrees in sysfs.
+pub fn read_cpu_temperature() -> Option<f64> {
+    let raw = std::fs::read_to_string("/sys/class/thermal/thermal_zone0/temp").ok()?;
+    raw.trim().parse::<f64>().ok().map(|milli| milli / 1000.0)
+}
+
+/// Serves GET /metrics; anything else is a 404. One thread per request,
+/// like the upload endpoint — Prometheus scrapes are rare and tiny.
+pub struct MetricsServer {
+    pub port: u16,
+    pub metrics: Arc<Metrics>,
+}
+
+impl MetricsServer {
+    pub fn spawn(self) -> std::io::Result<()> {
+        let listener = match crate::systemd::activated_listener(self.port) {
+            Some(listener) => listener,
+            None => TcpListener::bind(("0.0.0.0", self.port))?,
+        };
+        crate::log_info!("metrics", "Metrics endpoint on port {}", self.port);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let metrics = self.metrics.clone();
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone()?);
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line)?;
+                    if request_line.starts_with("GET /metrics") {
+                        let body = metrics.render();
+                        http_respond_bytes(
+                            &mut stream,
+                            200,
+                            "text/plain; version=0.0.4",
+                            body.as_bytes(),
+                        )
+                    } else {
+                        http_respond_bytes(&mut stream, 404, "text/plain", b"not found\n")
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposition_includes_every_metric_family() {
+        let metrics = Metrics::new();
+        metrics.frames_processed.fetch_add(5, Ordering::Relaxed);
+        metrics.set_fps(30.5);
+        let body = metrics.render();
+        assert!(body.contains("legrid_frames_processed_total 5"));
+        assert!(body.contains("legrid_fps 30.5"));
+        assert!(body.contains("legrid_power_limit_scale 1"));
+        assert!(body.contains("# TYPE legrid_render_latency_seconds histogram"));
+    }
+
+    #[test]
+    fn latency_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        metrics.record_render_latency(Duration::from_micros(200));
+        metrics.record_render_latency(Duration::from_micros(200));
+        metrics.record_render_latency(Duration::from_millis(50));
+        let body = metrics.render();
+        assert!(body.contains("legrid_render_latency_seconds_bucket{le=\"0.00025\"} 2"));
+        assert!(body.contains("legrid_render_latency_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(body.contains("legrid_render_latency_seconds_count 3"));
+    }
+}
@@ -0,0 +1,257 @@
+//! Composable effect modifiers: geometric and temporal transforms that
+//! wrap whatever is underneath — an idle effect, streamed frames, or
+//! the overlay layer — multiplying the built-in visuals without new
+//! effect code.
+//!
+//! `--modifiers "mirror:h,kaleidoscope"` chains them in order on the
+//! main layer; `--overlay-modifiers` runs a separate chain on the
+//! overlay stream. Specs read like the pipeline's: comma-separated
+//! `name` or `name:argument`.
+
+use crate::frame::Pixel;
+
+/// One modifier in a chain. Unlike pipeline stages these are frame
+/// transforms, not color ones: they run on logical RGB frames before
+/// the gain and pipeline stages see them.
+pub trait Modifier: Send {
+    fn name(&self) -> &'static str;
+    fn apply(&mut self, frame: &mut [Pixel], width: usize, height: usize);
+}
+
+/// Mirror one half of the panel onto the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    Horizontal,
+    Vertical,
+}
+
+pub struct MirrorModifier {
+    pub axis: MirrorAxis,
+}
+
+impl Modifier for MirrorModifier {
+    fn name(&self) -> &'static str {
+        "mirror"
+    }
+
+    fn apply(&mut self, frame: &mut [Pixel], width: usize, height: usize) {
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = match self.axis {
+                    MirrorAxis::Horizontal if x >= width.div_ceil(2) => (width - 1 - x, y),
+                    MirrorAxis::Vertical if y >= height.div_ceil(2) => (x, height - 1 - y),
+                    _ => continue,
+                };
+                frame[y * width + x] = frame[sy * width + sx];
+            }
+        }
+    }
+}
+
+/// Reflect the top-left quadrant into all four, the classic
+/// kaleidoscope fold.
+pub struct KaleidoscopeModifier;
+
+impl Modifier for KaleidoscopeModifier {
+    fn name(&self) -> &'static str {
+        "kaleidoscope"
+    }
+
+    fn apply(&mut self, frame: &mut [Pixel], width: usize, height: usize) {
+        for y in 0..height {
+            for x in 0..width {
+                let sx = if x >= width.div_ceil(2) { width - 1 - x } else { x };
+                let sy = if y >= height.div_ceil(2) { height - 1 - y } else { y };
+                frame[y * width + x] = frame[sy * width + sx];
+            }
+        }
+    }
+}
+
+/// Sort the bright runs of each row by brightness — the pixel-sort
+/// glitch look. Pixels dimmer than the threshold anchor the runs.
+pub struct PixelSortModifier {
+    pub threshold: u8,
+}
+
+fn luma(p: Pixel) -> u16 {
+    p.r as u16 + p.g as u16 + p.b as u16
+}
+
+impl Modifier for PixelSortModifier {
+    fn name(&self) -> &'static str {
+        "pixelsort"
+    }
+
+    fn apply(&mut self, frame: &mut [Pixel], width: usize, height: usize) {
+        let threshold = self.threshold as u16 * 3;
+        for y in 0..height {
+            let row = &mut frame[y * width..(y + 1) * width];
+            let mut start = 0;
+            while start < width {
+                if luma(row[start]) < threshold {
+                    start += 1;
+                    continue;
+                }
+                let mut end = start;
+                while end < width && luma(row[end]) >= threshold {
+                    end += 1;
+                }
+                row[start..end].sort_by_key(|&p| luma(p));
+                start = end;
+            }
+        }
+    }
+}
+
+/// Time-stretch by holding frames: `speed:0.5` shows every frame twice,
+/// halving the apparent motion. Speeds at or above 1 pass through —
+/// frames that haven't arrived can't be invented.
+pub struct SpeedModifier {
+    hold: u32,
+    counter: u32,
+    held: Vec<Pixel>,
+}
+
+impl SpeedModifier {
+    pub fn new(speed: f64) -> Self {
+        let hold = if speed >= 1.0 || speed <= 0.0 {
+            1
+        } else {
+            (1.0 / speed).round() as u32
+        };
+        Self { hold, counter: 0, held: Vec::new() }
+    }
+}
+
+impl Modifier for SpeedModifier {
+    fn name(&self) -> &'static str {
+        "speed"
+    }
+
+    fn apply(&mut self, frame: &mut [Pixel], _width: usize, _height: usize) {
+        if self.hold <= 1 {
+            return;
+        }
+        if self.counter == 0 || self.held.len() != frame.len() {
+            self.held = frame.to_vec();
+        } else {
+            frame.copy_from_slice(&self.held);
+        }
+        self.counter = (self.counter + 1) % self.hold;
+    }
+}
+
+/// Parse a modifier chain: comma-separated `name` or `name:argument` in
+/// execution order, like the pipeline spec.
+pub fn parse_modifiers(spec: &str) -> Result<Vec<Box<dyn Modifier>>, String> {
+    let mut chain: Vec<Box<dyn Modifier>> = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (name, arg) = match part.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg.trim())),
+            None => (part, None),
+        };
+        match name {
+            "mirror" => {
+                let axis = match arg.unwrap_or("h") {
+                    "h" => MirrorAxis::Horizontal,
+                    "v" => MirrorAxis::Vertical,
+                    other => return Err(format!("modifier '{}': bad axis '{}' (h|v)", part, other)),
+                };
+                chain.push(Box::new(MirrorModifier { axis }));
+            }
+            "kaleidoscope" => chain.push(Box::new(KaleidoscopeModifier)),
+            "pixelsort" => {
+                let threshold = match arg {
+                    Some(arg) => arg.parse().map_err(|_| format!("modifier '{}': bad threshold", part))?,
+                    None => 96,
+                };
+                chain.push(Box::new(PixelSortModifier { threshold }));
+            }
+            "speed" => {
+                let speed: f64 = arg
+                    .ok_or_else(|| format!("modifier '{}': missing speed", part))?
+                    .parse()
+                    .map_err(|_| format!("modifier '{}': bad speed", part))?;
+                chain.push(Box::new(SpeedModifier::new(speed)));
+            }
+            other => {
+                return Err(format!(
+                    "unknown modifier '{}' (expected mirror|kaleidoscope|pixelsort|speed)",
+                    other
+                ))
+            }
+        }
+    }
+    Ok(chain)
+}
+
+/// Run a chain over a frame in order.
+pub fn apply_chain(chain: &mut [Box<dyn Modifier>], frame: &mut [Pixel], width: usize, height: usize) {
+    for modifier in chain.iter_mut() {
+        modifier.apply(frame, width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn px(v: u8) -> Pixel {
+        Pixel { r: v, g: v, b: v }
+    }
+
+    #[test]
+    fn mirror_reflects_the_leading_half() {
+        let mut frame = vec![px(1), px(2), px(3), px(4)];
+        MirrorModifier { axis: MirrorAxis::Horizontal }.apply(&mut frame, 4, 1);
+        assert_eq!(frame, vec![px(1), px(2), px(2), px(1)]);
+
+        let mut frame = vec![px(1), px(2), px(3), px(4)];
+        MirrorModifier { axis: MirrorAxis::Vertical }.apply(&mut frame, 1, 4);
+        assert_eq!(frame, vec![px(1), px(2), px(2), px(1)]);
+    }
+
+    #[test]
+    fn kaleidoscope_folds_all_quadrants() {
+        // 2x2: only the top-left pixel survives, reflected everywhere.
+        let mut frame = vec![px(9), px(2), px(3), px(4)];
+        KaleidoscopeModifier.apply(&mut frame, 2, 2);
+        assert_eq!(frame, vec![px(9); 4]);
+    }
+
+    #[test]
+    fn pixelsort_orders_bright_runs_only() {
+        let mut frame = vec![px(200), px(120), px(10), px(180), px(150)];
+        PixelSortModifier { threshold: 96 }.apply(&mut frame, 5, 1);
+        // The dark pixel splits the row into two sorted runs.
+        assert_eq!(frame, vec![px(120), px(200), px(10), px(150), px(180)]);
+    }
+
+    #[test]
+    fn speed_holds_frames_to_slow_playback() {
+        let mut speed = SpeedModifier::new(0.5);
+        let mut first = vec![px(1)];
+        speed.apply(&mut first, 1, 1);
+        assert_eq!(first, vec![px(1)]);
+        let mut second = vec![px(2)];
+        speed.apply(&mut second, 1, 1);
+        assert_eq!(second, vec![px(1)]); // held
+        let mut third = vec![px(3)];
+        speed.apply(&mut third, 1, 1);
+        assert_eq!(third, vec![px(3)]); // new hold window
+    }
+
+    #[test]
+    fn specs_parse_in_order_and_reject_unknowns() {
+        let chain = parse_modifiers("mirror:v, kaleidoscope, pixelsort:64, speed:0.25").unwrap();
+        let names: Vec<&str> = chain.iter().map(|m| m.name()).collect();
+        assert_eq!(names, vec!["mirror", "kaleidoscope", "pixelsort", "speed"]);
+        assert!(parse_modifiers("swirl").is_err());
+        assert!(parse_modifiers("mirror:x").is_err());
+    }
+}
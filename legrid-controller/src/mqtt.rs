@@ -0,0 +1,382 @@
+//! MQTT bridge for Home Assistant.
+//!
+//! A minimal MQTT 3.1.1 client (CONNECT, QoS-0 PUBLISH/SUBSCRIBE, pings)
+//! over a plain TcpStream — the same no-dependency approach as the HTTP
+//! endpoints. On connect it publishes a retained Home Assistant discovery
+//! document (MQTT Light, json schema), so the panel appears as a light
+//! entity; commands arriving on the command topic are translated into the
+//! controller's own control messages and injected into the message
+//! channel the stdin reader feeds.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::controller::{json_num_field, json_str_field};
+use crate::frame::MSG_TYPE_CONTROL;
+
+const DISCOVERY_TOPIC: &str = "homeassistant/light/legrid/config";
+const COMMAND_TOPIC: &str = "legrid/set";
+/// Free/busy/focus indicator commands; see [`translate_status`].
+const INDICATOR_TOPIC: &str = "legrid/indicator";
+/// Tempo messages for the beat-synced idle clock; see [`translate_beat`].
+const BEAT_TOPIC: &str = "legrid/beat";
+const STATE_TOPIC: &str = "legrid/state";
+const AVAILABILITY_TOPIC: &str = "legrid/status";
+const KEEPALIVE: Duration = Duration::from_secs(60);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// An MQTT length-prefixed UTF-8 string.
+fn mqtt_string(s: &str) -> Vec<u8> {
+    let mut out = (s.len() as u16).to_be_bytes().to_vec();
+    out.extend_from_slice(s.as_bytes());
+    out
+}
+
+/// The variable-length "remaining length" field.
+fn remaining_length(mut len: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            return out;
+        }
+    }
+}
+
+struct MqttClient {
+    stream: TcpStream,
+    last_ping: Instant,
+}
+
+impl MqttClient {
+    fn connect(addr: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&mqtt_string("MQTT"));
+        payload.push(4); // protocol level 3.1.1
+        payload.push(0x02); // clean session
+        payload.extend_from_slice(&(KEEPALIVE.as_secs() as u16).to_be_bytes());
+        payload.extend_from_slice(&mqtt_string("legrid-controller"));
+
+        let mut client = Self { stream, last_ping: Instant::now() };
+        client.send_packet(0x10, &payload)?;
+
+        // CONNACK: type 0x20, length 2, session-present flag, return code.
+        let mut ack = [0u8; 4];
+        client.stream.read_exact(&mut ack)?;
+        if ack[0] != 0x20 || ack[3] != 0 {
+            return Err(io::Error::other(format!("MQTT connect refused (code {})", ack[3])));
+        }
+        Ok(client)
+    }
+
+    fn send_packet(&mut self, header: u8, body: &[u8]) -> io::Result<()> {
+        let mut packet = vec![header];
+        packet.extend_from_slice(&remaining_length(body.len()));
+        packet.extend_from_slice(body);
+        self.stream.write_all(&packet)
+    }
+
+    fn publish(&mut self, topic: &str, payload: &str, retain: bool) -> io::Result<()> {
+        let mut body = mqtt_string(topic);
+        body.extend_from_slice(payload.as_bytes());
+        self.send_packet(0x30 | retain as u8, &body)
+    }
+
+    fn subscribe(&mut self, topic: &str) -> io::Result<()> {
+        let mut body = 1u16.to_be_bytes().to_vec(); // packet id
+        body.extend_from_slice(&mqtt_string(topic));
+        body.push(0); // QoS 0
+        self.send_packet(0x82, &body)
+    }
+
+    /// Read one packet, riding the 1s read timeout as the poll interval.
+    /// Returns the (topic, payload) of a PUBLISH, if that is what arrived.
+    fn poll(&mut self) -> io::Result<Option<(String, Vec<u8>)>> {
+        if self.last_ping.elapsed() > KEEPALIVE / 2 {
+            self.send_packet(0xC0, &[])?;
+            self.last_ping = Instant::now();
+        }
+
+        let mut header = [0u8; 1];
+        match self.stream.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        }
+
+        let mut len = 0usize;
+        let mut shift = 0;
+        loop {
+            let mut byte = [0u8; 1];
+            self.stream.read_exact(&mut byte)?;
+            len |= ((byte[0] & 0x7F) as usize) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        let mut body = vec![0u8; len];
+        self.stream.read_exact(&mut body)?;
+
+        if header[0] & 0xF0 != 0x30 || body.len() < 2 {
+            return Ok(None); // SUBACK, PINGRESP, ...
+        }
+        let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+        if body.len() < 2 + topic_len {
+            return Ok(None);
+        }
+        let topic = String::from_utf8_lossy(&body[2..2 + topic_len]).into_owned();
+        Ok(Some((topic, body[2 + topic_len..].to_vec())))
+    }
+}
+
+/// The retained discovery document that makes Home Assistant create the
+/// light entity.
+fn discovery_json() -> String {
+    concat!(
+        "{\"name\":\"LED Grid\",\"unique_id\":\"legrid\",\"schema\":\"json\",",
+        "\"command_topic\":\"legrid/set\",\"state_topic\":\"legrid/state\",",
+        "\"availability_topic\":\"legrid/status\",",
+        "\"brightness\":true,\"effect\":true,",
+        "\"effect_list\":[\"none\",\"rainbow\",\"breathing\",\"sparkle\",\"solid\"]}"
+    )
+    .to_string()
+}
+
+/// Tracks what we last told Home Assistant, so state publishes reflect the
+/// commands we forwarded without round-tripping through the controller.
+struct LightState {
+    on: bool,
+    brightness: u8,
+    effect: String,
+}
+
+impl LightState {
+    fn json(&self) -> String {
+        format!(
+            "{{\"state\":\"{}\",\"brightness\":{},\"effect\":\"{}\"}}",
+            if self.on { "ON" } else { "OFF" },
+            self.brightness,
+            self.effect
+        )
+    }
+}
+
+/// Translate an HA json-schema light command into the controller's control
+/// messages (wire format: [version, type, flat JSON]). Returns the
+/// messages to inject, updating the mirrored state.
+fn translate_command(payload: &str, state: &mut LightState) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let control = |json: String| {
+        let mut msg = vec![1u8, MSG_TYPE_CONTROL];
+        msg.extend_from_slice(json.as_bytes());
+        msg
+    };
+
+    if let Some(on) = json_str_field(payload, "state") {
+        state.on = on.eq_ignore_ascii_case("on");
+        messages.push(control(format!(
+            "{{\"command\":\"set_power\",\"on\":{}}}",
+            state.on
+        )));
+    }
+    if let Some(brightness) = json_num_field(payload, "brightness") {
+        state.brightness = brightness.clamp(0.0, 255.0) as u8;
+        messages.push(control(format!(
+            "{{\"command\":\"set_brightness\",\"value\":{}}}",
+            state.brightness
+        )));
+    }
+    if let Some(effect) = json_str_field(payload, "effect") {
+        state.effect = effect.clone();
+        messages.push(control(format!(
+            "{{\"command\":\"set_idle_effect\",\"effect\":\"{}\"}}",
+            effect
+        )));
+    }
+    messages
+}
+
+/// Translate an indicator payload into the controller's `status`
+/// command. Accepts a bare state ("busy"), a state with a countdown
+/// ("focus:25"), or "clear"; anything else is dropped with a log so a
+/// fat-fingered automation doesn't paint the office red.
+fn translate_status(payload: &str) -> Option<Vec<u8>> {
+    let payload = payload.trim();
+    let (state, minutes) = match payload.split_once(':') {
+        Some((state, minutes)) => (state.trim(), minutes.trim().parse::<f64>().ok()),
+        None => (payload, None),
+    };
+    if state != "clear" && crate::status::StatusState::parse(state).is_none() {
+        crate::log_warn!("mqtt", "Ignoring unknown indicator state: {}", payload);
+        return None;
+    }
+    let json = match minutes {
+        Some(m) => format!("{{\"command\":\"status\",\"state\":\"{}\",\"minutes\":{}}}", state, m),
+        None => format!("{{\"command\":\"status\",\"state\":\"{}\"}}", state),
+    };
+    let mut msg = vec![1u8, MSG_TYPE_CONTROL];
+    msg.extend_from_slice(json.as_bytes());
+    Some(msg)
+}
+
+/// Translate a beat payload into the controller's `beat` command.
+/// Accepts a bare BPM number ("128"), "bpm:128", "tap" (one tempo tap),
+/// or "clear"; anything else is dropped with a log.
+fn translate_beat(payload: &str) -> Option<Vec<u8>> {
+    let payload = payload.trim();
+    let json = if payload == "tap" {
+        "{\"command\":\"beat\",\"tap\":true}".to_string()
+    } else if payload == "clear" {
+        "{\"command\":\"beat\",\"clear\":true}".to_string()
+    } else {
+        let bpm = payload.strip_prefix("bpm:").unwrap_or(payload).trim();
+        match bpm.parse::<f64>() {
+            Ok(bpm) => format!("{{\"command\":\"beat\",\"bpm\":{}}}", bpm),
+            Err(_) => {
+                crate::log_warn!("mqtt", "Ignoring unparseable beat payload: {}", payload);
+                return None;
+            }
+        }
+    };
+    let mut msg = vec![1u8, MSG_TYPE_CONTROL];
+    msg.extend_from_slice(json.as_bytes());
+    Some(msg)
+}
+
+/// Run the bridge on its own thread: connect (and reconnect), announce
+/// discovery and availability, then relay commands into `tx` forever.
+pub fn spawn_mqtt_bridge(addr: String, tx: mpsc::Sender<Vec<u8>>) {
+    std::thread::spawn(move || {
+        let mut state = LightState {
+            on: true,
+            brightness: 255,
+            effect: "none".to_string(),
+        };
+        loop {
+            let mut client = match MqttClient::connect(&addr) {
+                Ok(client) => client,
+                Err(e) => {
+                    crate::log_warn!("mqtt", "MQTT connect to {} failed, retrying: {}", addr, e);
+                    std::thread::sleep(RECONNECT_INTERVAL);
+                    continue;
+                }
+            };
+            crate::log_info!("mqtt", "MQTT connected to {}", addr);
+
+            let session = (|| -> io::Result<()> {
+                client.publish(DISCOVERY_TOPIC, &discovery_json(), true)?;
+                client.publish(AVAILABILITY_TOPIC, "online", true)?;
+                client.publish(STATE_TOPIC, &state.json(), true)?;
+                client.subscribe(COMMAND_TOPIC)?;
+                client.subscribe(INDICATOR_TOPIC)?;
+                client.subscribe(BEAT_TOPIC)?;
+                loop {
+                    let Some((topic, payload)) = client.poll()? else { continue };
+                    let payload = String::from_utf8_lossy(&payload).into_owned();
+                    if topic == INDICATOR_TOPIC {
+                        if let Some(message) = translate_status(&payload) {
+                            if tx.send(message).is_err() {
+                                return Ok(()); // controller is gone
+                            }
+                        }
+                        continue;
+                    }
+                    if topic == BEAT_TOPIC {
+                        if let Some(message) = translate_beat(&payload) {
+                            if tx.send(message).is_err() {
+                                return Ok(()); // controller is gone
+                            }
+                        }
+                        continue;
+                    }
+                    if topic != COMMAND_TOPIC {
+                        continue;
+                    }
+                    for message in translate_command(&payload, &mut state) {
+                        if tx.send(message).is_err() {
+                            return Ok(()); // controller is gone
+                        }
+                    }
+                    client.publish(STATE_TOPIC, &state.json(), true)?;
+                }
+            })();
+            if let Err(e) = session {
+                crate::log_info!("mqtt", "MQTT session ended, reconnecting: {}", e);
+            }
+            std::thread::sleep(RECONNECT_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining_length_uses_continuation_bits() {
+        assert_eq!(remaining_length(0), vec![0]);
+        assert_eq!(remaining_length(127), vec![127]);
+        assert_eq!(remaining_length(128), vec![0x80, 1]);
+        assert_eq!(remaining_length(321), vec![0xC1, 2]);
+    }
+
+    #[test]
+    fn light_commands_become_control_messages() {
+        let mut state = LightState {
+            on: true,
+            brightness: 255,
+            effect: "none".to_string(),
+        };
+        let messages = translate_command("{\"state\":\"OFF\"}", &mut state);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(&messages[0][..2], &[1, MSG_TYPE_CONTROL]);
+        let body = std::str::from_utf8(&messages[0][2..]).unwrap();
+        assert!(body.contains("\"set_power\""));
+        assert!(body.contains("false"));
+        assert!(!state.on);
+
+        let messages =
+            translate_command("{\"state\":\"ON\",\"brightness\":128,\"effect\":\"breathing\"}", &mut state);
+        assert_eq!(messages.len(), 3);
+        assert!(state.on);
+        assert_eq!(state.brightness, 128);
+        assert!(state.json().contains("\"effect\":\"breathing\""));
+    }
+
+    #[test]
+    fn indicator_payloads_become_status_commands() {
+        let msg = translate_status("focus:25").unwrap();
+        assert_eq!(&msg[..2], &[1, MSG_TYPE_CONTROL]);
+        let body = std::str::from_utf8(&msg[2..]).unwrap();
+        assert!(body.contains("\"status\"") && body.contains("\"focus\"") && body.contains("25"));
+        assert!(translate_status("clear").is_some());
+        assert!(translate_status("lunch").is_none());
+    }
+
+    #[test]
+    fn beat_payloads_become_beat_commands() {
+        let msg = translate_beat("bpm:128").unwrap();
+        assert_eq!(&msg[..2], &[1, MSG_TYPE_CONTROL]);
+        let body = std::str::from_utf8(&msg[2..]).unwrap();
+        assert!(body.contains("\"beat\"") && body.contains("128"));
+        // A bare number works too, as do tap and clear.
+        assert!(translate_beat("95.5").is_some());
+        let tap = translate_beat("tap").unwrap();
+        assert!(std::str::from_utf8(&tap[2..]).unwrap().contains("\"tap\":true"));
+        assert!(translate_beat("clear").is_some());
+        assert!(translate_beat("andante").is_none());
+    }
+}
@@ -0,0 +1,130 @@
+//! Notification queue: prioritized, timed messages over live content.
+//!
+//! Senders enqueue items with `{"command":"notify","text":"door
+//! open","icon":"alert","color":"FF0000","duration_s":4,"priority":2}`;
+//! the controller shows one at a time (icon plus scrolling text,
+//! composited over whatever is playing) and returns to normal when the
+//! queue drains. Queue state rides along in stats.
+
+use std::time::Instant;
+
+use crate::frame::Pixel;
+
+/// One queued notification.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// Optional icon shortcode name (see text::icon_char).
+    pub icon: Option<String>,
+    pub text: String,
+    pub color: Pixel,
+    pub duration_s: f64,
+    /// Higher shows first; equal priorities stay in arrival order.
+    pub priority: i64,
+}
+
+/// What the queue wants the display to do this frame.
+pub enum QueuePoll {
+    /// Start showing this notification (the previous one, if any, ended).
+    Start(Notification),
+    /// The last notification expired and nothing is waiting.
+    Stop,
+    /// Keep doing whatever is being done.
+    Idle,
+}
+
+#[derive(Default)]
+pub struct NotificationQueue {
+    waiting: Vec<Notification>,
+    active_until: Option<Instant>,
+    /// Notifications displayed over the run, for stats.
+    pub shown: u64,
+}
+
+impl NotificationQueue {
+    /// Insert by priority: after every waiting item of equal or higher
+    /// priority, so equal priorities drain first-in-first-out.
+    pub fn enqueue(&mut self, notification: Notification) {
+        let at = self
+            .waiting
+            .iter()
+            .position(|w| w.priority < notification.priority)
+            .unwrap_or(self.waiting.len());
+        self.waiting.insert(at, notification);
+    }
+
+    pub fn clear(&mut self) {
+        self.waiting.clear();
+        self.active_until = None;
+    }
+
+    pub fn waiting_len(&self) -> usize {
+        self.waiting.len()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active_until.is_some()
+    }
+
+    /// Advance the queue clock: expire the active item, promote the next
+    /// waiting one. Called once per output frame.
+    pub fn poll(&mut self, now: Instant) -> QueuePoll {
+        if self.active_until.is_some_and(|until| now < until) {
+            return QueuePoll::Idle;
+        }
+        let was_active = self.active_until.take().is_some();
+        if self.waiting.is_empty() {
+            return if was_active { QueuePoll::Stop } else { QueuePoll::Idle };
+        }
+        let next = self.waiting.remove(0);
+        self.active_until =
+            Some(now + std::time::Duration::from_secs_f64(next.duration_s.max(0.1)));
+        self.shown += 1;
+        QueuePoll::Start(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn item(text: &str, priority: i64) -> Notification {
+        Notification {
+            icon: None,
+            text: text.to_string(),
+            color: Pixel { r: 255, g: 255, b: 255 },
+            duration_s: 1.0,
+            priority,
+        }
+    }
+
+    #[test]
+    fn higher_priority_jumps_the_queue_fifo_within() {
+        let mut queue = NotificationQueue::default();
+        queue.enqueue(item("a", 0));
+        queue.enqueue(item("b", 0));
+        queue.enqueue(item("urgent", 5));
+
+        let now = Instant::now();
+        let order: Vec<String> = (0..3)
+            .map(|i| match queue.poll(now + Duration::from_secs(2 * i)) {
+                QueuePoll::Start(n) => n.text,
+                _ => panic!("expected a start"),
+            })
+            .collect();
+        assert_eq!(order, ["urgent", "a", "b"]);
+    }
+
+    #[test]
+    fn queue_stops_after_the_last_item_expires() {
+        let mut queue = NotificationQueue::default();
+        queue.enqueue(item("only", 0));
+        let now = Instant::now();
+        assert!(matches!(queue.poll(now), QueuePoll::Start(_)));
+        // Still running half way through its duration.
+        assert!(matches!(queue.poll(now + Duration::from_millis(500)), QueuePoll::Idle));
+        assert!(matches!(queue.poll(now + Duration::from_secs(2)), QueuePoll::Stop));
+        assert!(matches!(queue.poll(now + Duration::from_secs(3)), QueuePoll::Idle));
+        assert_eq!(queue.shown, 1);
+    }
+}
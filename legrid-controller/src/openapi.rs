@@ -0,0 +1,131 @@
+//! Generated OpenAPI description of the embedded HTTP surface.
+//!
+//! `GET /openapi.json` on the upload server returns this document, so
+//! API explorers and client generators work against the controller out
+//! of the box. The HTTP endpoints are described as OpenAPI paths; the
+//! control-channel JSON commands (stdin framing, MQTT bridge) have no
+//! HTTP path, so their schema lives under `components.schemas` where
+//! generators still pick it up. Hand-assembled like the rest of the
+//! JSON in this crate — the surface is small and changes rarely, and a
+//! test pins the paths to the handlers they describe.
+
+/// Every `command` value `process_control` accepts, in handler order.
+/// Kept here (next to the schema that uses it) so adding a command is a
+/// one-line diff plus its handler.
+pub const CONTROL_COMMANDS: &[&str] = &[
+    "apply_config",
+    "rollback_config",
+    "reload",
+    "set_power",
+    "set_brightness",
+    "set_stage",
+    "snapshot",
+    "extract_palette",
+    "save_splash",
+    "marquee",
+    "notify",
+    "status",
+    "cancel_alarm",
+    "beat",
+    "clear_notifications",
+    "icon",
+    "set_overlay",
+    "clear_overlay",
+    "transition",
+    "heartbeat",
+    "set_idle_effect",
+];
+
+/// The full document. Rebuilt per request; it is a few KB.
+pub fn openapi_json() -> String {
+    let commands: Vec<String> = CONTROL_COMMANDS.iter().map(|c| format!("\"{}\"", c)).collect();
+    format!(
+        concat!(
+            "{{\"openapi\":\"3.0.3\",",
+            "\"info\":{{\"title\":\"legrid controller\",",
+            "\"description\":\"Embedded HTTP surface of the LED grid controller. ",
+            "Control commands (components.schemas.ControlCommand) travel over the ",
+            "length-prefixed stdin framing or the MQTT bridge, not HTTP.\",",
+            "\"version\":\"{version}\"}},",
+            "\"components\":{{",
+            "\"securitySchemes\":{{\"bearerAuth\":{{\"type\":\"http\",\"scheme\":\"bearer\"}}}},",
+            "\"schemas\":{{",
+            "\"ControlCommand\":{{\"type\":\"object\",",
+            "\"required\":[\"command\"],",
+            "\"properties\":{{\"command\":{{\"type\":\"string\",\"enum\":[{commands}]}}}},",
+            "\"additionalProperties\":true}},",
+            "\"ContentEntry\":{{\"type\":\"object\",\"properties\":{{",
+            "\"name\":{{\"type\":\"string\"}},",
+            "\"bytes\":{{\"type\":\"integer\"}},",
+            "\"thumb\":{{\"type\":\"string\"}}}}}},",
+            "\"Error\":{{\"type\":\"object\",\"properties\":{{\"error\":{{\"type\":\"string\"}}}}}}",
+            "}}}},",
+            "\"security\":[{{\"bearerAuth\":[]}}],",
+            "\"paths\":{{",
+            "\"/openapi.json\":{{\"get\":{{\"summary\":\"This document\",\"security\":[],",
+            "\"responses\":{{\"200\":{{\"description\":\"OpenAPI 3.0 document\"}}}}}}}},",
+            "\"/content\":{{\"get\":{{\"summary\":\"List stored content files\",",
+            "\"responses\":{{\"200\":{{\"description\":\"Stored files\",",
+            "\"content\":{{\"application/json\":{{\"schema\":{{\"type\":\"array\",",
+            "\"items\":{{\"$ref\":\"#/components/schemas/ContentEntry\"}}}}}}}}}},",
+            "\"401\":{{\"description\":\"Missing or invalid token\"}}}}}}}},",
+            "\"/content/{{filename}}\":{{\"put\":{{\"summary\":\"Upload a content file\",",
+            "\"parameters\":[{{\"name\":\"filename\",\"in\":\"path\",\"required\":true,",
+            "\"schema\":{{\"type\":\"string\"}}}}],",
+            "\"requestBody\":{{\"required\":true,",
+            "\"content\":{{\"application/octet-stream\":{{}}}}}},",
+            "\"responses\":{{\"200\":{{\"description\":\"Stored\"}},",
+            "\"400\":{{\"description\":\"Invalid filename\"}},",
+            "\"413\":{{\"description\":\"Missing or oversized body\"}},",
+            "\"415\":{{\"description\":\"Unsupported file type\"}}}}}}}},",
+            "\"/thumb/{{filename}}\":{{\"get\":{{\"summary\":\"PNG thumbnail of a stored file\",",
+            "\"parameters\":[{{\"name\":\"filename\",\"in\":\"path\",\"required\":true,",
+            "\"schema\":{{\"type\":\"string\"}}}}],",
+            "\"responses\":{{\"200\":{{\"description\":\"PNG preview\",",
+            "\"content\":{{\"image/png\":{{}}}}}},",
+            "\"404\":{{\"description\":\"No such file\"}}}}}}}},",
+            "\"/snapshot.png\":{{\"get\":{{\"summary\":\"Currently displayed frame\",",
+            "\"description\":\"Served by the --snapshot-http listener, not the upload port.\",",
+            "\"security\":[],",
+            "\"responses\":{{\"200\":{{\"description\":\"PNG of the live buffer\",",
+            "\"content\":{{\"image/png\":{{}}}}}}}}}}}}",
+            "}}}}"
+        ),
+        version = env!("CARGO_PKG_VERSION"),
+        commands = commands.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_covers_the_served_paths() {
+        let doc = openapi_json();
+        assert!(doc.starts_with("{\"openapi\":\"3.0.3\""));
+        for path in ["/openapi.json", "/content", "/content/{filename}", "/thumb/{filename}", "/snapshot.png"] {
+            assert!(doc.contains(&format!("\"{}\":", path)), "missing path {}", path);
+        }
+        assert!(doc.contains("\"bearerAuth\""));
+    }
+
+    #[test]
+    fn control_schema_lists_every_command() {
+        let doc = openapi_json();
+        assert!(doc.contains("\"ControlCommand\""));
+        for command in CONTROL_COMMANDS {
+            assert!(doc.contains(&format!("\"{}\"", command)), "missing command {}", command);
+        }
+    }
+
+    #[test]
+    fn braces_balance() {
+        // Cheap structural check; the only braces inside string values
+        // are path templates like {filename}, which pair up too.
+        let doc = openapi_json();
+        let opens = doc.matches('{').count();
+        let closes = doc.matches('}').count();
+        assert_eq!(opens, closes);
+    }
+}
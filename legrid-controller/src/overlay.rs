@@ -0,0 +1,107 @@
+//! Overlay compositing for a second frame source.
+//!
+//! A sender addressing the overlay stream (type byte `MSG_TYPE_OVERLAY`)
+//! gets its frames composited on top of the main animation before output:
+//! notifications, tickers, status badges. Black overlay pixels are
+//! transparent in alpha mode, so text senders don't need an alpha plane.
+
+use crate::frame::Pixel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayMode {
+    /// Overlay replaces the base weighted by alpha; black keys out.
+    Alpha,
+    /// Overlay adds onto the base, scaled by alpha, saturating.
+    Additive,
+}
+
+impl OverlayMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "alpha" => Some(OverlayMode::Alpha),
+            "additive" => Some(OverlayMode::Additive),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OverlayMode::Alpha => "alpha",
+            OverlayMode::Additive => "additive",
+        }
+    }
+}
+
+/// Composite the overlay onto the base frame. Indices past the end of
+/// either buffer pass the base through unchanged.
+pub fn composite(base: &[Pixel], overlay: &[Pixel], mode: OverlayMode, alpha: f64) -> Vec<Pixel> {
+    let alpha = alpha.clamp(0.0, 1.0);
+    base.iter()
+        .enumerate()
+        .map(|(i, &under)| {
+            let Some(&over) = overlay.get(i) else {
+                return under;
+            };
+            match mode {
+                OverlayMode::Alpha => {
+                    if over == Pixel::BLACK {
+                        under
+                    } else {
+                        let mix = |u: u8, o: u8| {
+                            (u as f64 * (1.0 - alpha) + o as f64 * alpha).round() as u8
+                        };
+                        Pixel {
+                            r: mix(under.r, over.r),
+                            g: mix(under.g, over.g),
+                            b: mix(under.b, over.b),
+                        }
+                    }
+                }
+                OverlayMode::Additive => {
+                    let add = |u: u8, o: u8| {
+                        u.saturating_add((o as f64 * alpha).round() as u8)
+                    };
+                    Pixel {
+                        r: add(under.r, over.r),
+                        g: add(under.g, over.g),
+                        b: add(under.b, over.b),
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Pixel = Pixel { r: 200, g: 0, b: 0 };
+    const GREY: Pixel = Pixel { r: 100, g: 100, b: 100 };
+
+    #[test]
+    fn parses_modes() {
+        assert_eq!(OverlayMode::parse("alpha"), Some(OverlayMode::Alpha));
+        assert_eq!(OverlayMode::parse("additive"), Some(OverlayMode::Additive));
+        assert_eq!(OverlayMode::parse("screen"), None);
+    }
+
+    #[test]
+    fn alpha_keys_out_black_and_blends_the_rest() {
+        let out = composite(&[GREY, GREY], &[Pixel::BLACK, RED], OverlayMode::Alpha, 0.5);
+        assert_eq!(out[0], GREY);
+        assert_eq!(out[1], Pixel { r: 150, g: 50, b: 50 });
+    }
+
+    #[test]
+    fn additive_saturates() {
+        let out = composite(&[GREY], &[RED], OverlayMode::Additive, 1.0);
+        assert_eq!(out[0], Pixel { r: 255, g: 100, b: 100 });
+    }
+
+    #[test]
+    fn short_overlay_passes_the_base_through() {
+        let out = composite(&[GREY, GREY], &[RED], OverlayMode::Alpha, 1.0);
+        assert_eq!(out[1], GREY);
+    }
+}
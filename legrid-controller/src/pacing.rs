@@ -0,0 +1,94 @@
+//! Output frame pacing.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Below this remaining wait we spin instead of sleeping.
+pub const SPIN_THRESHOLD: Duration = Duration::from_micros(500);
+
+/// Paces the output loop to a maximum frame rate. Sleeps for the bulk of
+/// the wait and spins for the last stretch, since thread::sleep alone
+/// overshoots by more than a WS2812 latch window allows.
+pub struct FramePacer {
+    interval: Duration,
+    next_deadline: Option<Instant>,
+    /// EMA of how late we woke up relative to the deadline, in seconds.
+    pub jitter: f64,
+    /// EMA of the achieved output rate.
+    pub actual_fps: f64,
+    last_output: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new(max_fps: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / max_fps),
+            next_deadline: None,
+            jitter: 0.0,
+            actual_fps: 0.0,
+            last_output: None,
+        }
+    }
+
+    /// Block until the next output slot. Returns immediately on the first
+    /// call and whenever the loop is already running behind.
+    pub fn pace(&mut self) {
+        let now = Instant::now();
+        let deadline = match self.next_deadline {
+            Some(d) if d > now => d,
+            _ => now,
+        };
+
+        // Sleep until close to the deadline, then spin the rest.
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let remaining = deadline - now;
+            if remaining > SPIN_THRESHOLD {
+                thread::sleep(remaining - SPIN_THRESHOLD);
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+
+        let woke = Instant::now();
+        let late = woke.saturating_duration_since(deadline).as_secs_f64();
+        self.jitter = self.jitter * 0.9 + late * 0.1;
+
+        if let Some(last) = self.last_output {
+            let delta = woke.duration_since(last).as_secs_f64();
+            if delta > 0.0 {
+                self.actual_fps = self.actual_fps * 0.8 + (1.0 / delta) * 0.2;
+            }
+        }
+        self.last_output = Some(woke);
+
+        // Schedule relative to the deadline, not the wakeup, so jitter
+        // doesn't accumulate into drift.
+        self.next_deadline = Some(deadline + self.interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_pace_returns_immediately() {
+        let mut pacer = FramePacer::new(1.0);
+        let start = Instant::now();
+        pacer.pace();
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn second_pace_waits_for_the_slot() {
+        let mut pacer = FramePacer::new(100.0);
+        pacer.pace();
+        let start = Instant::now();
+        pacer.pace();
+        assert!(start.elapsed() >= Duration::from_millis(8));
+    }
+}
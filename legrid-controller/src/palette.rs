@@ -0,0 +1,137 @@
+//! Named color palettes.
+//!
+//! An `extract_palette` command pulls the dominant colors out of an
+//! uploaded image (via [`crate::ambient::dominant_colors`]) and registers
+//! them under a name; color fields in control commands (`set_idle_effect`,
+//! `marquee`, `notify`) then accept `name:index` alongside hex, so
+//! installations can theme their content to brand artwork with one call.
+//! The registry persists as `palettes` in the state dir, one
+//! `name = "#rrggbb #rrggbb ..."` line per palette.
+
+use std::io;
+use std::path::Path;
+
+use crate::config::parse_hex_color;
+use crate::frame::Pixel;
+
+/// Most colors a palette will hold; extraction requests are clamped here.
+pub const MAX_COLORS: usize = 16;
+
+/// The named palettes, in first-seen order so the file stays diffable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaletteRegistry {
+    entries: Vec<(String, Vec<Pixel>)>,
+}
+
+impl PaletteRegistry {
+    /// Parse the palettes file format. Malformed lines are skipped — a
+    /// corrupt file should cost the palettes, not the boot.
+    pub fn parse(contents: &str) -> Self {
+        let mut registry = Self::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, value)) = line.split_once('=') else { continue };
+            let colors: Vec<Pixel> = value
+                .trim()
+                .trim_matches('"')
+                .split_whitespace()
+                .filter_map(parse_hex_color)
+                .collect();
+            if !colors.is_empty() {
+                registry.register(name.trim(), colors);
+            }
+        }
+        registry
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        for (name, colors) in &self.entries {
+            let hex: Vec<String> = colors
+                .iter()
+                .map(|c| format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b))
+                .collect();
+            out.push_str(&format!("{} = \"{}\"\n", name, hex.join(" ")));
+        }
+        out
+    }
+
+    /// Load from disk; a missing or unreadable file is an empty registry.
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.serialize())
+    }
+
+    /// Register (or replace) a palette.
+    pub fn register(&mut self, name: &str, colors: Vec<Pixel>) {
+        match self.entries.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = colors,
+            None => self.entries.push((name.to_string(), colors)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[Pixel]> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, colors)| colors.as_slice())
+    }
+
+    /// Resolve a color spec: plain hex, or `name:index` into a palette.
+    /// Indexes wrap, so `brand:0`, `brand:1`, ... always land somewhere.
+    pub fn resolve(&self, spec: &str) -> Option<Pixel> {
+        if let Some(color) = parse_hex_color(spec) {
+            return Some(color);
+        }
+        let (name, index) = spec.split_once(':')?;
+        let colors = self.get(name.trim())?;
+        let index: usize = index.trim().parse().ok()?;
+        Some(colors[index % colors.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialization_round_trips() {
+        let mut registry = PaletteRegistry::default();
+        registry.register(
+            "brand",
+            vec![Pixel { r: 255, g: 136, b: 0 }, Pixel { r: 0, g: 64, b: 255 }],
+        );
+        registry.register("mono", vec![Pixel { r: 32, g: 32, b: 32 }]);
+        let parsed = PaletteRegistry::parse(&registry.serialize());
+        assert_eq!(parsed, registry);
+    }
+
+    #[test]
+    fn resolve_accepts_hex_and_palette_references() {
+        let mut registry = PaletteRegistry::default();
+        registry.register("brand", vec![Pixel { r: 10, g: 20, b: 30 }, Pixel { r: 40, g: 50, b: 60 }]);
+        assert_eq!(registry.resolve("#0a141e"), Some(Pixel { r: 10, g: 20, b: 30 }));
+        assert_eq!(registry.resolve("brand:1"), Some(Pixel { r: 40, g: 50, b: 60 }));
+        // Indexes wrap instead of failing.
+        assert_eq!(registry.resolve("brand:2"), Some(Pixel { r: 10, g: 20, b: 30 }));
+        assert_eq!(registry.resolve("other:0"), None);
+        assert_eq!(registry.resolve("nonsense"), None);
+    }
+
+    #[test]
+    fn registering_replaces_an_existing_palette() {
+        let mut registry = PaletteRegistry::default();
+        registry.register("brand", vec![Pixel { r: 1, g: 2, b: 3 }]);
+        registry.register("brand", vec![Pixel { r: 7, g: 8, b: 9 }]);
+        assert_eq!(registry.get("brand"), Some(&[Pixel { r: 7, g: 8, b: 9 }][..]));
+    }
+}
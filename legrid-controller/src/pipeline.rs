@@ -0,0 +1,796 @@
+//! The pixel pipeline: everything that happens to a frame between parsing
+//! and the output driver.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::frame::Pixel;
+
+/// Bit pattern of the scale the power-limit stage last applied (1.0 when
+/// under budget), published for the metrics endpoint without threading a
+/// handle through the stage chain.
+pub static LAST_POWER_SCALE_BITS: AtomicU64 = AtomicU64::new(1.0f64.to_bits());
+
+/// How to fill the gaps between incoming frames when the output rate is
+/// higher than the host's frame rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterpolateMode {
+    None,
+    Linear,
+}
+
+/// Wire order of the color channels on the strip. Logical frames are
+/// always RGB; the remap happens right before the driver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorOrder {
+    Rgb,
+    Rbg,
+    Grb,
+    Gbr,
+    Brg,
+    Bgr,
+}
+
+impl ColorOrder {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "rgb" => Some(ColorOrder::Rgb),
+            "rbg" => Some(ColorOrder::Rbg),
+            "grb" => Some(ColorOrder::Grb),
+            "gbr" => Some(ColorOrder::Gbr),
+            "brg" => Some(ColorOrder::Brg),
+            "bgr" => Some(ColorOrder::Bgr),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorOrder::Rgb => "rgb",
+            ColorOrder::Rbg => "rbg",
+            ColorOrder::Grb => "grb",
+            ColorOrder::Gbr => "gbr",
+            ColorOrder::Brg => "brg",
+            ColorOrder::Bgr => "bgr",
+        }
+    }
+
+    /// Reorder a logical RGB pixel into wire order.
+    pub fn remap(self, p: Pixel) -> Pixel {
+        let (r, g, b) = (p.r, p.g, p.b);
+        let (a, c, d) = match self {
+            ColorOrder::Rgb => (r, g, b),
+            ColorOrder::Rbg => (r, b, g),
+            ColorOrder::Grb => (g, r, b),
+            ColorOrder::Gbr => (g, b, r),
+            ColorOrder::Brg => (b, r, g),
+            ColorOrder::Bgr => (b, g, r),
+        };
+        Pixel { r: a, g: c, b: d }
+    }
+}
+
+pub fn lerp_u8(a: u8, b: u8, t: f64) -> u8 {
+    (a as f64 + (b as f64 - a as f64) * t).round() as u8
+}
+
+/// Which space blends run in. Averaging sRGB-encoded values darkens the
+/// midpoints visibly on a panel; linear-light blends convert at the edges
+/// and mix physically.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendSpace {
+    Srgb,
+    Linear,
+}
+
+impl BlendSpace {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "srgb" => Some(BlendSpace::Srgb),
+            "linear" => Some(BlendSpace::Linear),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BlendSpace::Srgb => "srgb",
+            BlendSpace::Linear => "linear",
+        }
+    }
+}
+
+/// The sRGB transfer function, decode direction (encoded u8 to linear
+/// 0..1).
+pub fn srgb_to_linear(v: u8) -> f64 {
+    let v = v as f64 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The sRGB transfer function, encode direction.
+pub fn linear_to_srgb(v: f64) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let encoded = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+/// Blend one channel in linear light.
+fn lerp_linear(a: u8, b: u8, t: f64) -> u8 {
+    linear_to_srgb(srgb_to_linear(a) + (srgb_to_linear(b) - srgb_to_linear(a)) * t)
+}
+
+/// Per-frame difference between two rendered buffers: (mean absolute
+/// channel delta, maximum channel delta). Used by the A/B comparison
+/// mode to quantify what a pipeline change actually does.
+pub fn frame_delta(a: &[Pixel], b: &[Pixel]) -> (f64, u8) {
+    let mut sum = 0u64;
+    let mut max = 0u8;
+    let n = a.len().min(b.len());
+    for (pa, pb) in a[..n].iter().zip(&b[..n]) {
+        for (ca, cb) in [(pa.r, pb.r), (pa.g, pb.g), (pa.b, pb.b)] {
+            let d = ca.abs_diff(cb);
+            sum += d as u64;
+            max = max.max(d);
+        }
+    }
+    let mean = if n > 0 { sum as f64 / (n * 3) as f64 } else { 0.0 };
+    (mean, max)
+}
+
+/// One stage of the post-processor chain. Stages see the frame as floats
+/// in 0..255 so precision survives across the chain; quantization back to
+/// 8 bits happens after the last stage (or inside a dither stage).
+pub trait PostProcessor: Send {
+    fn name(&self) -> &'static str;
+    fn process(&mut self, frame: &mut [[f64; 3]]);
+}
+
+/// Gamma correction through a power curve; 2.2 is the usual choice for
+/// WS2812-class strips fed sRGB-ish content.
+pub struct GammaStage {
+    gamma: f64,
+}
+
+impl PostProcessor for GammaStage {
+    fn name(&self) -> &'static str {
+        "gamma"
+    }
+
+    fn process(&mut self, frame: &mut [[f64; 3]]) {
+        for px in frame.iter_mut() {
+            for v in px.iter_mut() {
+                *v = 255.0 * (*v / 255.0).powf(self.gamma);
+            }
+        }
+    }
+}
+
+/// Global brightness scale in 0..1.
+pub struct BrightnessStage {
+    scale: f64,
+}
+
+impl PostProcessor for BrightnessStage {
+    fn name(&self) -> &'static str {
+        "brightness"
+    }
+
+    fn process(&mut self, frame: &mut [[f64; 3]]) {
+        for px in frame.iter_mut() {
+            for v in px.iter_mut() {
+                *v *= self.scale;
+            }
+        }
+    }
+}
+
+/// Temporal dither: quantizes here and carries the per-channel rounding
+/// error into the next frame, so sub-bit levels (e.g. after a dim
+/// brightness stage) average out over time instead of banding.
+pub struct DitherStage {
+    residuals: Vec<[f64; 3]>,
+}
+
+impl PostProcessor for DitherStage {
+    fn name(&self) -> &'static str {
+        "dither"
+    }
+
+    fn process(&mut self, frame: &mut [[f64; 3]]) {
+        self.residuals.resize(frame.len(), [0.0; 3]);
+        for (px, residual) in frame.iter_mut().zip(self.residuals.iter_mut()) {
+            for (v, r) in px.iter_mut().zip(residual.iter_mut()) {
+                let target = *v + *r;
+                let quantized = target.round().clamp(0.0, 255.0);
+                *r = target - quantized;
+                *v = quantized;
+            }
+        }
+    }
+}
+
+/// Scales the whole frame down when its estimated draw exceeds the power
+/// budget, so a full-white frame can't brown out the supply. Uses the
+/// usual 20mA-per-channel-at-full model plus 1mA idle per LED.
+pub struct PowerLimitStage {
+    budget_ma: f64,
+}
+
+impl PowerLimitStage {
+    fn estimate_ma(frame: &[[f64; 3]]) -> f64 {
+        let channel_sum: f64 = frame.iter().flatten().sum();
+        channel_sum / 255.0 * 20.0 + frame.len() as f64
+    }
+}
+
+impl PostProcessor for PowerLimitStage {
+    fn name(&self) -> &'static str {
+        "power-limit"
+    }
+
+    fn process(&mut self, frame: &mut [[f64; 3]]) {
+        let estimated = Self::estimate_ma(frame);
+        if estimated <= self.budget_ma {
+            LAST_POWER_SCALE_BITS.store(1.0f64.to_bits(), Ordering::Relaxed);
+            return;
+        }
+        let idle = frame.len() as f64;
+        let scale = ((self.budget_ma - idle) / (estimated - idle)).clamp(0.0, 1.0);
+        LAST_POWER_SCALE_BITS.store(scale.to_bits(), Ordering::Relaxed);
+        for px in frame.iter_mut() {
+            for v in px.iter_mut() {
+                *v *= scale;
+            }
+        }
+    }
+}
+
+/// Pipes the frame through an external command as raw RGB (u32 LE length
+/// prefix, then 3 bytes per LED each way), for installation-specific
+/// effects without rebuilding the controller. A dead child is logged once
+/// and the stage becomes a pass-through.
+pub struct ScriptStage {
+    command: String,
+    child: Option<std::process::Child>,
+    failed: bool,
+}
+
+impl ScriptStage {
+    fn run(&mut self, frame: &mut [[f64; 3]]) -> std::io::Result<()> {
+        use std::io::{Read, Write};
+        use std::process::{Command, Stdio};
+
+        if self.child.is_none() {
+            self.child = Some(
+                Command::new(&self.command)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()?,
+            );
+        }
+        let child = self.child.as_mut().unwrap();
+
+        let bytes: Vec<u8> = frame
+            .iter()
+            .flatten()
+            .map(|v| v.round().clamp(0.0, 255.0) as u8)
+            .collect();
+        let stdin = child.stdin.as_mut().expect("piped stdin");
+        stdin.write_all(&(bytes.len() as u32).to_le_bytes())?;
+        stdin.write_all(&bytes)?;
+        stdin.flush()?;
+
+        let stdout = child.stdout.as_mut().expect("piped stdout");
+        let mut out = vec![0u8; bytes.len()];
+        stdout.read_exact(&mut out)?;
+        for (px, chunk) in frame.iter_mut().zip(out.chunks_exact(3)) {
+            *px = [chunk[0] as f64, chunk[1] as f64, chunk[2] as f64];
+        }
+        Ok(())
+    }
+}
+
+impl PostProcessor for ScriptStage {
+    fn name(&self) -> &'static str {
+        "script"
+    }
+
+    fn process(&mut self, frame: &mut [[f64; 3]]) {
+        if self.failed {
+            return;
+        }
+        if let Err(e) = self.run(frame) {
+            crate::log_warn!("pipeline", "Script stage '{}' failed, passing through: {}", self.command, e);
+            self.child = None;
+            self.failed = true;
+        }
+    }
+}
+
+/// One LED range's color correction, as a 3x3 matrix applied per pixel.
+/// Per-channel gains are just the diagonal case.
+pub struct SegmentCorrection {
+    pub start: usize,
+    /// Inclusive.
+    pub end: usize,
+    /// Row-major: output channel = row · [r, g, b].
+    pub matrix: [[f64; 3]; 3],
+}
+
+/// Parse a correction spec: semicolon-separated segments, each
+/// `start-end:v1,v2,v3` (per-channel gains) or nine values (a row-major
+/// 3x3 matrix). E.g. `0-149:1.0,0.95,0.88;150-299:1.0,1.0,1.0` matches
+/// two strip batches to a common white.
+pub fn parse_correction(spec: &str) -> Result<Vec<SegmentCorrection>, String> {
+    let mut segments = Vec::new();
+    for part in spec.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (range, values) = part
+            .split_once(':')
+            .ok_or_else(|| format!("segment '{}': expected start-end:values", part))?;
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| format!("segment '{}': bad range '{}'", part, range))?;
+        let start: usize = start.trim().parse().map_err(|_| format!("segment '{}': bad range", part))?;
+        let end: usize = end.trim().parse().map_err(|_| format!("segment '{}': bad range", part))?;
+        if end < start {
+            return Err(format!("segment '{}': range ends before it starts", part));
+        }
+        let values: Vec<f64> = values
+            .split(',')
+            .map(|v| v.trim().parse().map_err(|_| format!("segment '{}': bad number", part)))
+            .collect::<Result<_, _>>()?;
+        let matrix = match values.len() {
+            3 => [
+                [values[0], 0.0, 0.0],
+                [0.0, values[1], 0.0],
+                [0.0, 0.0, values[2]],
+            ],
+            9 => [
+                [values[0], values[1], values[2]],
+                [values[3], values[4], values[5]],
+                [values[6], values[7], values[8]],
+            ],
+            n => {
+                return Err(format!(
+                    "segment '{}': expected 3 gains or 9 matrix values, got {}",
+                    part, n
+                ))
+            }
+        };
+        segments.push(SegmentCorrection { start, end, matrix });
+    }
+    if segments.is_empty() {
+        return Err("correction spec is empty".to_string());
+    }
+    Ok(segments)
+}
+
+/// Applies per-segment color correction, so strips from different batches
+/// can be matched to a uniform white. Runs as an ordinary pipeline stage
+/// ("correct"), ahead of dithering and power limiting.
+pub struct CorrectionStage {
+    pub segments: Vec<SegmentCorrection>,
+}
+
+impl PostProcessor for CorrectionStage {
+    fn name(&self) -> &'static str {
+        "correct"
+    }
+
+    fn process(&mut self, frame: &mut [[f64; 3]]) {
+        if frame.is_empty() {
+            return;
+        }
+        for segment in &self.segments {
+            if segment.start >= frame.len() {
+                continue;
+            }
+            let end = segment.end.min(frame.len() - 1);
+            for px in &mut frame[segment.start..=end] {
+                let [r, g, b] = *px;
+                for (out, row) in px.iter_mut().zip(&segment.matrix) {
+                    *out = row[0] * r + row[1] * g + row[2] * b;
+                }
+            }
+        }
+    }
+}
+
+/// Parse a pipeline spec: comma-separated stages in execution order, each
+/// `name` or `name:argument`. Errors name the offending stage.
+pub fn parse_stages(spec: &str) -> Result<Vec<Box<dyn PostProcessor>>, String> {
+    let mut stages: Vec<Box<dyn PostProcessor>> = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (name, arg) = match part.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg.trim())),
+            None => (part, None),
+        };
+        let num = |default: Option<f64>| -> Result<f64, String> {
+            match (arg, default) {
+                (Some(arg), _) => arg.parse().map_err(|_| format!("stage '{}': bad number", part)),
+                (None, Some(default)) => Ok(default),
+                (None, None) => Err(format!("stage '{}': missing argument", part)),
+            }
+        };
+        match name {
+            "gamma" => stages.push(Box::new(GammaStage { gamma: num(Some(2.2))? })),
+            "brightness" => stages.push(Box::new(BrightnessStage { scale: num(None)? })),
+            "dither" => stages.push(Box::new(DitherStage { residuals: Vec::new() })),
+            "power-limit" => stages.push(Box::new(PowerLimitStage { budget_ma: num(None)? })),
+            "script" => {
+                let command = arg.ok_or_else(|| format!("stage '{}': missing command", part))?;
+                stages.push(Box::new(ScriptStage {
+                    command: command.to_string(),
+                    child: None,
+                    failed: false,
+                }));
+            }
+            other => {
+                return Err(format!(
+                    "unknown stage '{}' (expected gamma|brightness|dither|power-limit|script)",
+                    other
+                ))
+            }
+        }
+    }
+    Ok(stages)
+}
+
+/// A stage plus its runtime enable state. Stages can be toggled live for
+/// troubleshooting, optionally with a timer that re-enables them so a
+/// bypassed power limit can't be forgotten.
+struct StageSlot {
+    stage: Box<dyn PostProcessor>,
+    enabled: bool,
+    reenable_at: Option<Instant>,
+}
+
+/// The per-frame color pipeline: an ordered post-processor chain followed
+/// by quantization and the color-order remap (always last; it is a wire
+/// concern, not a color one).
+pub struct PixelPipeline {
+    pub color_order: ColorOrder,
+    /// Space for crossfades and interpolation; quantized content blends
+    /// wrong in sRGB, so installations that care opt into linear.
+    pub blend_space: BlendSpace,
+    stages: Vec<StageSlot>,
+}
+
+impl PixelPipeline {
+    pub fn new(color_order: ColorOrder) -> Self {
+        Self {
+            color_order,
+            blend_space: BlendSpace::Srgb,
+            stages: Vec::new(),
+        }
+    }
+
+    /// Build the stage chain from a `--pipeline` spec.
+    pub fn with_stages(color_order: ColorOrder, spec: &str) -> Result<Self, String> {
+        Ok(Self {
+            color_order,
+            blend_space: BlendSpace::Srgb,
+            stages: parse_stages(spec)?
+                .into_iter()
+                .map(|stage| StageSlot {
+                    stage,
+                    enabled: true,
+                    reenable_at: None,
+                })
+                .collect(),
+        })
+    }
+
+    /// Append a stage built outside the spec (e.g. the color-correction
+    /// stage, whose own spec lives under a separate config key).
+    pub fn push_stage(&mut self, stage: Box<dyn PostProcessor>) {
+        self.stages.push(StageSlot {
+            stage,
+            enabled: true,
+            reenable_at: None,
+        });
+    }
+
+    /// Stage names in chain order; disabled stages are suffixed ":off" so
+    /// the state shows up in stats.
+    pub fn stage_names(&self) -> Vec<String> {
+        self.stages
+            .iter()
+            .map(|slot| {
+                if slot.enabled {
+                    slot.stage.name().to_string()
+                } else {
+                    format!("{}:off", slot.stage.name())
+                }
+            })
+            .collect()
+    }
+
+    /// Toggle a stage by name. Disabling can carry a timeout after which
+    /// the stage re-enables itself on a later frame.
+    pub fn set_stage_enabled(
+        &mut self,
+        name: &str,
+        enabled: bool,
+        reenable_after: Option<Duration>,
+    ) -> Result<(), String> {
+        let mut found = false;
+        for slot in self.stages.iter_mut() {
+            if slot.stage.name() == name {
+                slot.enabled = enabled;
+                slot.reenable_at = match (enabled, reenable_after) {
+                    (false, Some(after)) => Some(Instant::now() + after),
+                    _ => None,
+                };
+                found = true;
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(format!("no '{}' stage in the pipeline", name))
+        }
+    }
+
+    /// Apply the stage chain, producing the wire-ready buffer.
+    pub fn apply(&mut self, pixels: &[Pixel]) -> Vec<Pixel> {
+        for slot in self.stages.iter_mut() {
+            if let Some(at) = slot.reenable_at {
+                if Instant::now() >= at {
+                    crate::log_info!("pipeline", "Re-enabling '{}' stage after timeout", slot.stage.name());
+                    slot.enabled = true;
+                    slot.reenable_at = None;
+                }
+            }
+        }
+
+        if self.stages.is_empty() {
+            return if self.color_order == ColorOrder::Rgb {
+                pixels.to_vec()
+            } else {
+                pixels.iter().map(|&p| self.color_order.remap(p)).collect()
+            };
+        }
+
+        let mut frame: Vec<[f64; 3]> = pixels
+            .iter()
+            .map(|p| [p.r as f64, p.g as f64, p.b as f64])
+            .collect();
+        for slot in self.stages.iter_mut().filter(|s| s.enabled) {
+            slot.stage.process(&mut frame);
+        }
+        frame
+            .iter()
+            .map(|px| {
+                let quantized = Pixel {
+                    r: px[0].round().clamp(0.0, 255.0) as u8,
+                    g: px[1].round().clamp(0.0, 255.0) as u8,
+                    b: px[2].round().clamp(0.0, 255.0) as u8,
+                };
+                self.color_order.remap(quantized)
+            })
+            .collect()
+    }
+
+    /// Blend between two frames. `t` is clamped to [0, 1]; 0 shows `prev`,
+    /// 1 shows `current`.
+    pub fn blend(&self, prev: &[Pixel], current: &[Pixel], mode: InterpolateMode, t: f64) -> Vec<Pixel> {
+        match mode {
+            InterpolateMode::None => current.to_vec(),
+            InterpolateMode::Linear => {
+                let t = t.clamp(0.0, 1.0);
+                let lerp = match self.blend_space {
+                    BlendSpace::Srgb => lerp_u8,
+                    BlendSpace::Linear => lerp_linear,
+                };
+                prev.iter()
+                    .zip(current.iter())
+                    .map(|(a, b)| Pixel {
+                        r: lerp(a.r, b.r, t),
+                        g: lerp(a.g, b.g, t),
+                        b: lerp(a.b, b.b, t),
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_order_round_trips_through_parse_and_name() {
+        for name in ["rgb", "rbg", "grb", "gbr", "brg", "bgr"] {
+            assert_eq!(ColorOrder::parse(name).unwrap().name(), name);
+        }
+        assert!(ColorOrder::parse("rrb").is_none());
+    }
+
+    #[test]
+    fn grb_remap_swaps_red_and_green() {
+        let p = Pixel { r: 10, g: 20, b: 30 };
+        assert_eq!(ColorOrder::Grb.remap(p), Pixel { r: 20, g: 10, b: 30 });
+    }
+
+    #[test]
+    fn identity_order_is_a_no_op() {
+        let mut pipeline = PixelPipeline::new(ColorOrder::Rgb);
+        let pixels = vec![Pixel { r: 1, g: 2, b: 3 }];
+        assert_eq!(pipeline.apply(&pixels), pixels);
+    }
+
+    #[test]
+    fn stage_spec_preserves_order() {
+        let pipeline = PixelPipeline::with_stages(ColorOrder::Rgb, "brightness:0.5,gamma:2.2,dither").unwrap();
+        assert_eq!(pipeline.stage_names(), vec!["brightness", "gamma", "dither"]);
+    }
+
+    #[test]
+    fn stage_spec_errors_name_the_stage() {
+        let err = parse_stages("gamma,sharpen").err().unwrap();
+        assert!(err.contains("sharpen"), "{}", err);
+        let err = parse_stages("brightness").err().unwrap();
+        assert!(err.contains("brightness"), "{}", err);
+    }
+
+    #[test]
+    fn brightness_scales_and_gamma_curves() {
+        let mut pipeline = PixelPipeline::with_stages(ColorOrder::Rgb, "brightness:0.5").unwrap();
+        let out = pipeline.apply(&[Pixel { r: 200, g: 100, b: 0 }]);
+        assert_eq!(out[0], Pixel { r: 100, g: 50, b: 0 });
+
+        let mut pipeline = PixelPipeline::with_stages(ColorOrder::Rgb, "gamma:2.0").unwrap();
+        let out = pipeline.apply(&[Pixel { r: 255, g: 128, b: 0 }]);
+        // Endpoints survive; the midpoint drops to ~a quarter.
+        assert_eq!(out[0].r, 255);
+        assert_eq!(out[0].b, 0);
+        assert!((60..=68).contains(&out[0].g), "got {}", out[0].g);
+    }
+
+    #[test]
+    fn dither_recovers_sub_bit_levels_over_time() {
+        let mut pipeline = PixelPipeline::with_stages(ColorOrder::Rgb, "brightness:0.25,dither").unwrap();
+        let total: u32 = (0..8)
+            .map(|_| pipeline.apply(&[Pixel { r: 2, g: 0, b: 0 }])[0].r as u32)
+            .sum();
+        // 2 * 0.25 = 0.5 per frame; over 8 frames the dither should emit
+        // roughly 4 counts where plain rounding would give 0 or 8.
+        assert_eq!(total, 4);
+    }
+
+    #[test]
+    fn power_limit_caps_a_white_frame() {
+        let mut pipeline = PixelPipeline::with_stages(ColorOrder::Rgb, "power-limit:301").unwrap();
+        // 10 full-white LEDs: 10 * 60mA + 10mA idle = 610mA estimated.
+        let white = vec![Pixel { r: 255, g: 255, b: 255 }; 10];
+        let out = pipeline.apply(&white);
+        assert!(out[0].r < 255);
+        // A dim frame under budget is untouched.
+        let dim = vec![Pixel { r: 10, g: 10, b: 10 }; 10];
+        let out = pipeline.apply(&dim);
+        assert_eq!(out[0], dim[0]);
+    }
+
+    #[test]
+    fn correction_applies_per_segment() {
+        let segments = parse_correction("0-0:1.0,0.5,0.5;2-5:0.0,1.0,0.0,1.0,0.0,0.0,0.0,0.0,1.0").unwrap();
+        let mut pipeline = PixelPipeline::new(ColorOrder::Rgb);
+        pipeline.push_stage(Box::new(CorrectionStage { segments }));
+        assert_eq!(pipeline.stage_names(), vec!["correct"]);
+
+        let frame = vec![Pixel { r: 100, g: 200, b: 40 }; 3];
+        let out = pipeline.apply(&frame);
+        // Segment 0: green/blue gains halved.
+        assert_eq!(out[0], Pixel { r: 100, g: 100, b: 20 });
+        // Uncorrected pixel passes through.
+        assert_eq!(out[1], frame[1]);
+        // Segment 2: red/green swap matrix; ranges past the frame clip.
+        assert_eq!(out[2], Pixel { r: 200, g: 100, b: 40 });
+    }
+
+    #[test]
+    fn correction_spec_errors_name_the_segment() {
+        assert!(parse_correction("").is_err());
+        let err = parse_correction("5-2:1,1,1").err().unwrap();
+        assert!(err.contains("5-2"), "{}", err);
+        let err = parse_correction("0-9:1,1").err().unwrap();
+        assert!(err.contains("3 gains or 9"), "{}", err);
+    }
+
+    #[test]
+    fn disabled_stages_are_skipped_and_reported() {
+        let mut pipeline = PixelPipeline::with_stages(ColorOrder::Rgb, "brightness:0.5").unwrap();
+        pipeline.set_stage_enabled("brightness", false, None).unwrap();
+        assert_eq!(pipeline.stage_names(), vec!["brightness:off"]);
+        let out = pipeline.apply(&[Pixel { r: 200, g: 100, b: 0 }]);
+        assert_eq!(out[0], Pixel { r: 200, g: 100, b: 0 });
+
+        pipeline.set_stage_enabled("brightness", true, None).unwrap();
+        let out = pipeline.apply(&[Pixel { r: 200, g: 100, b: 0 }]);
+        assert_eq!(out[0], Pixel { r: 100, g: 50, b: 0 });
+
+        let err = pipeline.set_stage_enabled("dither", false, None).err().unwrap();
+        assert!(err.contains("dither"), "{}", err);
+    }
+
+    #[test]
+    fn bypassed_stage_reenables_after_the_timeout() {
+        let mut pipeline = PixelPipeline::with_stages(ColorOrder::Rgb, "brightness:0.5").unwrap();
+        pipeline
+            .set_stage_enabled("brightness", false, Some(Duration::from_millis(10)))
+            .unwrap();
+        let out = pipeline.apply(&[Pixel { r: 200, g: 0, b: 0 }]);
+        assert_eq!(out[0].r, 200);
+        std::thread::sleep(Duration::from_millis(15));
+        let out = pipeline.apply(&[Pixel { r: 200, g: 0, b: 0 }]);
+        assert_eq!(out[0].r, 100);
+    }
+
+    #[test]
+    fn frame_delta_reports_mean_and_worst_channel() {
+        let a = vec![Pixel { r: 100, g: 100, b: 100 }; 2];
+        assert_eq!(frame_delta(&a, &a), (0.0, 0));
+
+        let b = vec![
+            Pixel { r: 110, g: 100, b: 100 },
+            Pixel { r: 100, g: 100, b: 70 },
+        ];
+        // Deltas: 10 and 30 across 6 channels.
+        let (mean, max) = frame_delta(&a, &b);
+        assert!((mean - 40.0 / 6.0).abs() < 1e-9, "got {}", mean);
+        assert_eq!(max, 30);
+    }
+
+    #[test]
+    fn linear_blend_hits_the_midpoint() {
+        let pipeline = PixelPipeline::new(ColorOrder::Rgb);
+        let prev = vec![Pixel { r: 0, g: 0, b: 0 }];
+        let next = vec![Pixel { r: 100, g: 200, b: 50 }];
+        let mid = pipeline.blend(&prev, &next, InterpolateMode::Linear, 0.5);
+        assert_eq!(mid[0], Pixel { r: 50, g: 100, b: 25 });
+    }
+
+    #[test]
+    fn srgb_transfer_round_trips() {
+        for v in 0..=255u8 {
+            assert_eq!(linear_to_srgb(srgb_to_linear(v)), v);
+        }
+    }
+
+    #[test]
+    fn linear_light_blend_lifts_the_midpoint() {
+        let mut pipeline = PixelPipeline::new(ColorOrder::Rgb);
+        pipeline.blend_space = BlendSpace::Linear;
+        let black = vec![Pixel::BLACK];
+        let white = vec![Pixel { r: 255, g: 255, b: 255 }];
+        let mid = pipeline.blend(&black, &white, InterpolateMode::Linear, 0.5);
+        // Half linear energy encodes to ~188 sRGB, not the naive 128.
+        assert_eq!(mid[0].r, 188);
+    }
+
+    #[test]
+    fn blend_clamps_t() {
+        let pipeline = PixelPipeline::new(ColorOrder::Rgb);
+        let prev = vec![Pixel { r: 0, g: 0, b: 0 }];
+        let next = vec![Pixel { r: 100, g: 100, b: 100 }];
+        assert_eq!(pipeline.blend(&prev, &next, InterpolateMode::Linear, 7.0), next);
+    }
+}
@@ -0,0 +1,465 @@
+//! Time-of-day color profiles.
+//!
+//! `--profiles "07:00=1.0:6500;20:00=0.6:3200;23:00=0.25:2700"` keys a
+//! brightness scale and a color temperature to clock times; the controller
+//! interpolates smoothly between adjacent keyframes (wrapping midnight)
+//! and applies the result ahead of the pipeline — cool and bright at noon,
+//! warm and dim at night, with no host involvement. Times are local via
+//! the `utc_offset` config key.
+//!
+//! With `latitude`/`longitude` configured, keyframes can also be keyed
+//! to the sun — `sunset=0.9:3000` or `sunrise-30=1.0:6500` (offsets in
+//! minutes) — so outdoor panels track dusk across the seasons instead
+//! of a fixed clock time.
+
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Wall-clock seconds below this (2020-01-01) mean the clock was never
+/// set: the Pi booted without NTP and without saved clock state. Time-
+/// keyed features sit out rather than act on a bogus hour.
+const CLOCK_TRUSTED_AFTER: i64 = 1_577_836_800;
+
+/// Wall time drifting more than this from the monotonic clock between
+/// reads counts as a jump — NTP finally syncing, usually.
+const JUMP_THRESHOLD_SECS: i64 = 5;
+
+/// One keyframe: a time of day, a brightness scale, and a white point in
+/// kelvin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilePoint {
+    pub minutes: u32,
+    pub brightness: f64,
+    pub kelvin: f64,
+}
+
+/// When a keyframe fires: a fixed minute of the local day, or sunrise/
+/// sunset plus an offset in minutes. Solar specs resolve to a different
+/// minute each day — that's the point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSpec {
+    Fixed(u32),
+    Sunrise(i32),
+    Sunset(i32),
+}
+
+impl TimeSpec {
+    /// Parse `HH:MM`, `sunrise`, `sunset`, or the solar forms with a
+    /// `+MM`/`-MM` minute offset (`sunset+30`).
+    pub fn parse(s: &str) -> Option<TimeSpec> {
+        let s = s.trim();
+        for (keyword, build) in [
+            ("sunrise", TimeSpec::Sunrise as fn(i32) -> TimeSpec),
+            ("sunset", TimeSpec::Sunset as fn(i32) -> TimeSpec),
+        ] {
+            if let Some(rest) = s.strip_prefix(keyword) {
+                let offset = match rest.trim() {
+                    "" => 0,
+                    signed => signed.parse().ok()?,
+                };
+                return Some(build(offset));
+            }
+        }
+        let (hours, mins) = s.split_once(':')?;
+        let hours: u32 = hours.trim().parse().ok()?;
+        let mins: u32 = mins.trim().parse().ok()?;
+        if hours >= 24 || mins >= 60 {
+            return None;
+        }
+        Some(TimeSpec::Fixed(hours * 60 + mins))
+    }
+
+    /// The minute of the local day this spec lands on, given that day's
+    /// sunrise/sunset in local minutes. Solar specs resolve to `None`
+    /// when there is no sun data — no site configured, or polar day or
+    /// night — and the keyframe sits the day out.
+    pub fn resolve(&self, solar: Option<(f64, f64)>) -> Option<u32> {
+        let minutes = match (self, solar) {
+            (TimeSpec::Fixed(m), _) => *m as f64,
+            (TimeSpec::Sunrise(off), Some((rise, _))) => rise + *off as f64,
+            (TimeSpec::Sunset(off), Some((_, set))) => set + *off as f64,
+            _ => return None,
+        };
+        Some(minutes.rem_euclid(MINUTES_PER_DAY as f64) as u32)
+    }
+}
+
+/// One unresolved keyframe out of the profile spec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileKeyframe {
+    pub time: TimeSpec,
+    pub brightness: f64,
+    pub kelvin: f64,
+}
+
+/// Parse `HH:MM=brightness:kelvin` keyframes separated by ';'; `HH:MM`
+/// may also be `sunrise`/`sunset` with a minute offset.
+pub fn parse_profiles(spec: &str) -> Result<Vec<ProfileKeyframe>, String> {
+    let mut points = Vec::new();
+    for part in spec.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let bad = || format!("profile '{}': expected HH:MM=brightness:kelvin", part);
+        let (time, values) = part.split_once('=').ok_or_else(bad)?;
+        let (brightness, kelvin) = values.split_once(':').ok_or_else(bad)?;
+        points.push(ProfileKeyframe {
+            time: TimeSpec::parse(time).ok_or_else(bad)?,
+            brightness: brightness.trim().parse().map_err(|_| bad())?,
+            kelvin: kelvin.trim().parse().map_err(|_| bad())?,
+        });
+    }
+    if points.is_empty() {
+        return Err("profile spec is empty".to_string());
+    }
+    Ok(points)
+}
+
+/// Resolve keyframes against a day's sun times into sorted points ready
+/// for [`evaluate`]. Solar keyframes without sun data drop out for the
+/// day.
+pub fn resolve_profiles(
+    keyframes: &[ProfileKeyframe],
+    solar: Option<(f64, f64)>,
+) -> Vec<ProfilePoint> {
+    let mut points: Vec<ProfilePoint> = keyframes
+        .iter()
+        .filter_map(|k| {
+            Some(ProfilePoint {
+                minutes: k.time.resolve(solar)?,
+                brightness: k.brightness,
+                kelvin: k.kelvin,
+            })
+        })
+        .collect();
+    points.sort_by_key(|p| p.minutes);
+    points
+}
+
+/// White-point multipliers for a color temperature, normalized so the
+/// largest channel is 1. The usual blackbody curve-fit approximation;
+/// plenty for warm/cool shifts on a WS2812 panel.
+pub fn kelvin_to_rgb(kelvin: f64) -> [f64; 3] {
+    let t = kelvin.clamp(1000.0, 12000.0) / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let g = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+    let rgb = [r.clamp(0.0, 255.0), g.clamp(0.0, 255.0), b.clamp(0.0, 255.0)];
+    let max = rgb[0].max(rgb[1]).max(rgb[2]).max(1.0);
+    [rgb[0] / max, rgb[1] / max, rgb[2] / max]
+}
+
+/// Evaluate the profile at a minute of the day: linear interpolation
+/// between the surrounding keyframes, wrapping across midnight.
+pub fn evaluate(points: &[ProfilePoint], minutes: u32) -> (f64, [f64; 3]) {
+    let minutes = minutes % MINUTES_PER_DAY;
+    if points.len() == 1 {
+        return (points[0].brightness, kelvin_to_rgb(points[0].kelvin));
+    }
+
+    // The segment is [prev, next) in wrapped time.
+    let next_idx = points
+        .iter()
+        .position(|p| p.minutes > minutes)
+        .unwrap_or(0);
+    let prev_idx = (next_idx + points.len() - 1) % points.len();
+    let (prev, next) = (&points[prev_idx], &points[next_idx]);
+
+    let span = (next.minutes + MINUTES_PER_DAY - prev.minutes) % MINUTES_PER_DAY;
+    let into = (minutes + MINUTES_PER_DAY - prev.minutes) % MINUTES_PER_DAY;
+    let t = if span == 0 { 0.0 } else { into as f64 / span as f64 };
+
+    let brightness = prev.brightness + (next.brightness - prev.brightness) * t;
+    let kelvin = prev.kelvin + (next.kelvin - prev.kelvin) * t;
+    (brightness, kelvin_to_rgb(kelvin))
+}
+
+/// Pick the best epoch reading: the system clock when it is clearly set,
+/// otherwise the RTC when it is, otherwise nothing.
+fn best_source(system: i64, rtc: Option<i64>) -> Option<i64> {
+    if system >= CLOCK_TRUSTED_AFTER {
+        return Some(system);
+    }
+    rtc.filter(|s| *s >= CLOCK_TRUSTED_AFTER)
+}
+
+/// Read a battery-backed RTC (a DS3231 on I2C, typically) through the
+/// kernel's sysfs view, so no device ioctls are needed.
+pub fn rtc_epoch_secs(device: &str) -> Option<i64> {
+    let path = format!("/sys/class/rtc/{}/since_epoch", device);
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The wall clock as scheduled features should see it: `None` until some
+/// source (NTP'd system clock or the RTC) is plausibly set, with jump
+/// detection against the monotonic clock so a late NTP sync is visible
+/// in the logs instead of silently snapping night mode to a new hour.
+pub struct WallClock {
+    rtc_device: Option<String>,
+    /// Last accepted (epoch, monotonic) pair, for jump detection.
+    last: Option<(i64, Instant)>,
+}
+
+impl WallClock {
+    pub fn new(rtc_device: Option<String>) -> Self {
+        Self { rtc_device, last: None }
+    }
+
+    /// Current epoch seconds, or `None` when no source is trustworthy.
+    pub fn now_secs(&mut self) -> Option<i64> {
+        let system = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let rtc = self.rtc_device.as_deref().and_then(rtc_epoch_secs);
+        let reading = best_source(system, rtc)?;
+
+        let now = Instant::now();
+        if let Some((last_secs, last_instant)) = self.last {
+            let expected = last_secs + now.duration_since(last_instant).as_secs() as i64;
+            if (reading - expected).abs() > JUMP_THRESHOLD_SECS {
+                crate::log_warn!("profiles",
+                    "Wall clock jumped {}s (NTP sync?); schedules re-evaluate from the new time",
+                    reading - expected
+                );
+            }
+        }
+        self.last = Some((reading, now));
+        Some(reading)
+    }
+}
+
+/// One do-not-disturb window, in minutes of the local day. A window whose
+/// end precedes its start wraps across midnight (22:00-07:00).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DndWindow {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Parse `HH:MM-HH:MM` quiet windows separated by ';'.
+pub fn parse_dnd(spec: &str) -> Result<Vec<DndWindow>, String> {
+    let mut windows = Vec::new();
+    for part in spec.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let bad = || format!("dnd window '{}': expected HH:MM-HH:MM", part);
+        let (start, end) = part.split_once('-').ok_or_else(bad)?;
+        let minutes_of = |time: &str| -> Result<u32, String> {
+            let (hours, mins) = time.trim().split_once(':').ok_or_else(bad)?;
+            let hours: u32 = hours.trim().parse().map_err(|_| bad())?;
+            let mins: u32 = mins.trim().parse().map_err(|_| bad())?;
+            if hours >= 24 || mins >= 60 {
+                return Err(bad());
+            }
+            Ok(hours * 60 + mins)
+        };
+        windows.push(DndWindow { start: minutes_of(start)?, end: minutes_of(end)? });
+    }
+    if windows.is_empty() {
+        return Err("dnd spec is empty".to_string());
+    }
+    Ok(windows)
+}
+
+/// Whether a minute of the day falls inside any window; the start is
+/// inclusive, the end exclusive, and wrapped windows cover midnight.
+pub fn in_dnd(windows: &[DndWindow], minutes: u32) -> bool {
+    let minutes = minutes % MINUTES_PER_DAY;
+    windows.iter().any(|w| {
+        if w.start <= w.end {
+            minutes >= w.start && minutes < w.end
+        } else {
+            minutes >= w.start || minutes < w.end
+        }
+    })
+}
+
+/// Quiet windows keyed to the wall clock, like [`TimeOfDayProfile`]:
+/// while one is active, notifications wait and one-shot effects are
+/// dropped.
+pub struct DndSchedule {
+    windows: Vec<DndWindow>,
+    utc_offset_minutes: i32,
+}
+
+impl DndSchedule {
+    pub fn new(windows: Vec<DndWindow>, utc_offset_hours: f64) -> Self {
+        Self {
+            windows,
+            utc_offset_minutes: (utc_offset_hours * 60.0) as i32,
+        }
+    }
+
+    /// Whether a quiet window is active at the given wall-clock time.
+    /// The caller supplies epoch seconds from a [`WallClock`], so an
+    /// unset clock means "no reading" rather than 1970's schedule.
+    pub fn active_at(&self, epoch_secs: i64) -> bool {
+        let local = epoch_secs / 60 + self.utc_offset_minutes as i64;
+        in_dnd(&self.windows, local.rem_euclid(MINUTES_PER_DAY as i64) as u32)
+    }
+}
+
+pub struct TimeOfDayProfile {
+    keyframes: Vec<ProfileKeyframe>,
+    utc_offset_minutes: i32,
+    /// (latitude, longitude) in degrees for solar keyframes.
+    location: Option<(f64, f64)>,
+}
+
+impl TimeOfDayProfile {
+    pub fn new(
+        keyframes: Vec<ProfileKeyframe>,
+        utc_offset_hours: f64,
+        location: Option<(f64, f64)>,
+    ) -> Self {
+        Self {
+            keyframes,
+            utc_offset_minutes: (utc_offset_hours * 60.0) as i32,
+            location,
+        }
+    }
+
+    /// The day's sunrise/sunset in local minutes, when a site is
+    /// configured and the sun crosses the horizon that day.
+    fn solar_minutes(&self, epoch_secs: i64) -> Option<(f64, f64)> {
+        let (lat, lon) = self.location?;
+        let (rise, set) = crate::solar::sun_minutes_utc(epoch_secs, lat, lon)?;
+        let off = self.utc_offset_minutes as f64;
+        Some((rise + off, set + off))
+    }
+
+    /// The (brightness, white point) for the given wall-clock time, in
+    /// epoch seconds from a [`WallClock`]. Neutral when nothing resolves
+    /// — an all-solar profile in polar night leaves the panel alone.
+    pub fn current_at(&self, epoch_secs: i64) -> (f64, [f64; 3]) {
+        let points = resolve_profiles(&self.keyframes, self.solar_minutes(epoch_secs));
+        if points.is_empty() {
+            return (1.0, [1.0; 3]);
+        }
+        let local = epoch_secs / 60 + self.utc_offset_minutes as i64;
+        evaluate(&points, local.rem_euclid(MINUTES_PER_DAY as i64) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_sorts_keyframes() {
+        let keyframes = parse_profiles("20:00=0.6:3200;07:00=1.0:6500").unwrap();
+        assert_eq!(keyframes.len(), 2);
+        assert_eq!(keyframes[1].time, TimeSpec::Fixed(7 * 60));
+        assert_eq!(keyframes[0].brightness, 0.6);
+        let points = resolve_profiles(&keyframes, None);
+        assert_eq!(points[0].minutes, 7 * 60);
+        assert!(parse_profiles("25:00=1:6500").is_err());
+        assert!(parse_profiles("07:00=1.0").is_err());
+    }
+
+    #[test]
+    fn solar_keyframes_resolve_against_the_day() {
+        assert_eq!(TimeSpec::parse("sunset+30"), Some(TimeSpec::Sunset(30)));
+        assert_eq!(TimeSpec::parse("sunrise-15"), Some(TimeSpec::Sunrise(-15)));
+        assert_eq!(TimeSpec::parse("sundown"), None);
+
+        let keyframes = parse_profiles("sunrise=1.0:6500;sunset+30=0.5:3000").unwrap();
+        let solar = Some((6.0 * 60.0, 18.0 * 60.0));
+        let points = resolve_profiles(&keyframes, solar);
+        assert_eq!(points[0].minutes, 6 * 60);
+        assert_eq!(points[1].minutes, 18 * 60 + 30);
+        // Without sun data the solar keyframes sit the day out.
+        assert!(resolve_profiles(&keyframes, None).is_empty());
+    }
+
+    #[test]
+    fn all_solar_profile_is_neutral_without_a_site() {
+        let keyframes = parse_profiles("sunset=0.5:3000").unwrap();
+        let profile = TimeOfDayProfile::new(keyframes, 0.0, None);
+        let (brightness, white) = profile.current_at(CLOCK_TRUSTED_AFTER);
+        assert_eq!(brightness, 1.0);
+        assert_eq!(white, [1.0; 3]);
+    }
+
+    #[test]
+    fn warm_temperatures_cut_blue() {
+        let warm = kelvin_to_rgb(2700.0);
+        let cool = kelvin_to_rgb(6500.0);
+        assert!(warm[2] < cool[2]);
+        assert!((warm[0] - 1.0).abs() < 1e-9); // red leads when warm
+    }
+
+    #[test]
+    fn interpolates_between_keyframes() {
+        let points = resolve_profiles(&parse_profiles("06:00=1.0:6500;18:00=0.5:6500").unwrap(), None);
+        let (brightness, _) = evaluate(&points, 12 * 60);
+        assert!((brightness - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unset_clocks_are_rejected_until_a_source_is_set() {
+        // A 1970-ish system clock yields nothing without an RTC, the RTC
+        // wins while only it is set, and the system clock wins once NTP
+        // lands.
+        assert_eq!(best_source(12_345, None), None);
+        assert_eq!(best_source(12_345, Some(1_700_000_000)), Some(1_700_000_000));
+        assert_eq!(best_source(12_345, Some(100)), None); // RTC battery dead
+        assert_eq!(best_source(1_750_000_000, Some(1_700_000_000)), Some(1_750_000_000));
+    }
+
+    #[test]
+    fn schedules_evaluate_from_supplied_epoch_seconds() {
+        let points = parse_profiles("06:00=1.0:6500;18:00=0.5:6500").unwrap();
+        let profile = TimeOfDayProfile::new(points, 0.0, None);
+        // 2020-01-01 12:00 UTC is halfway between the keyframes.
+        let (brightness, _) = profile.current_at(CLOCK_TRUSTED_AFTER + 12 * 3600);
+        assert!((brightness - 0.75).abs() < 1e-9);
+
+        let windows = parse_dnd("22:00-07:00").unwrap();
+        let dnd = DndSchedule::new(windows, 0.0);
+        assert!(dnd.active_at(CLOCK_TRUSTED_AFTER + 23 * 3600));
+        assert!(!dnd.active_at(CLOCK_TRUSTED_AFTER + 12 * 3600));
+    }
+
+    #[test]
+    fn dnd_windows_wrap_midnight() {
+        let windows = parse_dnd("22:00-07:00;13:30-14:00").unwrap();
+        assert!(in_dnd(&windows, 23 * 60));
+        assert!(in_dnd(&windows, 3 * 60));
+        assert!(!in_dnd(&windows, 12 * 60));
+        assert!(in_dnd(&windows, 13 * 60 + 45));
+        // The end is exclusive, so delivery resumes exactly on it.
+        assert!(!in_dnd(&windows, 7 * 60));
+        assert!(parse_dnd("22:00").is_err());
+        assert!(parse_dnd("25:00-07:00").is_err());
+    }
+
+    #[test]
+    fn wraps_across_midnight() {
+        let points = resolve_profiles(&parse_profiles("22:00=0.4:3000;02:00=0.2:3000").unwrap(), None);
+        let (brightness, _) = evaluate(&points, 0); // midnight, halfway
+        assert!((brightness - 0.3).abs() < 1e-9);
+        // Outside the night window we are on the 02:00 -> 22:00 segment.
+        let (brightness, _) = evaluate(&points, 12 * 60);
+        assert!((brightness - 0.3).abs() < 1e-9);
+    }
+}
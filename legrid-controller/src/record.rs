@@ -0,0 +1,91 @@
+//! Frame recording and playback.
+//!
+//! `.legrid` files capture the incoming frame stream with timestamps:
+//! an 8-byte magic, then per frame a u64 offset in microseconds from the
+//! start of the recording, a u32 payload length, and the raw frame message.
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub const RECORDING_MAGIC: &[u8; 8] = b"LEGRID01";
+
+pub struct FrameRecorder {
+    file: io::BufWriter<std::fs::File>,
+    started: Instant,
+    pub frames_written: u64,
+}
+
+impl FrameRecorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        file.write_all(RECORDING_MAGIC)?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+            frames_written: 0,
+        })
+    }
+
+    pub fn record(&mut self, frame_data: &[u8]) -> io::Result<()> {
+        let offset_us = self.started.elapsed().as_micros() as u64;
+        self.file.write_all(&offset_us.to_le_bytes())?;
+        self.file.write_all(&(frame_data.len() as u32).to_le_bytes())?;
+        self.file.write_all(frame_data)?;
+        self.file.flush()?;
+        self.frames_written += 1;
+        Ok(())
+    }
+}
+
+/// Parse a .legrid recording into (offset, payload) records.
+pub fn read_recording(path: &Path) -> io::Result<Vec<(Duration, Vec<u8>)>> {
+    let data = std::fs::read(path)?;
+    if !data.starts_with(RECORDING_MAGIC) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a .legrid recording"));
+    }
+    let mut records = Vec::new();
+    let mut pos = RECORDING_MAGIC.len();
+    while pos + 12 <= data.len() {
+        let offset_us = u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
+        let length = u32::from_le_bytes(data[pos + 8..pos + 12].try_into().unwrap()) as usize;
+        pos += 12;
+        if pos + length > data.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Truncated recording"));
+        }
+        records.push((Duration::from_micros(offset_us), data[pos..pos + length].to_vec()));
+        pos += length;
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_round_trips() {
+        let path = std::env::temp_dir().join("legrid-record-test.legrid");
+        {
+            let mut rec = FrameRecorder::create(&path).unwrap();
+            rec.record(&[1, 2, 3]).unwrap();
+            rec.record(&[4, 5]).unwrap();
+            assert_eq!(rec.frames_written, 2);
+        }
+        let records = read_recording(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].1, vec![1, 2, 3]);
+        assert_eq!(records[1].1, vec![4, 5]);
+        assert!(records[1].0 >= records[0].0);
+    }
+
+    #[test]
+    fn rejects_wrong_magic() {
+        let path = std::env::temp_dir().join("legrid-record-bad.legrid");
+        std::fs::write(&path, b"NOTLEGRI").unwrap();
+        let err = read_recording(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
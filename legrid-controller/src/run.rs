@@ -0,0 +1,838 @@
+//! Top-level run modes: the stdin-driven main loop plus the standalone
+//! modes (test patterns, watch mode, playback, the color-order assistant).
+
+use std::io;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::content::{load_content, scan_content_dir};
+use crate::controller::{capabilities_json, dispatch_message, ready_json, LEDController};
+use crate::effects::{render_test_pattern, IdleAnimator, IdleEffect};
+use crate::frame::Pixel;
+use crate::http::UploadServer;
+use crate::metrics::MetricsServer;
+use crate::pipeline::{ColorOrder, InterpolateMode};
+use crate::record::FrameRecorder;
+use crate::transport::{send_message, spawn_stdin_reader};
+
+/// Set from the SIGHUP handler; the main loop picks it up between frames.
+#[cfg(unix)]
+static RELOAD_REQUESTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Install the SIGHUP hot-reload hook. Registered against libc directly so
+/// the handler stays a single atomic store, which is all that's
+/// async-signal-safe anyway.
+#[cfg(unix)]
+fn install_sighup_handler() {
+    unsafe extern "C" fn on_sighup(_signum: i32) {
+        RELOAD_REQUESTED.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    unsafe extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+    }
+    const SIGHUP: i32 = 1;
+    unsafe {
+        signal(SIGHUP, on_sighup as *const () as usize);
+    }
+}
+
+/// Flush everything to the SD card; on the power-loss path the supply
+/// may be gone in moments and a torn write costs more than a lost one.
+#[cfg(unix)]
+fn sync_filesystems() {
+    unsafe extern "C" {
+        fn sync();
+    }
+    unsafe { sync() };
+}
+
+#[cfg(not(unix))]
+fn sync_filesystems() {}
+
+/// Reload when SIGHUP arrived since the last tick.
+fn take_reload_request() -> bool {
+    #[cfg(unix)]
+    {
+        RELOAD_REQUESTED.swap(false, std::sync::atomic::Ordering::Relaxed)
+    }
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+/// The binary's entry point, minus allocator setup: parse the config,
+/// pick a run mode, and drive it to completion.
+pub fn run(args: &[String]) -> io::Result<()> {
+    // Utility subcommands run and exit before any controller setup.
+    if args.get(1).is_some_and(|a| a == "transcode") {
+        return crate::transcode::run_transcode(&args[1..]);
+    }
+    if args.get(1).is_some_and(|a| a == "bench") {
+        return crate::bench::run_bench(&args[1..]);
+    }
+    if args.get(1).is_some_and(|a| a == "simulate-sender") {
+        return crate::simulate::run_simulate(&args[1..]);
+    }
+
+    let config = crate::config::parse_args(args)?;
+    crate::log::init(config.log_level, config.log_format);
+    let output_fps = config.output_fps;
+    let interpolate = config.interpolate;
+
+    // --capabilities: print the format matrix as plain JSON and exit, for
+    // offline feature detection by sender tooling.
+    if args.iter().any(|a| a == "--capabilities") {
+        println!("{}", capabilities_json(&config));
+        return Ok(());
+    }
+
+    crate::log_debug!("run", "Starting: {}x{}, {} LEDs on pin {}",
+              config.width, config.height, config.led_count, config.led_pin);
+
+    // Handshake: report capabilities to the host before any frames flow.
+    if let Err(e) = send_message(&capabilities_json(&config)) {
+        crate::log_warn!("run", "Error sending capabilities handshake: {}", e);
+    }
+
+    let mut controller = LEDController::new(config)?;
+    let mut frame_count: u64 = 0;
+
+    // Under a Type=notify unit: the driver is up, so the service is ready.
+    // Watchdog keepalives follow from the main loop.
+    let mut sd = crate::systemd::SdNotify::from_env();
+    sd.ready();
+
+    // Chained operation: relay the frame stream to downstream controllers
+    // and fold their stats into ours.
+    if !controller.config.forward_addrs.is_empty() {
+        let addrs = controller.config.forward_addrs.clone();
+        crate::log_info!("run", "Forwarding frames to {} downstream controller(s)", addrs.len());
+        controller.forwarder = Some(crate::chain::ChainForwarder::new(&addrs)?);
+    }
+
+    // Interactive color-order detection; stdin carries the operator's
+    // answers instead of frames.
+    if controller.config.detect_color_order {
+        return run_color_order_assistant(&mut controller);
+    }
+
+    // Chain-length probe: sweep lit prefixes and read the current sensor.
+    if controller.config.detect_chain_length {
+        return run_chain_length_probe(&mut controller);
+    }
+
+    // Camera calibration: flash bit patterns and derive the pixel map.
+    if let Some(device) = controller.config.calibrate_camera.clone() {
+        return run_camera_calibration(&mut controller, &device);
+    }
+
+    // Test-pattern mode: generate frames locally at 30 FPS until killed.
+    if let Some(pattern) = controller.config.test_pattern {
+        crate::log_info!("run", "Running test pattern {:?} (no stdin input expected, ctrl-c to stop)", pattern);
+        crate::log_info!("run", "{}", pattern.guidance());
+        let mut step: u64 = 0;
+        loop {
+            let pixels = render_test_pattern(
+                pattern,
+                step,
+                controller.config.width,
+                controller.led_count(),
+            );
+            controller.pace_output();
+            controller.send_to_hardware(&pixels)?;
+            step += 1;
+            thread::sleep(Duration::from_millis(33));
+        }
+    }
+
+    // Ambient mood light: follow the room's colors via the camera.
+    if let Some(device) = controller.config.ambient_camera.clone() {
+        return run_ambient_mode(&mut controller, &device);
+    }
+
+    // Upload endpoint: runs alongside any mode that can use the content
+    // directory. Uploads are writes, so --no-persist turns it off.
+    if controller.config.no_persist && controller.config.upload_port.is_some() {
+        crate::log_warn!("run", "--upload-port ignored: persistence is disabled");
+    } else if let Some(port) = controller.config.upload_port {
+        let Some(token) = controller.config.upload_token.clone() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--upload-port requires --upload-token",
+            ));
+        };
+        let content_dir = controller
+            .config
+            .content_dir
+            .clone()
+            .or_else(|| controller.config.watch_dir.clone())
+            .unwrap_or_else(|| std::path::PathBuf::from("content"));
+        UploadServer { port, token, content_dir }.spawn()?;
+    }
+
+    // Physical controls on the frame; their events go upstream alongside
+    // stats, whatever run mode is active.
+    if let Some(spec) = controller.config.inputs_spec.clone() {
+        let inputs = crate::input::parse_inputs(&spec)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let pressed = crate::input::spawn_input_monitor(inputs)?;
+        // Any button doubles as the alarm snooze-less dismiss.
+        controller.alarm_cancel = Some(pressed);
+    }
+
+    // HTTP widgets: each refreshes on its own thread and publishes a
+    // layer the output path composites.
+    if let Some(spec) = controller.config.widgets_spec.clone() {
+        let (grid_w, grid_h) =
+            (controller.config.width as usize, controller.config.height as usize);
+        for entry in spec.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, arg) = match entry.split_once('=') {
+                Some((name, arg)) => (name.trim(), Some(arg.trim())),
+                None => (entry, None),
+            };
+            let widget: Box<dyn crate::widget::Widget> = match name {
+                "weather" => {
+                    let Some((lat, lon)) = controller.config.location() else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "weather widget needs latitude/longitude configured",
+                        ));
+                    };
+                    Box::new(crate::weather::WeatherWidget::new(lat, lon))
+                }
+                "departures" => {
+                    let Some(url) = arg else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "departures widget needs an endpoint: departures=URL",
+                        ));
+                    };
+                    Box::new(crate::departures::DeparturesWidget::new(url.to_string()))
+                }
+                other => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown widget: {}", other),
+                    ));
+                }
+            };
+            crate::log_info!("run", "Starting {} widget", name);
+            controller
+                .widget_layers
+                .push(crate::widget::spawn_widget(widget, grid_w, grid_h));
+        }
+    }
+
+    // Prometheus scrape target, likewise mode-independent.
+    if let Some(port) = controller.config.metrics_port {
+        MetricsServer { port, metrics: controller.metrics.clone() }.spawn()?;
+    }
+
+    // Live PNG preview of whatever the panel is showing.
+    if let Some(port) = controller.config.snapshot_port {
+        let latest: crate::http::SharedFrame = std::sync::Arc::new(std::sync::Mutex::new((
+            controller.config.width,
+            controller.config.height,
+            vec![Pixel::BLACK; controller.led_count()],
+        )));
+        controller.snapshot = Some(latest.clone());
+        crate::http::SnapshotServer { port, latest }.spawn()?;
+    }
+
+    // Audio-reactive: standalone visualization, or level modulation of
+    // the normal frame stream with --audio-modulate.
+    if let Some(device) = controller.config.audio_device.clone() {
+        let shared = crate::audio::spawn_capture(device)?;
+        if controller.config.audio_modulate {
+            controller.audio = Some(shared);
+        } else {
+            let effect = controller.config.audio_effect;
+            return run_audio_mode(&mut controller, shared, effect);
+        }
+    }
+
+    // Watch mode: play content files from a directory, newest first,
+    // picking up new arrivals as they are dropped in.
+    if let Some(dir) = controller.config.watch_dir.clone() {
+        let dwell = Duration::from_secs_f64(controller.config.watch_dwell.max(1.0));
+        return run_watch_mode(&mut controller, &dir, dwell);
+    }
+
+    // Playback mode: replay a recorded stream instead of reading stdin.
+    if let Some(path) = controller.config.play_path.clone() {
+        return run_playback(&mut controller, &path);
+    }
+
+    let mut recorder = match controller.config.record_path.as_ref() {
+        Some(_) if controller.config.no_persist => {
+            crate::log_warn!("run", "--record ignored: persistence is disabled");
+            None
+        }
+        Some(path) => {
+            crate::log_info!("run", "Recording incoming frames to {}", path.display());
+            Some(FrameRecorder::create(path)?)
+        }
+        None => None,
+    };
+
+    #[cfg(unix)]
+    if controller.config.config_path.is_some() {
+        install_sighup_handler();
+    }
+
+    // Failover pair setup: the primary announces itself, the standby gates
+    // its output on the monitor's takeover flag.
+    if let Some(spec) = controller.config.failover_spec.clone() {
+        match crate::failover::parse_failover(&spec)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        {
+            crate::failover::FailoverRole::Primary { peer } => {
+                crate::failover::spawn_heartbeat_sender(peer)?;
+                controller.failover_role = Some("primary");
+            }
+            crate::failover::FailoverRole::Standby { port } => {
+                let gate = crate::failover::spawn_standby_monitor(
+                    port,
+                    controller.config.takeover_gpio,
+                    crate::failover::FAILOVER_TIMEOUT,
+                )?;
+                controller.failover_role = Some("standby");
+                controller.standby_gate = Some(gate);
+            }
+        }
+    }
+
+    // Boot splash: show the saved frame (or the autosaved live frame with
+    // --restore-last) so the panel isn't black while the host comes up.
+    if let Some(splash) = controller.config.splash_path.clone() {
+        let restored = controller.config.restore_last_frame;
+        let candidate = if restored && splash.with_extension("last").exists() {
+            splash.with_extension("last")
+        } else {
+            splash
+        };
+        match crate::splash::load_frame(&candidate) {
+            Ok((_, _, mut pixels)) => {
+                crate::log_info!("run", "Displaying saved frame from {}", candidate.display());
+                pixels.resize(controller.led_count(), Pixel::BLACK);
+                if let Err(e) = controller.send_to_hardware(&pixels) {
+                    crate::log_warn!("run", "Error displaying splash: {}", e);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => crate::log_info!("run", "Could not load splash {}: {}", candidate.display(), e),
+        }
+    }
+
+    // Every subsystem the host can reach now exists; say so on stdout.
+    // The supervisor gates its frame stream on this line.
+    if let Err(e) = send_message(&ready_json(&controller)) {
+        crate::log_warn!("run", "Error sending ready message: {}", e);
+    }
+
+    let (msg_tx, rx) = spawn_stdin_reader();
+    if controller.config.link {
+        #[cfg(feature = "ableton-link")]
+        crate::link::spawn_link_bridge(msg_tx.clone());
+        #[cfg(not(feature = "ableton-link"))]
+        crate::log_warn!("run", "--link requires a build with the ableton-link feature");
+    }
+    if let Some(addr) = controller.config.mqtt_addr.clone() {
+        crate::mqtt::spawn_mqtt_bridge(addr, msg_tx);
+    }
+
+    // One loop serves both modes: with interpolation the tick is the output
+    // frame slot, otherwise it only exists so idle takeover can kick in.
+    let interpolating = interpolate != InterpolateMode::None && output_fps > 0.0;
+    let tick = if interpolating {
+        Duration::from_secs_f64(1.0 / output_fps)
+    } else {
+        Duration::from_millis(33)
+    };
+
+    let mut last_ingest: Option<Instant> = None;
+    let mut idle = IdleAnimator::new();
+    let mut idle_active = false;
+
+    // UPS power-loss watch: the monitor latches a flag, the loop below
+    // turns it into blank + save + sync + exit.
+    let power_loss = controller
+        .config
+        .power_loss_gpio
+        .map(crate::input::spawn_power_loss_monitor);
+
+    loop {
+        let mut got_frame = false;
+        match rx.recv_timeout(tick) {
+            Ok(frame_data) => match dispatch_message(&mut controller, &frame_data) {
+                Ok(true) => {
+                    got_frame = true;
+                    if let Some(forwarder) = controller.forwarder.as_mut() {
+                        forwarder.forward(&frame_data);
+                    }
+                    if let Some(rec) = recorder.as_mut() {
+                        if let Err(e) = rec.record(&frame_data) {
+                            crate::log_warn!("run", "Error writing recording, stopping it: {}", e);
+                            recorder = None;
+                        }
+                    }
+                    last_ingest = Some(Instant::now());
+                    frame_count += 1;
+                    if frame_count.is_multiple_of(30) {
+                        if let Err(e) = controller.send_stats() {
+                            crate::log_warn!("run", "Error sending stats: {}", e);
+                        }
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    crate::log_warn!("run", "Error processing message: {}", e);
+                    controller
+                        .metrics
+                        .frames_dropped
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    continue;
+                }
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if power_loss
+            .as_ref()
+            .is_some_and(|f| f.load(std::sync::atomic::Ordering::Relaxed))
+        {
+            crate::log_error!("run", "Power failing: blanking panel and syncing state");
+            sd.stopping();
+            if let Err(e) = controller.safe_shutdown() {
+                crate::log_warn!("run", "Error blanking during shutdown: {}", e);
+            }
+            sync_filesystems();
+            return Ok(());
+        }
+
+        if take_reload_request() {
+            if let Err(e) = controller.reload_config_file() {
+                crate::log_warn!("run", "Config reload failed: {}", e);
+            }
+        }
+
+        controller.check_config_health();
+        controller.maybe_autosave_frame();
+        sd.tick(Instant::now());
+        if let Err(e) = controller.heartbeat_tick(Instant::now()) {
+            crate::log_warn!("run", "Error blanking after peer timeout: {}", e);
+        }
+
+        // Idle takeover: after idle_timeout without frames the built-in
+        // effect runs; the first real frame hands control straight back.
+        let idle_timeout = Duration::from_secs_f64(controller.config.idle_timeout);
+        let host_idle = (controller.config.idle_effect != IdleEffect::None
+            || controller.shuffle.is_some())
+            && last_ingest.is_none_or(|t| t.elapsed() >= idle_timeout);
+
+        if got_frame || (interpolating && !host_idle && last_ingest.is_some()) {
+            if idle_active {
+                crate::log_info!("run", "Frames resumed, stopping idle effect");
+                idle_active = false;
+            }
+            let t = match last_ingest {
+                Some(ingest) if interpolating && controller.frame_interval > 0.0 => {
+                    ingest.elapsed().as_secs_f64() / controller.frame_interval
+                }
+                _ => 1.0,
+            };
+            // Tier 1 and up sheds interpolation first.
+            let mode = if controller.degrade_tier() >= 1 {
+                InterpolateMode::None
+            } else {
+                interpolate
+            };
+            let pixels = controller.interpolated_pixels(mode, t);
+            controller.pace_output();
+            if let Err(e) = controller.send_to_hardware(&pixels) {
+                crate::log_warn!("run", "Error sending to hardware: {}", e);
+            }
+        } else if host_idle {
+            let (effect, scale) = controller.idle_pick();
+            if !idle_active {
+                crate::log_info!("run", "No frames for {:?}, starting idle effect {:?}",
+                          idle_timeout, effect);
+                // A takeover after frames had been flowing is sustained
+                // frame loss; a host that never sent anything is not.
+                if last_ingest.is_some() {
+                    let detail = format!("no frames for {:?}; idle takeover", idle_timeout);
+                    controller.fire_webhook("frame_loss", &detail);
+                }
+                idle_active = true;
+            }
+            // While a beat lock is active the animator runs off beats
+            // instead of its own wall clock.
+            let mut pixels = match controller.beat.effect_time(Instant::now()) {
+                Some(t) => idle.render_at(
+                    effect,
+                    controller.config.idle_color,
+                    controller.config.width,
+                    controller.led_count(),
+                    t,
+                ),
+                None => idle.render(
+                    effect,
+                    controller.config.idle_color,
+                    controller.config.width,
+                    controller.led_count(),
+                ),
+            };
+            // The shuffle's brightness pick rides on top of the master
+            // brightness rather than replacing it.
+            if scale < 1.0 {
+                for p in &mut pixels {
+                    p.r = (p.r as f64 * scale) as u8;
+                    p.g = (p.g as f64 * scale) as u8;
+                    p.b = (p.b as f64 * scale) as u8;
+                }
+            }
+            controller.pace_output();
+            if let Err(e) = controller.send_to_hardware(&pixels) {
+                crate::log_warn!("run", "Error sending to hardware: {}", e);
+            }
+        }
+    }
+
+    sd.stopping();
+    if let Some(rec) = recorder.as_ref() {
+        crate::log_info!("run", "Recorded {} frames", rec.frames_written);
+    }
+    crate::log_info!("run", "Rust LED Controller shutting down");
+    Ok(())
+}
+
+/// Interactive assistant for the most common first-run misconfiguration:
+/// drive the panel's raw channels one at a time, ask the operator what
+/// they see, and infer the wire color order from the answers.
+pub fn run_color_order_assistant(controller: &mut LEDController) -> io::Result<()> {
+    let led_count = controller.led_count();
+    let (width, height) = (controller.config.width as usize, controller.config.height as usize);
+
+    crate::log_info!("run", "Color-order assistant: the panel will light up twice; answer what you see.");
+    let probes = [
+        ("first", Pixel { r: 255, g: 0, b: 0 }),
+        ("second", Pixel { r: 0, g: 255, b: 0 }),
+    ];
+    let mut seen: Vec<char> = Vec::new();
+
+    for (label, probe) in probes {
+        // Drive the wire directly: no color-order remap while probing.
+        let frame = vec![probe; led_count];
+        controller.driver.render(&frame, width, height)?;
+
+        loop {
+            eprint!("Which color does the panel show for the {} probe? [r/g/b]: ", label);
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Assistant aborted"));
+            }
+            match line.trim().chars().next() {
+                Some(c @ ('r' | 'g' | 'b')) if !seen.contains(&c) => {
+                    seen.push(c);
+                    break;
+                }
+                Some(c @ ('r' | 'g' | 'b')) => {
+                    crate::log_info!("run", "You already answered {} for an earlier probe; the two must differ.", c);
+                }
+                _ => crate::log_info!("run", "Please answer r, g or b."),
+            }
+        }
+    }
+
+    // Wire position 0 carries the first probe's byte, position 1 the
+    // second, and position 2 whatever color is left.
+    let third = ['r', 'g', 'b'].into_iter().find(|c| !seen.contains(c)).unwrap();
+    let order_str: String = [seen[0], seen[1], third].iter().collect();
+    let order = ColorOrder::parse(&order_str).unwrap();
+
+    // Blank the panel again now that probing is done.
+    let black = vec![Pixel::BLACK; led_count];
+    controller.driver.render(&black, width, height)?;
+
+    crate::log_info!("run", "Detected color order: {}", order.name());
+    crate::log_info!("run", "Start the controller with: --color-order {}", order.name());
+    if let Some(path) = controller.config.save_color_order.clone() {
+        if controller.config.no_persist {
+            crate::log_warn!("run", "--save-color-order ignored: persistence is disabled");
+        } else {
+            std::fs::write(&path, format!("{}\n", order.name()))?;
+            crate::log_info!("run", "Wrote color order to {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Standalone audio visualization: render the chosen effect from the
+/// latest analysis block at a fixed rate until killed.
+pub fn run_audio_mode(
+    controller: &mut LEDController,
+    shared: crate::audio::SharedAnalysis,
+    effect: crate::audio::AudioEffect,
+) -> io::Result<()> {
+    crate::log_info!("run", "Audio-reactive mode: {:?} (ctrl-c to stop)", effect);
+    let width = controller.config.width as usize;
+    let height = controller.config.height as usize;
+    loop {
+        let analysis = shared.lock().unwrap().clone();
+        let pixels = crate::audio::render_audio_effect(effect, &analysis, width, height);
+        controller.pace_output();
+        controller.send_to_hardware(&pixels)?;
+        thread::sleep(Duration::from_millis(25));
+    }
+}
+
+/// Light progressively longer prefixes of the chain, sample the supply
+/// current after each step, and estimate the connected LED count from
+/// where the draw stops rising. Flags a mismatch against --led-count.
+pub fn run_chain_length_probe(controller: &mut LEDController) -> io::Result<()> {
+    let led_count = controller.led_count();
+    let (width, height) = (controller.config.width as usize, controller.config.height as usize);
+    if crate::current::read_current_ma().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No current sensor found under /sys/class/hwmon (need a curr1_input)",
+        ));
+    }
+
+    crate::log_info!("run", "Chain-length probe: sweeping lit prefixes over {} LEDs", led_count);
+    // Dim white keeps the sweep inside small PSU budgets while still
+    // drawing a measurable step per LED.
+    let probe = Pixel { r: 64, g: 64, b: 64 };
+    let step = (led_count / 8).max(1);
+    let mut samples: Vec<(usize, f64)> = Vec::new();
+    let mut lit = 0usize;
+    loop {
+        let mut frame = vec![Pixel::BLACK; led_count];
+        frame[..lit].fill(probe);
+        controller.driver.render(&frame, width, height)?;
+        // Let the PSU and the sensor's averaging window settle.
+        thread::sleep(Duration::from_millis(300));
+        let ma = crate::current::read_current_ma().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "Current sensor disappeared mid-probe")
+        })?;
+        crate::log_info!("run", "  {} lit: {:.0} mA", lit, ma);
+        samples.push((lit, ma));
+        if lit == led_count {
+            break;
+        }
+        lit = (lit + step).min(led_count);
+    }
+
+    // Blank the panel before reporting.
+    controller.driver.render(&vec![Pixel::BLACK; led_count], width, height)?;
+
+    let estimated = crate::current::estimate_led_count(&samples)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    crate::log_info!("run", "Estimated connected LEDs: {}", estimated);
+    // Half a step of slack: the estimate quantizes to the sweep stride.
+    if estimated.abs_diff(led_count) > step / 2 {
+        crate::log_warn!("run",
+            "MISMATCH: --led-count is {} but the chain looks like {} LEDs",
+            led_count, estimated
+        );
+    } else {
+        crate::log_info!("run", "Matches --led-count {}", led_count);
+    }
+    Ok(())
+}
+
+/// Ambient-mode main loop: resample the room every few seconds on a
+/// background thread and animate gradients in its dominant colors at
+/// 30 FPS. Camera failures keep the last palette; the panel never goes
+/// dark just because the camera hiccuped.
+pub fn run_ambient_mode(controller: &mut LEDController, device: &str) -> io::Result<()> {
+    crate::log_info!("run", "Ambient mode: sampling room colors via {}", device);
+    // First capture runs inline so a bad device path fails loudly at
+    // startup instead of silently showing the fallback palette.
+    let first = crate::camera::capture_rgb(device)?;
+
+    let latest: std::sync::Arc<std::sync::Mutex<Vec<Pixel>>> =
+        std::sync::Arc::new(std::sync::Mutex::new(first));
+    {
+        let latest = latest.clone();
+        let device = device.to_string();
+        thread::Builder::new()
+            .name("ambient-camera".into())
+            .spawn(move || loop {
+                thread::sleep(Duration::from_secs(5));
+                match crate::camera::capture_rgb(&device) {
+                    Ok(frame) => *latest.lock().unwrap() = frame,
+                    Err(e) => crate::log_warn!("run", "Ambient capture failed: {}", e),
+                }
+            })?;
+    }
+
+    let mut scene = crate::ambient::AmbientScene::new();
+    let mut frames: u64 = 0;
+    loop {
+        // Re-derive the palette about once a second; the easing hides
+        // the quantized updates.
+        if frames.is_multiple_of(30) {
+            let sample = latest.lock().unwrap().clone();
+            let colors = crate::ambient::dominant_colors(&sample, crate::ambient::PALETTE_SIZE);
+            if !colors.is_empty() {
+                scene.set_target(&colors);
+            }
+        }
+        let pixels = scene.step(controller.config.width, controller.led_count());
+        controller.pace_output();
+        controller.send_to_hardware(&pixels)?;
+        frames += 1;
+        thread::sleep(Duration::from_millis(33));
+    }
+}
+
+/// Flash structured-light patterns while a camera at `device` watches
+/// the panel, then decode each camera pixel's on/off sequence into the
+/// index of the LED it sees. Emits the derived pixel map as a
+/// `pixel_map` message. Experimental: wants a dim room and a camera
+/// framing the whole panel.
+pub fn run_camera_calibration(controller: &mut LEDController, device: &str) -> io::Result<()> {
+    let led_count = controller.led_count();
+    let (width, height) = (controller.config.width as usize, controller.config.height as usize);
+    let planes = crate::calibrate::pattern_count(led_count);
+    crate::log_info!("run",
+        "Camera calibration via {}: {} patterns over {} LEDs",
+        device, planes, led_count
+    );
+
+    // Drive the wire directly, like the color-order assistant: the map
+    // we derive should describe the physical chain, not the pipeline.
+    let mut show_and_grab = |frame: &[Pixel]| -> io::Result<Vec<u8>> {
+        controller.driver.render(frame, width, height)?;
+        // Let the camera's auto-exposure settle on the new pattern.
+        thread::sleep(Duration::from_millis(250));
+        crate::camera::capture_gray(device)
+    };
+
+    let all_off = show_and_grab(&vec![Pixel::BLACK; led_count])?;
+    let all_on = show_and_grab(&vec![Pixel { r: 255, g: 255, b: 255 }; led_count])?;
+    let mut patterns = Vec::with_capacity(planes);
+    for bit in 0..planes {
+        crate::log_info!("run", "  pattern {}/{}", bit + 1, planes);
+        patterns.push(show_and_grab(&crate::calibrate::bit_pattern(led_count, bit))?);
+    }
+
+    // Blank the panel before reporting.
+    controller.driver.render(&vec![Pixel::BLACK; led_count], width, height)?;
+
+    let positions = crate::calibrate::decode_positions(
+        crate::camera::CAPTURE_WIDTH,
+        crate::camera::CAPTURE_HEIGHT,
+        &all_off,
+        &all_on,
+        &patterns,
+        led_count,
+    );
+    let mapped = positions.iter().filter(|p| p.is_some()).count();
+    crate::log_info!("run", "Mapped {} of {} LEDs", mapped, led_count);
+    if mapped == 0 {
+        crate::log_warn!("run",
+            "No LEDs decoded; check the camera's framing and dim the room"
+        );
+    }
+    send_message(&crate::calibrate::pixel_map_json(&positions))?;
+    Ok(())
+}
+
+/// Watch-mode main loop: cycle through the directory's content newest
+/// first, re-scanning so new drops jump to the front of the playlist.
+pub fn run_watch_mode(
+    controller: &mut LEDController,
+    dir: &Path,
+    dwell: Duration,
+) -> io::Result<()> {
+    crate::log_info!("run", "Watching {} for content (dwell {:?} per file)", dir.display(), dwell);
+    let grid_w = controller.config.width as usize;
+    let grid_h = controller.config.height as usize;
+
+    loop {
+        let playlist = scan_content_dir(dir);
+        if playlist.is_empty() {
+            thread::sleep(Duration::from_secs(2));
+            continue;
+        }
+
+        'playlist: for (path, _) in &playlist {
+            let frames = match load_content(path, grid_w, grid_h) {
+                Ok(frames) => frames,
+                Err(e) => {
+                    crate::log_info!("run", "Skipping {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            crate::log_info!("run", "Displaying {} ({} frame{})", path.display(), frames.len(),
+                      if frames.len() == 1 { "" } else { "s" });
+            let shown = Instant::now();
+            let mut last_rescan = Instant::now();
+            let mut frame_idx = 0usize;
+
+            while shown.elapsed() < dwell {
+                let frame = &frames[frame_idx % frames.len()];
+                controller.pace_output();
+                controller.send_to_hardware(&frame.pixels)?;
+                let hold = if frames.len() > 1 {
+                    frame.delay.max(Duration::from_millis(20))
+                } else {
+                    Duration::from_millis(500)
+                };
+                thread::sleep(hold);
+                frame_idx += 1;
+
+                // Pick up newly dropped files without waiting out the dwell.
+                if last_rescan.elapsed() >= Duration::from_secs(2) {
+                    last_rescan = Instant::now();
+                    if scan_content_dir(dir) != playlist {
+                        crate::log_info!("run", "Content directory changed, restarting playlist");
+                        break 'playlist;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replay a .legrid recording against the output path, preserving the
+/// original timing scaled by --play-speed.
+pub fn run_playback(controller: &mut LEDController, path: &Path) -> io::Result<()> {
+    let records = crate::record::read_recording(path)?;
+    let speed = if controller.config.play_speed > 0.0 {
+        controller.config.play_speed
+    } else {
+        1.0
+    };
+    crate::log_info!("run", "Playing back {} ({} frames, speed {}x{})", path.display(), records.len(), speed,
+              if controller.config.play_loop { ", looping" } else { "" });
+
+    loop {
+        let started = Instant::now();
+        for (offset, payload) in &records {
+            let target = offset.div_f64(speed);
+            let elapsed = started.elapsed();
+            if target > elapsed {
+                thread::sleep(target - elapsed);
+            }
+            if let Err(e) = dispatch_message(controller, payload) {
+                crate::log_warn!("run", "Error replaying frame: {}", e);
+                continue;
+            }
+            let pixels = controller.interpolated_pixels(InterpolateMode::None, 1.0);
+            controller.pace_output();
+            controller.send_to_hardware(&pixels)?;
+        }
+        if !controller.config.play_loop {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Randomized idle-effect shuffle.
+//!
+//! `--shuffle "rainbow,sparkle,breathing;dwell=300;brightness=0.4-0.9"`
+//! rotates the idle takeover through a whitelist of effects instead of
+//! showing one forever: every dwell window picks an effect and a
+//! brightness inside the bounds. Picks are seeded from the day and the
+//! slot, so a panel shows the same sequence on a given day no matter
+//! how often it restarts — deterministic enough to reason about, fresh
+//! enough to not be wallpaper.
+
+use crate::effects::{IdleEffect, XorShift};
+
+/// The parsed shuffle policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShuffleSpec {
+    pub effects: Vec<IdleEffect>,
+    /// Seconds each pick stays up.
+    pub dwell_secs: u64,
+    /// Brightness bounds for the per-slot scale.
+    pub min_brightness: f64,
+    pub max_brightness: f64,
+}
+
+/// Parse `effect,effect,...[;dwell=SECS][;brightness=MIN-MAX]`.
+pub fn parse_shuffle(spec: &str) -> Result<ShuffleSpec, String> {
+    let mut parts = spec.split(';').map(str::trim);
+    let list = parts.next().unwrap_or_default();
+    let mut effects = Vec::new();
+    for name in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match IdleEffect::parse(name) {
+            IdleEffect::None => return Err(format!("shuffle: unknown effect '{}'", name)),
+            effect => effects.push(effect),
+        }
+    }
+    if effects.is_empty() {
+        return Err("shuffle: no effects listed".to_string());
+    }
+
+    let mut parsed = ShuffleSpec {
+        effects,
+        dwell_secs: 300,
+        min_brightness: 1.0,
+        max_brightness: 1.0,
+    };
+    for part in parts.filter(|s| !s.is_empty()) {
+        let bad = || format!("shuffle option '{}': expected dwell=SECS or brightness=MIN-MAX", part);
+        let (key, value) = part.split_once('=').ok_or_else(bad)?;
+        match key.trim() {
+            "dwell" => {
+                parsed.dwell_secs = value.trim().parse().map_err(|_| bad())?;
+                if parsed.dwell_secs == 0 {
+                    return Err("shuffle: dwell must be at least 1 second".to_string());
+                }
+            }
+            "brightness" => {
+                let (min, max) = value.split_once('-').ok_or_else(bad)?;
+                parsed.min_brightness = min.trim().parse().map_err(|_| bad())?;
+                parsed.max_brightness = max.trim().parse().map_err(|_| bad())?;
+                if !(0.0..=1.0).contains(&parsed.min_brightness)
+                    || !(0.0..=1.0).contains(&parsed.max_brightness)
+                    || parsed.min_brightness > parsed.max_brightness
+                {
+                    return Err("shuffle: brightness bounds must satisfy 0 <= min <= max <= 1".to_string());
+                }
+            }
+            _ => return Err(bad()),
+        }
+    }
+    Ok(parsed)
+}
+
+impl ShuffleSpec {
+    /// The pick for a wall-clock moment: effect and brightness for the
+    /// dwell slot containing `epoch_secs`. Seeded from (day, slot), so
+    /// the sequence is stable across restarts and differs day to day.
+    pub fn pick_at(&self, epoch_secs: i64) -> (IdleEffect, f64) {
+        let day = epoch_secs.div_euclid(86_400);
+        let slot = epoch_secs.rem_euclid(86_400) as u64 / self.dwell_secs;
+        self.pick(day as u64, slot)
+    }
+
+    /// The deterministic pick for a (day, slot) pair.
+    pub fn pick(&self, day: u64, slot: u64) -> (IdleEffect, f64) {
+        let mut rng = XorShift::new(day.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ slot);
+        // A few warm-up rounds so nearby seeds decorrelate.
+        rng.next_u64();
+        rng.next_u64();
+        let effect = self.effects[(rng.next_u64() % self.effects.len() as u64) as usize];
+        let span = self.max_brightness - self.min_brightness;
+        let brightness = self.min_brightness + span * (rng.next_u64() % 1_000) as f64 / 999.0;
+        (effect, brightness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_whitelist_and_options() {
+        let spec = parse_shuffle("rainbow, sparkle;dwell=120;brightness=0.4-0.9").unwrap();
+        assert_eq!(spec.effects, vec![IdleEffect::Rainbow, IdleEffect::Sparkle]);
+        assert_eq!(spec.dwell_secs, 120);
+        assert_eq!(spec.min_brightness, 0.4);
+        assert!(parse_shuffle("").is_err());
+        assert!(parse_shuffle("lava").is_err());
+        assert!(parse_shuffle("rainbow;brightness=0.9-0.4").is_err());
+        assert!(parse_shuffle("rainbow;dwell=0").is_err());
+    }
+
+    #[test]
+    fn picks_are_deterministic_within_a_slot_and_bounded() {
+        let spec = parse_shuffle("rainbow,sparkle,breathing;dwell=60;brightness=0.3-0.8").unwrap();
+        let (effect, brightness) = spec.pick_at(1_700_000_000);
+        // Same slot, same pick; restarts don't reshuffle the day.
+        assert_eq!(spec.pick_at(1_700_000_000 + 30), (effect, brightness));
+        assert!((0.3..=0.8).contains(&brightness));
+        // The sequence moves on in the next slot... eventually: with
+        // three effects some adjacent slots repeat, so check variety
+        // over a stretch instead of one boundary.
+        let picks: Vec<IdleEffect> = (0..20).map(|s| spec.pick(10, s).0).collect();
+        assert!(picks.iter().any(|e| *e != picks[0]));
+    }
+}
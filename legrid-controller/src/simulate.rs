@@ -0,0 +1,205 @@
+//! Frame-source simulator: the `simulate-sender` subcommand.
+//!
+//! `local_controller simulate-sender --scenario bursty --width 25 --height 24`
+//! writes length-prefixed wire messages to stdout, exactly as the host
+//! would. Pipe it straight into a controller, or through socat/ssh to
+//! one on real hardware, and rehearse the failure handling on the bench
+//! before go-live: steady streaming, bursty delivery, a mid-stream
+//! resize, corrupted payloads, and silence followed by resumption. All
+//! chatter goes to stderr; stdout carries only the wire bytes.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::config::parse_args;
+use crate::frame::MSG_TYPE_FRAME;
+
+/// The scripted failure rehearsals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scenario {
+    /// Frames at a steady cadence for the whole run.
+    Steady,
+    /// Full-speed bursts separated by half-second gaps.
+    Bursty,
+    /// Steady, but the frame dimensions halve mid-stream.
+    Resize,
+    /// Steady with garbage and truncated messages mixed in.
+    CorruptLength,
+    /// Steady, a long silence (idle takeover territory), then resume.
+    Silence,
+}
+
+impl Scenario {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "steady" => Some(Scenario::Steady),
+            "bursty" => Some(Scenario::Bursty),
+            "resize" => Some(Scenario::Resize),
+            "corrupt-length" | "corrupt" => Some(Scenario::CorruptLength),
+            "silence" => Some(Scenario::Silence),
+            _ => None,
+        }
+    }
+}
+
+/// One RGB frame message: a gradient that drifts with `step`, so motion
+/// (or the lack of it) is obvious on the panel.
+pub fn frame_message(width: u16, height: u16, step: u64) -> Vec<u8> {
+    let mut msg = vec![1u8, MSG_TYPE_FRAME, 0, 0, 0, 0];
+    msg.extend_from_slice(&width.to_le_bytes());
+    msg.extend_from_slice(&height.to_le_bytes());
+    let width = width.max(1) as usize;
+    for i in 0..width * height.max(1) as usize {
+        let x = (i % width) as u64;
+        let level = ((x * 255 / width as u64 + step * 4) % 256) as u8;
+        msg.extend_from_slice(&[level, 255 - level, (step % 256) as u8]);
+    }
+    msg
+}
+
+/// A message of garbage bytes. The length prefix stays honest — this
+/// rehearses payload corruption, which must error without desyncing the
+/// stream (resynchronizing a lied-about prefix is the transport's
+/// problem, not the parser's).
+pub fn garbage_message(len: usize) -> Vec<u8> {
+    vec![0xA5; len.max(1)]
+}
+
+/// A frame whose header promises more pixels than the payload carries.
+pub fn truncated_frame(width: u16, height: u16) -> Vec<u8> {
+    let full = frame_message(width, height, 0);
+    full[..full.len() / 2].to_vec()
+}
+
+fn write_message(out: &mut impl Write, msg: &[u8]) -> io::Result<()> {
+    out.write_all(&(msg.len() as u32).to_le_bytes())?;
+    out.write_all(msg)?;
+    out.flush()
+}
+
+pub fn run_simulate(args: &[String]) -> io::Result<()> {
+    // The ordinary controller flags give us the grid size; the
+    // simulator-specific ones are parsed below (parse_args skips them).
+    let config = parse_args(args)?;
+    let (width, height) = (config.width, config.height);
+
+    let mut scenario = Scenario::Steady;
+    let mut duration = Duration::from_secs(10);
+    let mut fps = 30.0_f64;
+    for i in 0..args.len() {
+        match args[i].as_str() {
+            "--scenario" if i + 1 < args.len() => {
+                scenario = Scenario::parse(&args[i + 1]).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Unknown scenario: {} (expected steady|bursty|resize|corrupt-length|silence)",
+                            args[i + 1]
+                        ),
+                    )
+                })?;
+            }
+            "--duration" if i + 1 < args.len() => {
+                duration = Duration::from_secs_f64(args[i + 1].parse().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "--duration expects seconds")
+                })?);
+            }
+            "--fps" if i + 1 < args.len() => {
+                fps = args[i + 1].parse().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "--fps expects a number")
+                })?;
+            }
+            _ => {}
+        }
+    }
+    let interval = Duration::from_secs_f64(1.0 / fps.max(1.0));
+
+    crate::log_info!("simulate",
+        "Scenario {:?}: {}x{} at {} fps for {:?}", scenario, width, height, fps, duration);
+
+    let mut out = io::stdout();
+    let started = Instant::now();
+    let mut step = 0u64;
+    while started.elapsed() < duration {
+        match scenario {
+            Scenario::Steady => {
+                write_message(&mut out, &frame_message(width, height, step))?;
+                std::thread::sleep(interval);
+            }
+            Scenario::Bursty => {
+                // A burst at wire speed, then a gap: the pacing and
+                // interpolation paths see their worst case.
+                for _ in 0..30 {
+                    write_message(&mut out, &frame_message(width, height, step))?;
+                    step += 1;
+                }
+                std::thread::sleep(Duration::from_millis(500));
+            }
+            Scenario::Resize => {
+                let halfway = started.elapsed() >= duration / 2;
+                let (w, h) = if halfway {
+                    ((width / 2).max(1), (height / 2).max(1))
+                } else {
+                    (width, height)
+                };
+                write_message(&mut out, &frame_message(w, h, step))?;
+                std::thread::sleep(interval);
+            }
+            Scenario::CorruptLength => {
+                write_message(&mut out, &frame_message(width, height, step))?;
+                // Every second or so, wedge garbage and a truncated
+                // frame between good ones; the controller should log
+                // and carry on, never freeze or desync.
+                if step % 30 == 15 {
+                    write_message(&mut out, &garbage_message(64))?;
+                    write_message(&mut out, &truncated_frame(width, height))?;
+                }
+                std::thread::sleep(interval);
+            }
+            Scenario::Silence => {
+                // Steady for the first quarter, silent for half (long
+                // enough for idle takeover), steady again for the rest.
+                let t = started.elapsed().as_secs_f64() / duration.as_secs_f64();
+                if !(0.25..0.75).contains(&t) {
+                    write_message(&mut out, &frame_message(width, height, step))?;
+                }
+                std::thread::sleep(interval);
+            }
+        }
+        step += 1;
+    }
+    crate::log_info!("simulate", "Scenario {:?} complete ({} messages)", scenario, step);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::FrameParser;
+
+    #[test]
+    fn scenario_names_parse() {
+        assert_eq!(Scenario::parse("steady"), Some(Scenario::Steady));
+        assert_eq!(Scenario::parse("corrupt-length"), Some(Scenario::CorruptLength));
+        assert_eq!(Scenario::parse("meltdown"), None);
+    }
+
+    #[test]
+    fn simulated_frames_parse_like_host_frames() {
+        let msg = frame_message(4, 3, 7);
+        let parsed = FrameParser::parse(&msg).unwrap();
+        assert_eq!((parsed.width, parsed.height), (4, 3));
+        assert_eq!(parsed.pixels.len(), 12);
+        // Motion: a later step shifts the gradient.
+        assert_ne!(frame_message(4, 3, 8), msg);
+    }
+
+    #[test]
+    fn corruption_is_rejected_without_poisoning_the_stream() {
+        assert!(FrameParser::parse(&garbage_message(64)).is_err());
+        assert!(FrameParser::parse(&truncated_frame(4, 4)).is_err());
+        // The next honest frame still parses; resync is the length
+        // prefix's job and it never lied.
+        assert!(FrameParser::parse(&frame_message(4, 4, 0)).is_ok());
+    }
+}
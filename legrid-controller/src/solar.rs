@@ -0,0 +1,99 @@
+//! Local sunrise/sunset computation for solar-keyed schedules.
+//!
+//! The standard NOAA approximation: fractional year, solar declination,
+//! the equation of time, and the hour angle at which the sun's center
+//! sits 0.833 degrees below the horizon. Accurate to a couple of
+//! minutes, which is plenty for dimming a panel at dusk.
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Sunrise and sunset as fractional minutes of the UTC day containing
+/// `epoch_secs`, for a site at `lat`/`lon` in degrees (east positive).
+/// `None` means the sun never crosses the horizon that day — polar day
+/// or polar night.
+pub fn sun_minutes_utc(epoch_secs: i64, lat: f64, lon: f64) -> Option<(f64, f64)> {
+    let doy = day_of_year(epoch_secs.div_euclid(SECS_PER_DAY));
+    let gamma = 2.0 * std::f64::consts::PI / 365.0 * (doy as f64 - 0.5);
+
+    let decl = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos()
+        + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+    let eqtime = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2.0 * gamma).cos()
+            - 0.040849 * (2.0 * gamma).sin());
+
+    // Hour angle at official sunrise: solar zenith 90.833 degrees, the
+    // extra 0.833 covering refraction and the solar disc's radius.
+    let lat_rad = lat.to_radians();
+    let cos_ha = (90.833f64.to_radians().cos() - lat_rad.sin() * decl.sin())
+        / (lat_rad.cos() * decl.cos());
+    if !(-1.0..=1.0).contains(&cos_ha) {
+        return None;
+    }
+    let ha_deg = cos_ha.acos().to_degrees();
+
+    let sunrise = 720.0 - 4.0 * (lon + ha_deg) - eqtime;
+    let sunset = 720.0 - 4.0 * (lon - ha_deg) - eqtime;
+    Some((sunrise, sunset))
+}
+
+/// Day of the year (1-based) for a day count since 1970-01-01, via the
+/// usual days-to-civil-date conversion.
+fn day_of_year(days_since_epoch: i64) -> u32 {
+    let z = days_since_epoch + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy_march = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let month_march = (5 * doy_march + 2) / 153;
+    let day = doy_march - (153 * month_march + 2) / 5 + 1;
+    let month = if month_march < 10 { month_march + 3 } else { month_march - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_before: [i64; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let mut doy = days_before[month as usize - 1] + day;
+    if leap && month > 2 {
+        doy += 1;
+    }
+    doy as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-06-20 12:00 UTC, near the June solstice.
+    const SOLSTICE: i64 = 1_718_884_800;
+
+    #[test]
+    fn day_of_year_handles_leap_years() {
+        assert_eq!(day_of_year(0), 1); // 1970-01-01
+        assert_eq!(day_of_year(SOLSTICE / SECS_PER_DAY), 172); // 2024-06-20
+        assert_eq!(day_of_year(19_722), 365); // 2023-12-31
+        assert_eq!(day_of_year(19_723), 1); // 2024-01-01
+    }
+
+    #[test]
+    fn solstice_times_look_right() {
+        // London: sunrise ~03:43 UTC, sunset ~20:21 UTC at the solstice.
+        let (sunrise, sunset) = sun_minutes_utc(SOLSTICE, 51.5, -0.1).unwrap();
+        assert!((sunrise - 223.0).abs() < 20.0, "sunrise {} min", sunrise);
+        assert!((sunset - 1221.0).abs() < 20.0, "sunset {} min", sunset);
+
+        // On the equator the day is close to twelve hours year-round.
+        let (sunrise, sunset) = sun_minutes_utc(SOLSTICE, 0.0, 0.0).unwrap();
+        assert!(((sunset - sunrise) - 720.0).abs() < 30.0);
+    }
+
+    #[test]
+    fn polar_day_returns_none() {
+        assert!(sun_minutes_utc(SOLSTICE, 80.0, 0.0).is_none());
+        // Six months on, the same site is in polar night.
+        assert!(sun_minutes_utc(SOLSTICE + 183 * SECS_PER_DAY, 80.0, 0.0).is_none());
+    }
+}
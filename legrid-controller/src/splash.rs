@@ -0,0 +1,78 @@
+//! Boot splash and last-frame restore.
+//!
+//! A `save_splash` control command persists the frame currently on the
+//! panel; at the next startup it is displayed before the first host frame
+//! arrives. With `--restore-last`, the controller also autosaves the live
+//! frame periodically and restores it after a crash or restart, so the
+//! panel never sits black while services come up.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use crate::frame::Pixel;
+
+const MAGIC: &[u8; 4] = b"LGSP";
+
+/// Write a frame to disk. The write goes through a sibling temp file and
+/// a rename, so a crash mid-save never corrupts the existing splash.
+pub fn save_frame(path: &Path, pixels: &[Pixel], width: u16, height: u16) -> io::Result<()> {
+    let mut data = Vec::with_capacity(8 + pixels.len() * 3);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&height.to_le_bytes());
+    for p in pixels {
+        data.extend_from_slice(&[p.r, p.g, p.b]);
+    }
+    let tmp = path.with_extension("tmp");
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+/// Read a frame back. Errors on a bad magic or truncated pixel data.
+pub fn load_frame(path: &Path) -> io::Result<(u16, u16, Vec<Pixel>)> {
+    let mut data = Vec::new();
+    fs::File::open(path)?.read_to_end(&mut data)?;
+    if data.len() < 8 || &data[..4] != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a splash file"));
+    }
+    let width = u16::from_le_bytes([data[4], data[5]]);
+    let height = u16::from_le_bytes([data[6], data[7]]);
+    let need = width as usize * height as usize * 3;
+    let pixel_data = data
+        .get(8..8 + need)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Truncated splash file"))?;
+    let pixels = pixel_data
+        .chunks_exact(3)
+        .map(|c| Pixel { r: c[0], g: c[1], b: c[2] })
+        .collect();
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splash_round_trips() {
+        let path = std::env::temp_dir().join("legrid-splash-test.lgsp");
+        let pixels = vec![Pixel { r: 1, g: 2, b: 3 }, Pixel { r: 4, g: 5, b: 6 }];
+        save_frame(&path, &pixels, 2, 1).unwrap();
+        let (w, h, loaded) = load_frame(&path).unwrap();
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(loaded, pixels);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_foreign_files() {
+        let path = std::env::temp_dir().join("legrid-splash-bad.lgsp");
+        std::fs::write(&path, b"not a splash").unwrap();
+        assert!(load_frame(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
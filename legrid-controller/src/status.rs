@@ -0,0 +1,180 @@
+//! Free/busy/focus status indicator for office installations.
+//!
+//! A `status` control message (or the MQTT `legrid/indicator` topic)
+//! colors the panel — green free, red busy, purple focus — either as a
+//! full fill or as a border ring, optionally with a countdown: the lit
+//! area shrinks as the timer runs out, and the indicator clears itself
+//! when it does. The panel keeps rendering whatever is underneath; the
+//! indicator is just a layer, so no external renderer is needed.
+
+use std::time::{Duration, Instant};
+
+use crate::frame::Pixel;
+
+/// The advertised states, with the colors people expect of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusState {
+    Free,
+    Busy,
+    Focus,
+}
+
+impl StatusState {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "free" => Some(StatusState::Free),
+            "busy" => Some(StatusState::Busy),
+            "focus" => Some(StatusState::Focus),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusState::Free => "free",
+            StatusState::Busy => "busy",
+            StatusState::Focus => "focus",
+        }
+    }
+
+    pub fn color(self) -> Pixel {
+        match self {
+            StatusState::Free => Pixel { r: 0, g: 200, b: 40 },
+            StatusState::Busy => Pixel { r: 220, g: 20, b: 20 },
+            StatusState::Focus => Pixel { r: 130, g: 0, b: 200 },
+        }
+    }
+}
+
+/// Render the indicator layer: a fill drains from the top as time runs
+/// out, a border ring empties clockwise from the top-left. `fraction`
+/// is the remaining share (1 with no countdown); black stays
+/// transparent to the compositor.
+pub fn render_status(
+    state: StatusState,
+    fraction: f64,
+    border: bool,
+    width: usize,
+    height: usize,
+) -> Vec<Pixel> {
+    let color = state.color();
+    let fraction = fraction.clamp(0.0, 1.0);
+    let mut layer = vec![Pixel::BLACK; width * height];
+    if border {
+        let path = border_path(width, height);
+        let lit = (fraction * path.len() as f64).ceil() as usize;
+        for &(x, y) in path.iter().take(lit) {
+            layer[y * width + x] = color;
+        }
+    } else {
+        // The filled region keeps the bottom rows: a meeting visibly
+        // "drains" downward as it runs out.
+        let rows = (fraction * height as f64).ceil() as usize;
+        for y in height - rows.min(height)..height {
+            for x in 0..width {
+                layer[y * width + x] = color;
+            }
+        }
+    }
+    layer
+}
+
+/// The border pixels in clockwise order starting at the top-left, so a
+/// countdown empties like a clock face.
+fn border_path(width: usize, height: usize) -> Vec<(usize, usize)> {
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let mut path: Vec<(usize, usize)> = (0..width).map(|x| (x, 0)).collect();
+    path.extend((1..height).map(|y| (width - 1, y)));
+    if height > 1 {
+        path.extend((0..width - 1).rev().map(|x| (x, height - 1)));
+    }
+    if width > 1 {
+        path.extend((1..height - 1).rev().map(|y| (0, y)));
+    }
+    path
+}
+
+/// The active indicator: a state, an optional countdown, and the shape.
+pub struct StatusIndicator {
+    pub state: StatusState,
+    pub border: bool,
+    /// Countdown window, when one was requested.
+    deadline: Option<(Instant, Duration)>,
+}
+
+impl StatusIndicator {
+    pub fn new(state: StatusState, minutes: Option<f64>, border: bool) -> Self {
+        let deadline = minutes
+            .filter(|m| *m > 0.0)
+            .map(|m| Duration::from_secs_f64(m * 60.0))
+            .map(|total| (Instant::now() + total, total));
+        Self { state, border, deadline }
+    }
+
+    /// Remaining share of the countdown at `now`: 1 with no countdown,
+    /// `None` once it has expired and the indicator should clear.
+    pub fn fraction_at(&self, now: Instant) -> Option<f64> {
+        match self.deadline {
+            None => Some(1.0),
+            Some((ends, total)) => {
+                if now >= ends {
+                    return None;
+                }
+                Some((ends - now).as_secs_f64() / total.as_secs_f64())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_parse_and_carry_their_colors() {
+        assert_eq!(StatusState::parse("busy"), Some(StatusState::Busy));
+        assert_eq!(StatusState::parse("away"), None);
+        assert!(StatusState::Free.color().g > StatusState::Free.color().r);
+        assert!(StatusState::Busy.color().r > StatusState::Busy.color().g);
+    }
+
+    #[test]
+    fn border_path_rings_the_panel_once() {
+        let path = border_path(4, 3);
+        assert_eq!(path.len(), 2 * 4 + 2 * 3 - 4);
+        assert_eq!(path[0], (0, 0));
+        // Every border pixel exactly once.
+        let mut seen = path.clone();
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), path.len());
+    }
+
+    #[test]
+    fn fill_drains_and_border_empties_with_the_countdown() {
+        let full = render_status(StatusState::Busy, 1.0, false, 4, 4);
+        assert!(full.iter().all(|p| *p != Pixel::BLACK));
+        let half = render_status(StatusState::Busy, 0.5, false, 4, 4);
+        // Top half transparent, bottom half lit.
+        assert_eq!(half[0], Pixel::BLACK);
+        assert_ne!(half[3 * 4], Pixel::BLACK);
+
+        let ring = render_status(StatusState::Focus, 0.5, true, 4, 4);
+        let lit = ring.iter().filter(|p| **p != Pixel::BLACK).count();
+        assert_eq!(lit, 6); // half of the 12-pixel ring
+        assert_eq!(ring[5], Pixel::BLACK); // interior untouched
+    }
+
+    #[test]
+    fn countdowns_expire() {
+        let timed = StatusIndicator::new(StatusState::Busy, Some(25.0), false);
+        let now = Instant::now();
+        assert!(timed.fraction_at(now).is_some_and(|f| f > 0.9));
+        assert_eq!(timed.fraction_at(now + Duration::from_secs(26 * 60)), None);
+
+        let untimed = StatusIndicator::new(StatusState::Free, None, false);
+        assert_eq!(untimed.fraction_at(now + Duration::from_secs(3600)), Some(1.0));
+    }
+}
@@ -0,0 +1,193 @@
+//! systemd integration: sd_notify readiness, watchdog keepalives, and
+//! socket activation.
+//!
+//! All three speak the plain environment-variable protocols directly
+//! (`NOTIFY_SOCKET`, `WATCHDOG_USEC`, `LISTEN_FDS`) so a `Type=notify`
+//! unit sees READY=1 once the driver is up, `WatchdogSec=` restarts the
+//! service if the render loop hangs, and pre-bound listeners from
+//! `.socket` units are picked up instead of binding fresh. Outside
+//! systemd every call is an inert no-op.
+
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+/// Connection to the service manager, built from the environment at
+/// startup. Without `NOTIFY_SOCKET` it is inert.
+pub struct SdNotify {
+    #[cfg(unix)]
+    socket: Option<std::os::unix::net::UnixDatagram>,
+    /// Configured watchdog period; keepalives go out at half of it.
+    watchdog: Option<Duration>,
+    last_keepalive: Option<Instant>,
+}
+
+impl SdNotify {
+    pub fn from_env() -> Self {
+        let watchdog = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|_| {
+                // WATCHDOG_PID, when present, scopes the watchdog to one
+                // process; respect it so forks don't ping for the parent.
+                std::env::var("WATCHDOG_PID")
+                    .map(|pid| pid.trim() == std::process::id().to_string())
+                    .unwrap_or(true)
+            })
+            .map(Duration::from_micros);
+        Self {
+            #[cfg(unix)]
+            socket: std::env::var("NOTIFY_SOCKET").ok().and_then(connect_notify),
+            watchdog,
+            last_keepalive: None,
+        }
+    }
+
+    /// Report the service as up; call once the driver is initialized.
+    pub fn ready(&self) {
+        self.send("READY=1");
+    }
+
+    /// Tell the manager an orderly shutdown has begun.
+    pub fn stopping(&self) {
+        self.send("STOPPING=1");
+    }
+
+    /// Called every main-loop tick; emits WATCHDOG=1 at half the
+    /// configured period so one delayed tick doesn't trip the restart.
+    pub fn tick(&mut self, now: Instant) {
+        let Some(period) = self.watchdog else { return };
+        if self
+            .last_keepalive
+            .is_none_or(|t| now.duration_since(t) >= period / 2)
+        {
+            self.last_keepalive = Some(now);
+            self.send("WATCHDOG=1");
+        }
+    }
+
+    #[cfg(unix)]
+    fn send(&self, state: &str) {
+        if let Some(socket) = self.socket.as_ref() {
+            if let Err(e) = socket.send(state.as_bytes()) {
+                crate::log_warn!("systemd", "sd_notify send failed: {}", e);
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn send(&self, _state: &str) {}
+}
+
+/// Connect the notify datagram socket; systemd passes either a
+/// filesystem path or an abstract-namespace name prefixed with '@'.
+#[cfg(unix)]
+fn connect_notify(addr: String) -> Option<std::os::unix::net::UnixDatagram> {
+    use std::os::unix::net::UnixDatagram;
+    let socket = UnixDatagram::unbound().ok()?;
+    let result = if let Some(name) = addr.strip_prefix('@') {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            use std::os::linux::net::SocketAddrExt;
+            std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())
+                .and_then(|a| socket.connect_addr(&a))
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let _ = name;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "abstract sockets are linux-only",
+            ))
+        }
+    } else {
+        socket.connect(&addr)
+    };
+    match result {
+        Ok(()) => Some(socket),
+        Err(e) => {
+            crate::log_warn!("systemd", "Cannot connect NOTIFY_SOCKET {}: {}", addr, e);
+            None
+        }
+    }
+}
+
+/// Take the socket-activated listener bound to this port, if systemd
+/// passed one (`LISTEN_FDS`, fds starting at 3). Matching by port lets
+/// one service declare several `.socket` units and each server pick up
+/// its own; ports with no pre-bound listener fall back to binding.
+pub fn activated_listener(port: u16) -> Option<TcpListener> {
+    #[cfg(unix)]
+    {
+        use std::sync::{Mutex, OnceLock};
+        static ACTIVATED: OnceLock<Mutex<Vec<TcpListener>>> = OnceLock::new();
+        let pool = ACTIVATED.get_or_init(|| Mutex::new(collect_activated()));
+        let mut pool = pool.lock().unwrap();
+        let at = pool
+            .iter()
+            .position(|l| l.local_addr().map(|a| a.port()).ok() == Some(port))?;
+        Some(pool.swap_remove(at))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = port;
+        None
+    }
+}
+
+/// Adopt the file descriptors systemd passed, if they are for us.
+#[cfg(unix)]
+fn collect_activated() -> Vec<TcpListener> {
+    use std::os::fd::FromRawFd;
+
+    let for_us = std::env::var("LISTEN_PID")
+        .map(|pid| pid.trim() == std::process::id().to_string())
+        .unwrap_or(false);
+    let count: u32 = std::env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if !for_us || count == 0 {
+        return Vec::new();
+    }
+    crate::log_info!("systemd", "Adopting {} socket-activated listener(s)", count);
+    // SD_LISTEN_FDS_START is 3; systemd hands them over in order.
+    (3..3 + count as i32)
+        .map(|fd| unsafe { TcpListener::from_raw_fd(fd) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inert_outside_systemd() {
+        // No NOTIFY_SOCKET / WATCHDOG_USEC in the test environment, so
+        // everything is a no-op and nothing panics.
+        let mut sd = SdNotify::from_env();
+        sd.ready();
+        sd.tick(Instant::now());
+        sd.stopping();
+        assert!(sd.watchdog.is_none());
+        assert!(activated_listener(9100).is_none());
+    }
+
+    #[test]
+    fn keepalives_pace_at_half_the_period() {
+        let mut sd = SdNotify {
+            #[cfg(unix)]
+            socket: None,
+            watchdog: Some(Duration::from_secs(10)),
+            last_keepalive: None,
+        };
+        let t0 = Instant::now();
+        sd.tick(t0);
+        assert_eq!(sd.last_keepalive, Some(t0));
+        // Inside the half-period nothing is due.
+        sd.tick(t0 + Duration::from_secs(3));
+        assert_eq!(sd.last_keepalive, Some(t0));
+        let t1 = t0 + Duration::from_secs(5);
+        sd.tick(t1);
+        assert_eq!(sd.last_keepalive, Some(t1));
+    }
+}
@@ -0,0 +1,486 @@
+//! Bitmap-font text rendering and the scrolling marquee.
+//!
+//! A `{"command":"marquee","text":"HELLO","color":"FF8800","speed":12}`
+//! control message starts a marquee that scrolls right-to-left across the
+//! grid, composited over whatever the panel is showing. The controller
+//! rasterizes locally, so the host doesn't have to ship text as 30 FPS
+//! pixel frames.
+
+use std::time::Instant;
+
+use crate::frame::Pixel;
+
+/// Glyph cell geometry: 5 columns of 7 rows, plus one blank column.
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+pub const GLYPH_ADVANCE: usize = GLYPH_WIDTH + 1;
+
+/// Normalize a character before glyph lookup: fold to uppercase (Unicode,
+/// so Cyrillic lowercase folds too) and strip Latin diacritics down to
+/// the base letter. Accented names render readably instead of as boxes.
+pub fn fold_char(c: char) -> char {
+    let c = c.to_uppercase().next().unwrap_or(c);
+    match c {
+        'À'..='Å' | 'Æ' => 'A',
+        'Ç' => 'C',
+        'È'..='Ë' => 'E',
+        'Ì'..='Ï' => 'I',
+        'Ñ' => 'N',
+        'Ò'..='Ö' | 'Ø' => 'O',
+        'Ù'..='Ü' => 'U',
+        'Ý' => 'Y',
+        'Š' => 'S',
+        'Ž' => 'Z',
+        'ß' => 'S',
+        // Ё folds to Е; the diaeresis doesn't fit a 7-row cell.
+        'Ё' => 'Е',
+        _ => c,
+    }
+}
+
+/// Classic 5x7 font, one byte per column, bit 0 the top row. Characters
+/// go through [`fold_char`] first; anything still unmapped renders as a
+/// hollow box so a missing glyph is visible, not invisible.
+pub fn glyph(c: char) -> [u8; GLYPH_WIDTH] {
+    match fold_char(c) {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00],
+        '!' => [0x00, 0x00, 0x5F, 0x00, 0x00],
+        '\'' => [0x00, 0x05, 0x03, 0x00, 0x00],
+        ',' => [0x00, 0x50, 0x30, 0x00, 0x00],
+        '-' => [0x08, 0x08, 0x08, 0x08, 0x08],
+        '.' => [0x00, 0x60, 0x60, 0x00, 0x00],
+        ':' => [0x00, 0x36, 0x36, 0x00, 0x00],
+        '?' => [0x02, 0x01, 0x51, 0x09, 0x06],
+        '0' => [0x3E, 0x51, 0x49, 0x45, 0x3E],
+        '1' => [0x00, 0x42, 0x7F, 0x40, 0x00],
+        '2' => [0x42, 0x61, 0x51, 0x49, 0x46],
+        '3' => [0x21, 0x41, 0x45, 0x4B, 0x31],
+        '4' => [0x18, 0x14, 0x12, 0x7F, 0x10],
+        '5' => [0x27, 0x45, 0x45, 0x45, 0x39],
+        '6' => [0x3C, 0x4A, 0x49, 0x49, 0x30],
+        '7' => [0x01, 0x71, 0x09, 0x05, 0x03],
+        '8' => [0x36, 0x49, 0x49, 0x49, 0x36],
+        '9' => [0x06, 0x49, 0x49, 0x29, 0x1E],
+        'A' | 'А' => [0x7E, 0x11, 0x11, 0x11, 0x7E],
+        'B' | 'В' => [0x7F, 0x49, 0x49, 0x49, 0x36],
+        'C' | 'С' => [0x3E, 0x41, 0x41, 0x41, 0x22],
+        'D' => [0x7F, 0x41, 0x41, 0x22, 0x1C],
+        'E' | 'Е' => [0x7F, 0x49, 0x49, 0x49, 0x41],
+        'F' => [0x7F, 0x09, 0x09, 0x09, 0x01],
+        'G' => [0x3E, 0x41, 0x49, 0x49, 0x7A],
+        'H' | 'Н' => [0x7F, 0x08, 0x08, 0x08, 0x7F],
+        'I' => [0x00, 0x41, 0x7F, 0x41, 0x00],
+        'J' => [0x20, 0x40, 0x41, 0x3F, 0x01],
+        'K' | 'К' => [0x7F, 0x08, 0x14, 0x22, 0x41],
+        'L' => [0x7F, 0x40, 0x40, 0x40, 0x40],
+        'M' | 'М' => [0x7F, 0x02, 0x0C, 0x02, 0x7F],
+        'N' => [0x7F, 0x04, 0x08, 0x10, 0x7F],
+        'O' | 'О' => [0x3E, 0x41, 0x41, 0x41, 0x3E],
+        'P' | 'Р' => [0x7F, 0x09, 0x09, 0x09, 0x06],
+        'Q' => [0x3E, 0x41, 0x51, 0x21, 0x5E],
+        'R' => [0x7F, 0x09, 0x19, 0x29, 0x46],
+        'S' => [0x46, 0x49, 0x49, 0x49, 0x31],
+        'T' | 'Т' => [0x01, 0x01, 0x7F, 0x01, 0x01],
+        'U' => [0x3F, 0x40, 0x40, 0x40, 0x3F],
+        'V' => [0x1F, 0x20, 0x40, 0x20, 0x1F],
+        'W' => [0x3F, 0x40, 0x38, 0x40, 0x3F],
+        'X' | 'Х' => [0x63, 0x14, 0x08, 0x14, 0x63],
+        'Y' => [0x07, 0x08, 0x70, 0x08, 0x07],
+        'Z' => [0x61, 0x51, 0x49, 0x45, 0x43],
+        'Б' => [0x7F, 0x49, 0x49, 0x49, 0x71],
+        'Г' => [0x7F, 0x01, 0x01, 0x01, 0x01],
+        'Д' => [0x60, 0x3F, 0x21, 0x3F, 0x60],
+        'Ж' => [0x63, 0x14, 0x7F, 0x14, 0x63],
+        'З' => [0x22, 0x41, 0x49, 0x49, 0x36],
+        'И' => [0x7F, 0x10, 0x08, 0x04, 0x7F],
+        'Й' => [0x7E, 0x10, 0x09, 0x04, 0x7E],
+        'Л' => [0x40, 0x3F, 0x01, 0x01, 0x7F],
+        'П' => [0x7F, 0x01, 0x01, 0x01, 0x7F],
+        'У' => [0x07, 0x48, 0x48, 0x48, 0x3F],
+        'Ф' => [0x1C, 0x22, 0x7F, 0x22, 0x1C],
+        'Ц' => [0x3F, 0x20, 0x20, 0x3F, 0x60],
+        'Ч' => [0x07, 0x08, 0x08, 0x08, 0x7F],
+        'Ш' => [0x7F, 0x40, 0x7F, 0x40, 0x7F],
+        'Щ' => [0x3F, 0x20, 0x3F, 0x20, 0x7F],
+        'Ъ' => [0x01, 0x7F, 0x48, 0x48, 0x30],
+        'Ы' => [0x7F, 0x48, 0x30, 0x00, 0x7F],
+        'Ь' => [0x7F, 0x48, 0x48, 0x48, 0x30],
+        'Э' => [0x22, 0x41, 0x49, 0x49, 0x3E],
+        'Ю' => [0x7F, 0x08, 0x3E, 0x41, 0x3E],
+        'Я' => [0x46, 0x29, 0x19, 0x09, 0x7F],
+        // Icon glyphs share the text cell, so they flow inline. Senders
+        // can use the Unicode character directly or a :name: shortcode.
+        '♥' => [0x06, 0x0F, 0x1E, 0x0F, 0x06],
+        '☀' => [0x49, 0x2A, 0x1C, 0x2A, 0x49],
+        '☁' => [0x30, 0x2C, 0x26, 0x2C, 0x30],
+        '☂' => [0x06, 0x56, 0x26, 0x56, 0x06],
+        '❄' => [0x2A, 0x1C, 0x7F, 0x1C, 0x2A],
+        '⚠' => [0x60, 0x58, 0x6F, 0x58, 0x60],
+        '★' => [0x24, 0x1C, 0x0F, 0x1C, 0x24],
+        '←' => [0x08, 0x1C, 0x2A, 0x08, 0x08],
+        '→' => [0x08, 0x08, 0x2A, 0x1C, 0x08],
+        '↑' => [0x04, 0x02, 0x7F, 0x02, 0x04],
+        '↓' => [0x10, 0x20, 0x7F, 0x20, 0x10],
+        '✓' => [0x10, 0x20, 0x10, 0x08, 0x04],
+        '✗' => [0x63, 0x14, 0x08, 0x14, 0x63],
+        '☺' => [0x08, 0x12, 0x10, 0x12, 0x08],
+        '🔔' => [0x20, 0x3E, 0x7F, 0x3E, 0x20],
+        _ => [0x7F, 0x41, 0x41, 0x41, 0x7F],
+    }
+}
+
+/// The character behind an icon shortcode name, e.g. "heart" -> '♥'.
+pub fn icon_char(name: &str) -> Option<char> {
+    match name {
+        "heart" => Some('♥'),
+        "sun" => Some('☀'),
+        "cloud" => Some('☁'),
+        "rain" => Some('☂'),
+        "snow" => Some('❄'),
+        "alert" => Some('⚠'),
+        "star" => Some('★'),
+        "arrow-left" => Some('←'),
+        "arrow-right" => Some('→'),
+        "arrow-up" => Some('↑'),
+        "arrow-down" => Some('↓'),
+        "check" => Some('✓'),
+        "cross" => Some('✗'),
+        "smile" => Some('☺'),
+        "bell" => Some('🔔'),
+        _ => None,
+    }
+}
+
+/// Replace `:name:` shortcodes with their icon characters, leaving
+/// unknown names (and stray colons) untouched.
+pub fn expand_shortcodes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find(':') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find(':') {
+            Some(close) if icon_char(&after[..close]).is_some() => {
+                out.push(icon_char(&after[..close]).unwrap());
+                rest = &after[close + 1..];
+            }
+            _ => {
+                out.push(':');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Rasterize one icon centered on the grid as a compositor layer (black
+/// is transparent), for standalone notification icons.
+pub fn render_icon(name: &str, color: Pixel, width: usize, height: usize) -> Option<Vec<Pixel>> {
+    let c = icon_char(name)?;
+    let mut layer = vec![Pixel::BLACK; width * height];
+    let columns = glyph(c);
+    let x0 = width.saturating_sub(GLYPH_WIDTH) / 2;
+    let y0 = height.saturating_sub(GLYPH_HEIGHT) / 2;
+    for (col, &bits) in columns.iter().enumerate() {
+        for row in 0..GLYPH_HEIGHT {
+            if bits >> row & 1 == 1 {
+                let (x, y) = (x0 + col, y0 + row);
+                if x < width && y < height {
+                    layer[y * width + x] = color;
+                }
+            }
+        }
+    }
+    Some(layer)
+}
+
+/// Which way the marquee travels. Horizontal is the classic right-to-left
+/// crawl; vertical stacks the characters and scrolls bottom-to-top for
+/// tall panel orientations; diagonal combines both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
+
+impl ScrollDirection {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "horizontal" => Some(ScrollDirection::Horizontal),
+            "vertical" => Some(ScrollDirection::Vertical),
+            "diagonal" => Some(ScrollDirection::Diagonal),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a `"start-end:RRGGBB;start-end:RRGGBB"` span spec mapping
+/// character index ranges (inclusive) to colors.
+pub fn parse_color_spans(spec: &str) -> Result<Vec<(usize, usize, Pixel)>, String> {
+    let mut spans = Vec::new();
+    for part in spec.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (range, hex) = part
+            .split_once(':')
+            .ok_or_else(|| format!("span '{}': expected start-end:RRGGBB", part))?;
+        let (start, end) = range
+            .split_once('-')
+            .ok_or_else(|| format!("span '{}': bad range '{}'", part, range))?;
+        let start: usize = start.trim().parse().map_err(|_| format!("span '{}': bad range", part))?;
+        let end: usize = end.trim().parse().map_err(|_| format!("span '{}': bad range", part))?;
+        if end < start {
+            return Err(format!("span '{}': range ends before it starts", part));
+        }
+        let color = crate::config::parse_hex_color(hex.trim())
+            .ok_or_else(|| format!("span '{}': bad color '{}'", part, hex))?;
+        spans.push((start, end, color));
+    }
+    Ok(spans)
+}
+
+/// A running marquee: the rasterized message and its scroll clock.
+pub struct Marquee {
+    text: Vec<char>,
+    color: Pixel,
+    /// Scroll speed in pixels per second.
+    speed: f64,
+    pub direction: ScrollDirection,
+    /// Seconds to hold the message when it reaches the panel center, once
+    /// per cycle. Zero scrolls straight through.
+    pub pause: f64,
+    /// Per-character color overrides as (start, end, color) index spans;
+    /// characters outside every span use the base color.
+    spans: Vec<(usize, usize, Pixel)>,
+    started: Instant,
+}
+
+impl Marquee {
+    pub fn new(text: &str, color: Pixel, speed: f64) -> Self {
+        Self {
+            text: expand_shortcodes(text).chars().collect(),
+            color,
+            speed: speed.max(1.0),
+            direction: ScrollDirection::Horizontal,
+            pause: 0.0,
+            spans: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn set_spans(&mut self, spans: Vec<(usize, usize, Pixel)>) {
+        self.spans = spans;
+    }
+
+    fn color_for(&self, index: usize) -> Pixel {
+        self.spans
+            .iter()
+            .find(|&&(start, end, _)| (start..=end).contains(&index))
+            .map(|&(_, _, color)| color)
+            .unwrap_or(self.color)
+    }
+
+    /// Total width of the rendered message in pixels.
+    pub fn text_width(&self) -> usize {
+        self.text.len() * GLYPH_ADVANCE
+    }
+
+    /// Total height of the message when stacked vertically.
+    pub fn text_height(&self) -> usize {
+        self.text.len() * (GLYPH_HEIGHT + 1)
+    }
+
+    /// Render the marquee layer for the current clock position. Text
+    /// pixels carry the marquee color; the rest stay black (transparent
+    /// to the compositor).
+    pub fn render(&self, width: usize, height: usize) -> Vec<Pixel> {
+        let offset = (self.started.elapsed().as_secs_f64() * self.speed) as usize;
+        self.render_at(offset, width, height)
+    }
+
+    /// The pure half of render(): `offset` is how far the text has
+    /// scrolled. The message enters from the trailing edge (right, bottom,
+    /// or bottom-right corner depending on direction), optionally dwells
+    /// at the center, and wraps after fully leaving the other side.
+    pub fn render_at(&self, offset: usize, width: usize, height: usize) -> Vec<Pixel> {
+        let mut layer = vec![Pixel::BLACK; width * height];
+        if self.text.is_empty() || width == 0 || height == 0 {
+            return layer;
+        }
+        let travel = match self.direction {
+            ScrollDirection::Horizontal => self.text_width() + width,
+            ScrollDirection::Vertical => self.text_height() + height,
+            ScrollDirection::Diagonal => self.text_width() + width + 2 * height,
+        };
+        // The pause is a dwell inserted at the center of the travel; in
+        // offset units it lasts pause seconds worth of scroll speed.
+        let pause_px = (self.pause * self.speed) as usize;
+        let center = travel / 2;
+        let p = offset % (travel + pause_px);
+        let scroll = if p < center {
+            p
+        } else if p < center + pause_px {
+            center
+        } else {
+            p - pause_px
+        } as i64;
+
+        let mut plot = |x: i64, y: i64, color: Pixel| {
+            if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                layer[y as usize * width + x as usize] = color;
+            }
+        };
+        let x0 = width.saturating_sub(GLYPH_WIDTH) / 2;
+        let y0 = height.saturating_sub(GLYPH_HEIGHT) / 2;
+        for (i, &c) in self.text.iter().enumerate() {
+            let color = self.color_for(i);
+            let columns = glyph(c);
+            for (col, &bits) in columns.iter().enumerate() {
+                for row in 0..GLYPH_HEIGHT {
+                    if bits >> row & 1 != 1 {
+                        continue;
+                    }
+                    match self.direction {
+                        ScrollDirection::Horizontal => plot(
+                            width as i64 - scroll + (i * GLYPH_ADVANCE + col) as i64,
+                            (y0 + row) as i64,
+                            color,
+                        ),
+                        ScrollDirection::Vertical => plot(
+                            (x0 + col) as i64,
+                            height as i64 - scroll + (i * (GLYPH_HEIGHT + 1) + row) as i64,
+                            color,
+                        ),
+                        // One row up for every two columns of horizontal
+                        // travel: a readable shallow diagonal.
+                        ScrollDirection::Diagonal => plot(
+                            width as i64 - scroll + (i * GLYPH_ADVANCE + col) as i64,
+                            height as i64 - scroll / 2 + row as i64,
+                            color,
+                        ),
+                    }
+                }
+            }
+        }
+        layer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Pixel = Pixel { r: 255, g: 0, b: 0 };
+
+    #[test]
+    fn glyphs_fold_case_and_box_unknowns() {
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(glyph('A')[0], 0x7E);
+        assert_eq!(glyph('€'), [0x7F, 0x41, 0x41, 0x41, 0x7F]);
+    }
+
+    #[test]
+    fn diacritics_fold_to_their_base_letter() {
+        assert_eq!(glyph('é'), glyph('E'));
+        assert_eq!(glyph('Ñ'), glyph('N'));
+        assert_eq!(glyph('ü'), glyph('U'));
+    }
+
+    #[test]
+    fn cyrillic_renders_including_lowercase() {
+        // Lookalikes share the Latin columns; Cyrillic-only letters have
+        // their own, and lowercase folds through Unicode uppercasing.
+        assert_eq!(glyph('С'), glyph('C'));
+        assert_eq!(glyph('д'), glyph('Д'));
+        assert_ne!(glyph('Ж'), [0x7F, 0x41, 0x41, 0x41, 0x7F]);
+        assert_eq!(glyph('ё'), glyph('Е'));
+    }
+
+    #[test]
+    fn shortcodes_expand_to_icon_glyphs() {
+        assert_eq!(expand_shortcodes("I :heart: LEDs"), "I ♥ LEDs");
+        // Unknown names and clock colons pass through untouched.
+        assert_eq!(expand_shortcodes("12:30 :nope:"), "12:30 :nope:");
+        assert_ne!(glyph('♥'), [0x7F, 0x41, 0x41, 0x41, 0x7F]);
+    }
+
+    #[test]
+    fn icons_render_centered_as_a_layer() {
+        let layer = render_icon("alert", RED, 11, 9).unwrap();
+        assert_eq!(layer.len(), 99);
+        assert!(layer.contains(&RED));
+        // Centered: the left and right edge columns stay dark.
+        assert!((0..9).all(|y| layer[y * 11] == Pixel::BLACK));
+        assert!(render_icon("frobnicate", RED, 11, 9).is_none());
+    }
+
+    #[test]
+    fn text_enters_from_the_right_edge() {
+        let marquee = Marquee::new("I", RED, 10.0);
+        // Offset 0: still entirely off-screen.
+        let layer = marquee.render_at(0, 10, 7);
+        assert!(layer.iter().all(|&p| p == Pixel::BLACK));
+        // Three pixels in, the 'I' serif column is on screen.
+        let layer = marquee.render_at(3, 10, 7);
+        assert!(layer.contains(&RED));
+    }
+
+    #[test]
+    fn scroll_wraps_after_a_full_cycle() {
+        let marquee = Marquee::new("I", RED, 10.0);
+        let cycle = marquee.text_width() + 10;
+        let a = marquee.render_at(3, 10, 7);
+        let b = marquee.render_at(3 + cycle, 10, 7);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn vertical_text_enters_from_the_bottom() {
+        let mut marquee = Marquee::new("I", RED, 10.0);
+        marquee.direction = ScrollDirection::Vertical;
+        let layer = marquee.render_at(0, 5, 10);
+        assert!(layer.iter().all(|&p| p == Pixel::BLACK));
+        // A few pixels in, glyph rows appear near the bottom edge.
+        let layer = marquee.render_at(3, 5, 10);
+        assert!(layer[8 * 5..].contains(&RED));
+    }
+
+    #[test]
+    fn pause_dwells_at_the_center_of_the_travel() {
+        let mut marquee = Marquee::new("AB", RED, 10.0);
+        marquee.pause = 2.0; // 20 offset units at 10 px/s
+        let travel = marquee.text_width() + 10;
+        let center = travel / 2;
+        let held = marquee.render_at(center, 10, 7);
+        // Everywhere inside the dwell window the layer is identical.
+        assert_eq!(marquee.render_at(center + 10, 10, 7), held);
+        assert_eq!(marquee.render_at(center + 19, 10, 7), held);
+        // Past the dwell, scrolling resumes.
+        assert_ne!(marquee.render_at(center + 21, 10, 7), held);
+    }
+
+    #[test]
+    fn color_spans_override_per_character() {
+        const BLUE: Pixel = Pixel { r: 0, g: 0, b: 255 };
+        let mut marquee = Marquee::new("AB", RED, 10.0);
+        marquee.set_spans(parse_color_spans("1-1:0000FF").unwrap());
+        // Scroll far enough that both glyphs are on a wide layer.
+        let layer = marquee.render_at(12, 20, 7);
+        assert!(layer.contains(&RED));
+        assert!(layer.contains(&BLUE));
+
+        assert!(parse_color_spans("1-0:FF0000").is_err());
+        assert!(parse_color_spans("0-1:XYZ").is_err());
+    }
+
+    #[test]
+    fn short_grids_clip_instead_of_panicking() {
+        let marquee = Marquee::new("AB", RED, 10.0);
+        let layer = marquee.render_at(5, 4, 3);
+        assert_eq!(layer.len(), 12);
+    }
+}
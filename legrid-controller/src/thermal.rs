@@ -0,0 +1,160 @@
+//! Thermal throttling.
+//!
+//! `--thermal-limit warn:max` (degrees C) scales brightness down linearly
+//! between the two thresholds, bottoming out at a floor instead of going
+//! dark. Temperature is the hotter of the SoC thermal zone and an
+//! optional DS18B20 on the 1-wire bus near the PSU. The scale slews
+//! rather than steps so recovery doesn't flicker the panel.
+
+use std::time::{Duration, Instant};
+
+use crate::metrics::read_cpu_temperature;
+
+/// The throttle never dims below this, so the panel stays legible while
+/// the enclosure cools off.
+const MIN_SCALE: f64 = 0.3;
+/// How often to poll the sensors; sysfs reads are cheap but not free.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+/// Maximum scale movement per sample, to smooth steps on the panel.
+const SLEW_PER_SAMPLE: f64 = 0.05;
+
+/// Parse a `warn:max` threshold pair in degrees C.
+pub fn parse_thermal(spec: &str) -> Result<(f64, f64), String> {
+    let bad = || format!("thermal limit '{}': expected warn:max in degrees C", spec);
+    let (warn, max) = spec.split_once(':').ok_or_else(bad)?;
+    let warn: f64 = warn.trim().parse().map_err(|_| bad())?;
+    let max: f64 = max.trim().parse().map_err(|_| bad())?;
+    if warn >= max {
+        return Err(format!(
+            "thermal limit '{}': warn threshold must be below max",
+            spec
+        ));
+    }
+    Ok((warn, max))
+}
+
+/// DS18B20 on the 1-wire bus: first `28-*` device, `t=` in millidegrees.
+pub fn read_ds18b20() -> Option<f64> {
+    let entries = std::fs::read_dir("/sys/bus/w1/devices").ok()?;
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with("28-") {
+            continue;
+        }
+        let raw = std::fs::read_to_string(entry.path().join("w1_slave")).ok()?;
+        let milli: f64 = raw.rsplit_once("t=")?.1.trim().parse().ok()?;
+        return Some(milli / 1000.0);
+    }
+    None
+}
+
+pub struct ThermalThrottle {
+    warn_c: f64,
+    max_c: f64,
+    /// Current brightness scale in [MIN_SCALE, 1].
+    pub scale: f64,
+    pub last_temp: Option<f64>,
+    last_sample: Instant,
+}
+
+impl ThermalThrottle {
+    pub fn new(warn_c: f64, max_c: f64) -> Self {
+        Self {
+            warn_c,
+            max_c,
+            scale: 1.0,
+            last_temp: None,
+            last_sample: Instant::now()
+                .checked_sub(SAMPLE_INTERVAL)
+                .unwrap_or_else(Instant::now),
+        }
+    }
+
+    pub fn throttled(&self) -> bool {
+        self.scale < 1.0
+    }
+
+    /// Poll the sensors if the sample interval has passed and move the
+    /// scale one slew step toward the target. Called from the output path.
+    pub fn update(&mut self) {
+        if self.last_sample.elapsed() < SAMPLE_INTERVAL {
+            return;
+        }
+        self.last_sample = Instant::now();
+        let temp = match (read_cpu_temperature(), read_ds18b20()) {
+            (Some(soc), Some(strip)) => soc.max(strip),
+            (Some(t), None) | (None, Some(t)) => t,
+            (None, None) => return,
+        };
+        self.apply_temperature(temp);
+    }
+
+    /// The pure half of update(), separated so it can be exercised without
+    /// sysfs.
+    pub fn apply_temperature(&mut self, temp: f64) {
+        let was_throttled = self.throttled();
+        self.last_temp = Some(temp);
+        let target = if temp <= self.warn_c {
+            1.0
+        } else if temp >= self.max_c {
+            MIN_SCALE
+        } else {
+            let t = (temp - self.warn_c) / (self.max_c - self.warn_c);
+            1.0 - t * (1.0 - MIN_SCALE)
+        };
+        let step = (target - self.scale).clamp(-SLEW_PER_SAMPLE, SLEW_PER_SAMPLE);
+        self.scale = (self.scale + step).clamp(MIN_SCALE, 1.0);
+        if self.throttled() != was_throttled {
+            if self.throttled() {
+                crate::log_warn!("thermal", "Thermal throttle engaged at {:.1}C", temp);
+            } else {
+                crate::log_warn!("thermal", "Thermal throttle released at {:.1}C", temp);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_threshold_pairs() {
+        assert_eq!(parse_thermal("70:80").unwrap(), (70.0, 80.0));
+        assert!(parse_thermal("80:70").is_err());
+        assert!(parse_thermal("70").is_err());
+    }
+
+    #[test]
+    fn scale_ramps_down_and_recovers() {
+        let mut throttle = ThermalThrottle::new(70.0, 80.0);
+        throttle.apply_temperature(60.0);
+        assert_eq!(throttle.scale, 1.0);
+        assert!(!throttle.throttled());
+
+        // Hold at max temperature: slews down to the floor, one step at a
+        // time.
+        throttle.apply_temperature(85.0);
+        assert!((throttle.scale - 0.95).abs() < 1e-9);
+        assert!(throttle.throttled());
+        for _ in 0..20 {
+            throttle.apply_temperature(85.0);
+        }
+        assert!((throttle.scale - 0.3).abs() < 1e-9);
+
+        // Recovery slews back up.
+        for _ in 0..20 {
+            throttle.apply_temperature(50.0);
+        }
+        assert_eq!(throttle.scale, 1.0);
+        assert!(!throttle.throttled());
+    }
+
+    #[test]
+    fn midpoint_targets_the_middle_of_the_ramp() {
+        let mut throttle = ThermalThrottle::new(70.0, 80.0);
+        for _ in 0..20 {
+            throttle.apply_temperature(75.0);
+        }
+        assert!((throttle.scale - 0.65).abs() < 1e-9);
+    }
+}
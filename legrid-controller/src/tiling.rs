@@ -0,0 +1,245 @@
+//! Panel tiling for multi-panel walls.
+//!
+//! The host renders one large logical frame; the wall is built from
+//! smaller physical panels, each mounted at some offset and rotation and
+//! wired serpentine internally. A tile map describes the panels
+//! (`--tiles "0,0:25x24:normal:18;25,0:25x24:rot180:13"`, panels in chain
+//! order) and precomputes, for every LED of the physical chain, which
+//! logical pixel it shows.
+
+use crate::driver::ChannelSpec;
+use crate::frame::Pixel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelOrientation {
+    Normal,
+    Rot90,
+    Rot180,
+    Rot270,
+}
+
+impl PanelOrientation {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "normal" => Some(PanelOrientation::Normal),
+            "rot90" => Some(PanelOrientation::Rot90),
+            "rot180" => Some(PanelOrientation::Rot180),
+            "rot270" => Some(PanelOrientation::Rot270),
+            _ => None,
+        }
+    }
+}
+
+/// One physical panel: where its top-left corner sits in the logical
+/// frame, its own dimensions, how it is rotated, and the GPIO pin that
+/// drives its chain segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanelSpec {
+    pub x_off: usize,
+    pub y_off: usize,
+    pub width: usize,
+    pub height: usize,
+    pub orientation: PanelOrientation,
+    pub pin: u8,
+}
+
+impl PanelSpec {
+    /// The logical footprint: rotation by 90/270 swaps the panel's extent.
+    fn footprint(&self) -> (usize, usize) {
+        match self.orientation {
+            PanelOrientation::Normal | PanelOrientation::Rot180 => (self.width, self.height),
+            PanelOrientation::Rot90 | PanelOrientation::Rot270 => (self.height, self.width),
+        }
+    }
+
+    /// Map a panel-local pixel to logical frame coordinates.
+    fn to_logical(&self, px: usize, py: usize) -> (usize, usize) {
+        let (w, h) = (self.width, self.height);
+        match self.orientation {
+            PanelOrientation::Normal => (self.x_off + px, self.y_off + py),
+            PanelOrientation::Rot180 => (self.x_off + (w - 1 - px), self.y_off + (h - 1 - py)),
+            PanelOrientation::Rot90 => (self.x_off + (h - 1 - py), self.y_off + px),
+            PanelOrientation::Rot270 => (self.x_off + py, self.y_off + (w - 1 - px)),
+        }
+    }
+}
+
+/// Parse a tile spec: panels separated by ';', each
+/// `xoff,yoff:WxH:orientation:pin`. Panels are listed in chain order.
+pub fn parse_tiles(spec: &str) -> Result<Vec<PanelSpec>, String> {
+    let mut panels = Vec::new();
+    for part in spec.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let bad = || format!("tile '{}': expected xoff,yoff:WxH:orientation:pin", part);
+        let mut fields = part.split(':');
+        let offset = fields.next().ok_or_else(bad)?;
+        let size = fields.next().ok_or_else(bad)?;
+        let orientation = fields.next().ok_or_else(bad)?;
+        let pin = fields.next().ok_or_else(bad)?;
+        if fields.next().is_some() {
+            return Err(bad());
+        }
+
+        let (x_off, y_off) = offset.split_once(',').ok_or_else(bad)?;
+        let (width, height) = size.split_once('x').ok_or_else(bad)?;
+        panels.push(PanelSpec {
+            x_off: x_off.trim().parse().map_err(|_| bad())?,
+            y_off: y_off.trim().parse().map_err(|_| bad())?,
+            width: width.trim().parse().map_err(|_| bad())?,
+            height: height.trim().parse().map_err(|_| bad())?,
+            orientation: PanelOrientation::parse(orientation.trim())
+                .ok_or_else(|| format!("tile '{}': expected normal|rot90|rot180|rot270", part))?,
+            pin: pin.trim().parse().map_err(|_| bad())?,
+        });
+    }
+    if panels.is_empty() {
+        return Err("tile spec is empty".to_string());
+    }
+    Ok(panels)
+}
+
+/// Precomputed routing from the logical frame to the physical chain.
+pub struct TileMap {
+    panels: Vec<PanelSpec>,
+    /// For each chain position, the logical pixel index it displays.
+    map: Vec<usize>,
+}
+
+impl TileMap {
+    /// Validate the panels against the logical grid and build the chain
+    /// map. Panels must fit inside the grid and must not overlap; within a
+    /// panel the chain runs serpentine (even rows left-to-right).
+    pub fn build(panels: Vec<PanelSpec>, grid_w: usize, grid_h: usize) -> Result<Self, String> {
+        let mut claimed = vec![false; grid_w * grid_h];
+        let mut map = Vec::new();
+
+        for panel in &panels {
+            let (fw, fh) = panel.footprint();
+            if panel.x_off + fw > grid_w || panel.y_off + fh > grid_h {
+                return Err(format!(
+                    "panel at {},{} extends past the {}x{} grid",
+                    panel.x_off, panel.y_off, grid_w, grid_h
+                ));
+            }
+            for py in 0..panel.height {
+                // Serpentine: odd panel rows run right-to-left.
+                let xs: Vec<usize> = if py % 2 == 0 {
+                    (0..panel.width).collect()
+                } else {
+                    (0..panel.width).rev().collect()
+                };
+                for px in xs {
+                    let (lx, ly) = panel.to_logical(px, py);
+                    let idx = ly * grid_w + lx;
+                    if claimed[idx] {
+                        return Err(format!(
+                            "panel at {},{} overlaps another panel at logical pixel {},{}",
+                            panel.x_off, panel.y_off, lx, ly
+                        ));
+                    }
+                    claimed[idx] = true;
+                    map.push(idx);
+                }
+            }
+        }
+        Ok(Self { panels, map })
+    }
+
+    /// Total chain length across all panels.
+    pub fn led_count(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Reorder a logical frame into physical chain order.
+    pub fn route(&self, logical: &[Pixel]) -> Vec<Pixel> {
+        self.map
+            .iter()
+            .map(|&idx| logical.get(idx).copied().unwrap_or(Pixel::BLACK))
+            .collect()
+    }
+
+    /// Derive the per-pin output channels from the panel list, for
+    /// multi-channel output without a separate --channels spec.
+    pub fn channels(&self) -> Vec<ChannelSpec> {
+        let mut channels = Vec::new();
+        let mut start = 0usize;
+        for panel in &self.panels {
+            let len = panel.width * panel.height;
+            channels.push(ChannelSpec {
+                pin: panel.pin,
+                start,
+                end: start + len - 1,
+            });
+            start += len;
+        }
+        channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_two_panel_spec() {
+        let panels = parse_tiles("0,0:25x24:normal:18;25,0:25x24:rot180:13").unwrap();
+        assert_eq!(panels.len(), 2);
+        assert_eq!(panels[1].x_off, 25);
+        assert_eq!(panels[1].orientation, PanelOrientation::Rot180);
+        assert_eq!(panels[1].pin, 13);
+    }
+
+    #[test]
+    fn rejects_malformed_panels() {
+        assert!(parse_tiles("0,0:25x24:sideways:18").is_err());
+        assert!(parse_tiles("0:25x24:normal:18").is_err());
+        assert!(parse_tiles("").is_err());
+    }
+
+    #[test]
+    fn serpentine_routing_within_a_panel() {
+        let panels = parse_tiles("0,0:2x2:normal:18").unwrap();
+        let tile_map = TileMap::build(panels, 2, 2).unwrap();
+        let logical = vec![
+            Pixel { r: 0, g: 0, b: 0 },
+            Pixel { r: 1, g: 0, b: 0 },
+            Pixel { r: 2, g: 0, b: 0 },
+            Pixel { r: 3, g: 0, b: 0 },
+        ];
+        let chain = tile_map.route(&logical);
+        // Row 0 left-to-right, row 1 right-to-left.
+        let order: Vec<u8> = chain.iter().map(|p| p.r).collect();
+        assert_eq!(order, vec![0, 1, 3, 2]);
+    }
+
+    #[test]
+    fn side_by_side_panels_cover_the_grid() {
+        let panels = parse_tiles("0,0:2x2:normal:18;2,0:2x2:normal:13").unwrap();
+        let tile_map = TileMap::build(panels, 4, 2).unwrap();
+        assert_eq!(tile_map.led_count(), 8);
+        let channels = tile_map.channels();
+        assert_eq!(channels.len(), 2);
+        assert_eq!((channels[1].start, channels[1].end), (4, 7));
+        assert_eq!(channels[1].pin, 13);
+    }
+
+    #[test]
+    fn rot180_panel_reads_backwards() {
+        let panels = parse_tiles("0,0:2x1:rot180:18").unwrap();
+        let tile_map = TileMap::build(panels, 2, 1).unwrap();
+        let logical = vec![Pixel { r: 1, g: 0, b: 0 }, Pixel { r: 2, g: 0, b: 0 }];
+        let order: Vec<u8> = tile_map.route(&logical).iter().map(|p| p.r).collect();
+        assert_eq!(order, vec![2, 1]);
+    }
+
+    #[test]
+    fn overlap_and_bounds_are_rejected() {
+        let panels = parse_tiles("0,0:2x2:normal:18;1,0:2x2:normal:13").unwrap();
+        assert!(TileMap::build(panels, 4, 2).err().unwrap().contains("overlap"));
+        let panels = parse_tiles("0,0:3x2:normal:18").unwrap();
+        assert!(TileMap::build(panels, 2, 2).err().unwrap().contains("extends"));
+    }
+}
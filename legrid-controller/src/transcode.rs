@@ -0,0 +1,184 @@
+//! Offline content conversion: the `transcode` subcommand.
+//!
+//! `local_controller transcode <input> <output>` reads one format the
+//! controller understands and writes another, so content can be prepared
+//! without a live panel:
+//!
+//!   inputs:  .legrid recordings, GIF/PPM/BMP content files
+//!   outputs: .legrid recordings, v2 stdin streams (.v2 or `-` for
+//!            stdout), DDP packet captures (.ddp)
+//!
+//! Grid size for image inputs comes from --width/--height as usual.
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::content::load_content;
+use crate::frame::{Pixel, MSG_TYPE_FRAME};
+use crate::record::{read_recording, FrameRecorder};
+
+/// Encode a version-2 frame payload (with host timestamp) from raw pixels.
+pub fn encode_frame_v2(frame_id: u32, width: u16, height: u16, pixels: &[Pixel], ts_us: u64) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(18 + pixels.len() * 3);
+    payload.push(2);
+    payload.push(MSG_TYPE_FRAME);
+    payload.extend_from_slice(&frame_id.to_le_bytes());
+    payload.extend_from_slice(&width.to_le_bytes());
+    payload.extend_from_slice(&height.to_le_bytes());
+    payload.extend_from_slice(&ts_us.to_le_bytes());
+    for p in pixels {
+        payload.extend_from_slice(&[p.r, p.g, p.b]);
+    }
+    payload
+}
+
+/// One DDP data packet: 10-byte header (flags, sequence, pixel config,
+/// destination id, big-endian offset and length) followed by RGB data.
+pub fn encode_ddp_packet(sequence: u8, offset: u32, rgb: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(10 + rgb.len());
+    packet.push(0x41); // version 1, push flag
+    packet.push(sequence & 0x0F);
+    packet.push(0x01); // RGB, 8 bits per channel
+    packet.push(0x01); // default output device
+    packet.extend_from_slice(&offset.to_be_bytes());
+    packet.extend_from_slice(&(rgb.len() as u16).to_be_bytes());
+    packet.extend_from_slice(rgb);
+    packet
+}
+
+/// Read the input into timestamped frame payloads.
+fn read_input(path: &Path, width: u16, height: u16) -> io::Result<Vec<(Duration, Vec<u8>)>> {
+    if path.extension().is_some_and(|e| e == "legrid") {
+        return read_recording(path);
+    }
+    let frames = load_content(path, width as usize, height as usize)?;
+    let mut records = Vec::with_capacity(frames.len());
+    let mut offset = Duration::ZERO;
+    for (i, frame) in frames.iter().enumerate() {
+        let payload = encode_frame_v2(i as u32, width, height, &frame.pixels, offset.as_micros() as u64);
+        records.push((offset, payload));
+        offset += frame.delay.max(Duration::from_millis(20));
+    }
+    Ok(records)
+}
+
+/// Write records in the format the output path's extension asks for.
+fn write_output(path_arg: &str, records: &[(Duration, Vec<u8>)]) -> io::Result<()> {
+    if path_arg == "-" {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        for (_, payload) in records {
+            out.write_all(&(payload.len() as u32).to_le_bytes())?;
+            out.write_all(payload)?;
+        }
+        return out.flush();
+    }
+    let path = Path::new(path_arg);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("legrid") => {
+            // FrameRecorder stamps its own clock; offsets degrade to
+            // write-time spacing, which playback tooling tolerates.
+            let mut recorder = FrameRecorder::create(path)?;
+            for (_, payload) in records {
+                recorder.record(payload)?;
+            }
+            crate::log_info!("transcode", "Wrote {} frames to {}", recorder.frames_written, path.display());
+            Ok(())
+        }
+        Some("ddp") => {
+            let mut out = io::BufWriter::new(std::fs::File::create(path)?);
+            for (i, (_, payload)) in records.iter().enumerate() {
+                let Some((w, h, rgb)) = crate::frame::frame_payload_rgb(payload) else {
+                    continue;
+                };
+                let _ = (w, h);
+                out.write_all(&encode_ddp_packet(i as u8, 0, &rgb))?;
+            }
+            out.flush()
+        }
+        Some("v2") | Some("bin") => {
+            let mut out = io::BufWriter::new(std::fs::File::create(path)?);
+            for (_, payload) in records {
+                out.write_all(&(payload.len() as u32).to_le_bytes())?;
+                out.write_all(payload)?;
+            }
+            out.flush()
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unknown output format: {} (expected .legrid, .ddp, .v2 or -)", path_arg),
+        )),
+    }
+}
+
+/// Entry point for the subcommand; `args` is everything after
+/// `transcode`, with the usual flags still allowed for grid size.
+pub fn run_transcode(args: &[String]) -> io::Result<()> {
+    let config = crate::config::parse_args(args)?;
+    // Positional arguments, with flag values skipped alongside their flag.
+    let mut positional: Vec<&String> = Vec::new();
+    let mut iter = args.iter().skip(1).peekable();
+    while let Some(arg) = iter.next() {
+        if arg.starts_with("--") {
+            if iter.peek().is_some_and(|next| !next.starts_with("--")) {
+                iter.next();
+            }
+        } else {
+            positional.push(arg);
+        }
+    }
+    let [input, output] = positional[..] else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Usage: transcode <input> <output> [--width W --height H]",
+        ));
+    };
+    let records = read_input(Path::new(input), config.width, config.height)?;
+    crate::log_info!("transcode", "Read {} frames from {}", records.len(), input);
+    write_output(output, &records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v2_payloads_parse_back() {
+        let pixels = vec![Pixel { r: 9, g: 8, b: 7 }];
+        let payload = encode_frame_v2(3, 1, 1, &pixels, 555);
+        let frame = crate::frame::FrameParser::parse(&payload).unwrap();
+        assert_eq!(frame.frame_id, 3);
+        assert_eq!(frame.host_timestamp_us, Some(555));
+        assert_eq!(frame.pixels, pixels);
+    }
+
+    #[test]
+    fn ddp_packets_carry_the_header_and_data() {
+        let packet = encode_ddp_packet(2, 30, &[1, 2, 3]);
+        assert_eq!(packet[0], 0x41);
+        assert_eq!(packet[1], 2);
+        assert_eq!(&packet[4..8], &30u32.to_be_bytes());
+        assert_eq!(&packet[8..10], &3u16.to_be_bytes());
+        assert_eq!(&packet[10..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn recording_transcodes_to_a_v2_stream_file() {
+        let dir = std::env::temp_dir();
+        let input = dir.join("legrid-transcode-in.legrid");
+        let output = dir.join("legrid-transcode-out.v2");
+        {
+            let mut rec = FrameRecorder::create(&input).unwrap();
+            rec.record(&encode_frame_v2(0, 1, 1, &[Pixel::BLACK], 0)).unwrap();
+        }
+        let records = read_input(&input, 1, 1).unwrap();
+        write_output(output.to_str().unwrap(), &records).unwrap();
+        let written = std::fs::read(&output).unwrap();
+        std::fs::remove_file(&input).ok();
+        std::fs::remove_file(&output).ok();
+        // Length prefix plus the 21-byte payload.
+        assert_eq!(written.len(), 4 + 21);
+        assert_eq!(u32::from_le_bytes(written[..4].try_into().unwrap()), 21);
+    }
+}
@@ -0,0 +1,170 @@
+//! Transitions between frame sources.
+//!
+//! A `{"command":"transition","kind":"crossfade","duration_ms":500}`
+//! control message captures the buffer currently on the panel and blends
+//! incoming frames against it for the requested duration, so a pattern
+//! switch on the host fades instead of hard-cutting. Crossfades honor the
+//! configured blend space.
+
+use std::time::{Duration, Instant};
+
+use crate::frame::Pixel;
+use crate::pipeline::{lerp_u8, linear_to_srgb, srgb_to_linear, BlendSpace};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    Crossfade,
+    Wipe,
+    FadeThroughBlack,
+}
+
+impl TransitionKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "crossfade" => Some(TransitionKind::Crossfade),
+            "wipe" => Some(TransitionKind::Wipe),
+            "fade-through-black" => Some(TransitionKind::FadeThroughBlack),
+            _ => None,
+        }
+    }
+}
+
+fn blend_pixel(space: BlendSpace, a: Pixel, b: Pixel, t: f64) -> Pixel {
+    let channel = |a: u8, b: u8| match space {
+        BlendSpace::Srgb => lerp_u8(a, b, t),
+        BlendSpace::Linear => {
+            linear_to_srgb(srgb_to_linear(a) + (srgb_to_linear(b) - srgb_to_linear(a)) * t)
+        }
+    };
+    Pixel {
+        r: channel(a.r, b.r),
+        g: channel(a.g, b.g),
+        b: channel(a.b, b.b),
+    }
+}
+
+/// One in-flight transition: the buffer we are leaving and the clock.
+pub struct Transition {
+    kind: TransitionKind,
+    from: Vec<Pixel>,
+    started: Instant,
+    duration: Duration,
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, from: Vec<Pixel>, duration: Duration) -> Self {
+        Self {
+            kind,
+            from,
+            started: Instant::now(),
+            duration: duration.max(Duration::from_millis(1)),
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.started.elapsed() >= self.duration
+    }
+
+    /// Mix the incoming frame against the captured one at the current
+    /// clock position.
+    pub fn apply(&self, current: &[Pixel], width: usize, space: BlendSpace) -> Vec<Pixel> {
+        let t = (self.started.elapsed().as_secs_f64() / self.duration.as_secs_f64()).clamp(0.0, 1.0);
+        self.apply_at(t, current, width, space)
+    }
+
+    /// The pure half of apply(), keyed on explicit progress for tests.
+    pub fn apply_at(&self, t: f64, current: &[Pixel], width: usize, space: BlendSpace) -> Vec<Pixel> {
+        let from = |i: usize| self.from.get(i).copied().unwrap_or(Pixel::BLACK);
+        match self.kind {
+            TransitionKind::Crossfade => current
+                .iter()
+                .enumerate()
+                .map(|(i, &px)| blend_pixel(space, from(i), px, t))
+                .collect(),
+            TransitionKind::Wipe => {
+                // Left-to-right column reveal.
+                let width = width.max(1);
+                let edge = (t * width as f64) as usize;
+                current
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &px)| if i % width < edge { px } else { from(i) })
+                    .collect()
+            }
+            TransitionKind::FadeThroughBlack => {
+                // First half dims the old buffer to black, second half
+                // brings the new one up.
+                if t < 0.5 {
+                    let fade = 1.0 - t * 2.0;
+                    (0..current.len())
+                        .map(|i| blend_pixel(space, Pixel::BLACK, from(i), fade))
+                        .collect()
+                } else {
+                    let rise = (t - 0.5) * 2.0;
+                    current
+                        .iter()
+                        .map(|&px| blend_pixel(space, Pixel::BLACK, px, rise))
+                        .collect()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Pixel = Pixel { r: 255, g: 255, b: 255 };
+
+    #[test]
+    fn parses_the_three_kinds() {
+        assert_eq!(TransitionKind::parse("crossfade"), Some(TransitionKind::Crossfade));
+        assert_eq!(TransitionKind::parse("wipe"), Some(TransitionKind::Wipe));
+        assert_eq!(
+            TransitionKind::parse("fade-through-black"),
+            Some(TransitionKind::FadeThroughBlack)
+        );
+        assert_eq!(TransitionKind::parse("iris"), None);
+    }
+
+    #[test]
+    fn crossfade_midpoint_mixes_both_buffers() {
+        let transition = Transition::new(
+            TransitionKind::Crossfade,
+            vec![Pixel::BLACK; 4],
+            Duration::from_millis(500),
+        );
+        let out = transition.apply_at(0.5, &[WHITE; 4], 2, BlendSpace::Srgb);
+        assert_eq!(out[0].r, 128);
+    }
+
+    #[test]
+    fn wipe_reveals_columns_left_to_right() {
+        let transition = Transition::new(
+            TransitionKind::Wipe,
+            vec![Pixel::BLACK; 4],
+            Duration::from_millis(500),
+        );
+        // 2x2 grid at half progress: left column new, right column old.
+        let out = transition.apply_at(0.5, &[WHITE; 4], 2, BlendSpace::Srgb);
+        assert_eq!(out[0], WHITE);
+        assert_eq!(out[1], Pixel::BLACK);
+        assert_eq!(out[2], WHITE);
+    }
+
+    #[test]
+    fn fade_through_black_is_dark_at_the_midpoint() {
+        let transition = Transition::new(
+            TransitionKind::FadeThroughBlack,
+            vec![WHITE; 1],
+            Duration::from_millis(500),
+        );
+        let quarter = transition.apply_at(0.25, &[WHITE; 1], 1, BlendSpace::Srgb);
+        assert_eq!(quarter[0].r, 128); // old buffer halfway down
+        let mid = transition.apply_at(0.5, &[WHITE; 1], 1, BlendSpace::Srgb);
+        assert_eq!(mid[0], Pixel::BLACK);
+        let late = transition.apply_at(0.75, &[WHITE; 1], 1, BlendSpace::Srgb);
+        assert_eq!(late[0].r, 128); // new buffer halfway up
+    }
+}
@@ -0,0 +1,77 @@
+//! The stdio transport: length-prefixed messages in both directions.
+
+use std::io::{self, Read, Write};
+use std::sync::mpsc;
+use std::thread;
+
+/// Send a length-prefixed JSON message to the host on stdout.
+pub fn send_message(body: &str) -> io::Result<()> {
+    let bytes = body.as_bytes();
+    let length = bytes.len() as u32;
+
+    // Length prefix is 4 bytes, little-endian.
+    io::stdout().write_all(&length.to_le_bytes())?;
+    io::stdout().write_all(bytes)?;
+    io::stdout().flush()?;
+
+    Ok(())
+}
+
+/// Standard base64 (with padding), for binary payloads embedded in the
+/// JSON control channel.
+pub fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        out.push(TABLE[(n >> 18) as usize & 63] as char);
+        out.push(TABLE[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { TABLE[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { TABLE[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+/// Reads length-prefixed frames from stdin on a dedicated thread so the
+/// output loop can run on its own clock when interpolation is enabled.
+/// Other message sources (the MQTT bridge) clone the returned sender and
+/// inject messages into the same channel.
+pub fn spawn_stdin_reader() -> (mpsc::Sender<Vec<u8>>, mpsc::Receiver<Vec<u8>>) {
+    let (tx, rx) = mpsc::channel::<Vec<u8>>();
+    let stdin_tx = tx.clone();
+    thread::spawn(move || {
+        let tx = stdin_tx;
+        loop {
+            let mut length_bytes = [0u8; 4];
+            if io::stdin().read_exact(&mut length_bytes).is_err() {
+                break; // EOF or error
+            }
+
+            let frame_length = u32::from_le_bytes(length_bytes) as usize;
+
+            let mut frame_data = vec![0u8; frame_length];
+            if io::stdin().read_exact(&mut frame_data).is_err() {
+                break; // EOF or error
+            }
+
+            if tx.send(frame_data).is_err() {
+                break; // Output side went away
+            }
+        }
+    });
+    (tx, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_known_vectors() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+}
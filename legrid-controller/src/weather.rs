@@ -0,0 +1,140 @@
+//! Precipitation nowcast widget.
+//!
+//! Fetches Open-Meteo's 15-minute precipitation forecast for the
+//! configured site and renders approaching rain as a row of bars along
+//! the bottom of the panel — now at the left, the next few hours
+//! marching right, taller and bluer meaning heavier. Clear skies render
+//! nothing at all.
+
+use std::io;
+use std::time::Duration;
+
+use crate::frame::Pixel;
+use crate::widget::Widget;
+
+/// Forecast steps shown; 16 quarter-hours is four hours of warning.
+const STEPS: usize = 16;
+
+/// mm per 15 minutes that counts as a full-height bar. ~2 mm in a
+/// quarter hour is already a proper downpour.
+const HEAVY_MM: f64 = 2.0;
+
+/// Pull every number out of the named JSON array field.
+pub fn json_num_array(body: &str, key: &str) -> Option<Vec<f64>> {
+    let needle = format!("\"{}\"", key);
+    let rest = &body[body.find(&needle)? + needle.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let rest = rest.strip_prefix('[')?;
+    let inner = &rest[..rest.find(']')?];
+    inner
+        .split(',')
+        .map(|v| {
+            let v = v.trim();
+            if v == "null" {
+                Some(0.0)
+            } else {
+                v.parse().ok()
+            }
+        })
+        .collect()
+}
+
+/// Bars along the bottom edge: one column group per forecast step,
+/// height and hue by intensity (0..1). Black stays transparent.
+pub fn render_bars(intensities: &[f64], width: usize, height: usize) -> Option<Vec<Pixel>> {
+    if intensities.iter().all(|&i| i <= 0.0) {
+        return None;
+    }
+    let mut layer = vec![Pixel::BLACK; width * height];
+    for x in 0..width {
+        let step = x * intensities.len() / width.max(1);
+        let intensity = intensities[step].clamp(0.0, 1.0);
+        let bar = (intensity * height as f64).ceil() as usize;
+        for dy in 0..bar.min(height) {
+            let y = height - 1 - dy;
+            // Drizzle is a soft cyan, heavy rain a saturated blue.
+            layer[y * width + x] = Pixel {
+                r: 0,
+                g: (120.0 * (1.0 - intensity)) as u8 + 20,
+                b: 120 + (135.0 * intensity) as u8,
+            };
+        }
+    }
+    Some(layer)
+}
+
+/// The nowcast widget itself; see the module docs.
+pub struct WeatherWidget {
+    latitude: f64,
+    longitude: f64,
+    /// Normalized intensities for the next [`STEPS`] quarter-hours.
+    intensities: Vec<f64>,
+}
+
+impl WeatherWidget {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self { latitude, longitude, intensities: Vec::new() }
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "https://api.open-meteo.com/v1/forecast?latitude={:.4}&longitude={:.4}&minutely_15=precipitation&forecast_minutely_15={}",
+            self.latitude, self.longitude, STEPS
+        )
+    }
+}
+
+impl Widget for WeatherWidget {
+    fn name(&self) -> &'static str {
+        "weather"
+    }
+
+    fn refresh_interval(&self) -> Duration {
+        Duration::from_secs(10 * 60)
+    }
+
+    fn refresh(&mut self) -> io::Result<()> {
+        let body = crate::widget::fetch_url(&self.url())?;
+        let mm = json_num_array(&body, "precipitation").ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "No precipitation array in response")
+        })?;
+        self.intensities = mm.iter().take(STEPS).map(|v| (v / HEAVY_MM).min(1.0)).collect();
+        Ok(())
+    }
+
+    fn render(&self, width: usize, height: usize) -> Option<Vec<Pixel>> {
+        render_bars(&self.intensities, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_the_precipitation_array() {
+        let body = r#"{"minutely_15":{"time":["x"],"precipitation":[0.0, 0.4,null,2.5]}}"#;
+        assert_eq!(json_num_array(body, "precipitation"), Some(vec![0.0, 0.4, 0.0, 2.5]));
+        assert_eq!(json_num_array(body, "missing"), None);
+    }
+
+    #[test]
+    fn dry_forecasts_render_nothing() {
+        assert!(render_bars(&[0.0; STEPS], 8, 8).is_none());
+    }
+
+    #[test]
+    fn bars_grow_with_intensity_from_the_bottom() {
+        let mut intensities = vec![0.0; 4];
+        intensities[0] = 0.25;
+        intensities[3] = 1.0;
+        let layer = render_bars(&intensities, 4, 8).unwrap();
+        let column_height = |x: usize| (0..8).filter(|y| layer[y * 4 + x] != Pixel::BLACK).count();
+        assert_eq!(column_height(0), 2);
+        assert_eq!(column_height(1), 0);
+        assert_eq!(column_height(3), 8);
+        // Bars hang from the bottom edge, not the top.
+        assert_eq!(layer[0], Pixel::BLACK);
+        assert_ne!(layer[7 * 4], Pixel::BLACK);
+    }
+}
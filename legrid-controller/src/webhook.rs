@@ -0,0 +1,172 @@
+//! Outbound webhooks for threshold events.
+//!
+//! `--webhooks "https://hooks.example/abc https://other/xyz|thermal_throttle"`
+//! posts a small JSON payload to each URL when something an operator
+//! would want to know about happens: sustained frame loss, the thermal
+//! throttle engaging, the output driver being reset. Small deployments
+//! without a monitoring stack point this at a Slack/Discord webhook and
+//! get alerted anyway. Posts go through `curl` like the widget fetches —
+//! TLS without linking a TLS stack — on a throwaway thread so a slow
+//! endpoint never stalls the frame path. A per-event cooldown keeps a
+//! flapping condition from flooding the channel.
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Minimum spacing between two posts for the same event.
+const EVENT_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// One configured endpoint, optionally filtered to specific events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Webhook {
+    pub url: String,
+    /// `None` means every event.
+    pub events: Option<Vec<String>>,
+}
+
+/// Parse whitespace-separated entries, each `URL` or `URL|event,event`.
+pub fn parse_webhooks(spec: &str) -> Result<Vec<Webhook>, String> {
+    let mut hooks = Vec::new();
+    for entry in spec.split_whitespace() {
+        let (url, events) = match entry.split_once('|') {
+            Some((url, list)) => {
+                let events: Vec<String> = list
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                (url, Some(events))
+            }
+            None => (entry, None),
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!("webhook '{}': expected an http(s) URL", url));
+        }
+        hooks.push(Webhook { url: url.to_string(), events });
+    }
+    if hooks.is_empty() {
+        return Err("webhooks: no URLs listed".to_string());
+    }
+    Ok(hooks)
+}
+
+/// The posted payload. Pure so tests can look at it.
+pub fn payload_json(event: &str, detail: &str, uptime_secs: u64) -> String {
+    format!(
+        "{{\"event\":\"{}\",\"detail\":\"{}\",\"uptime_secs\":{}}}",
+        event,
+        detail.replace('\\', "\\\\").replace('"', "\\\""),
+        uptime_secs
+    )
+}
+
+/// Fires configured webhooks, remembering when each event last fired.
+pub struct WebhookNotifier {
+    hooks: Vec<Webhook>,
+    last_fired: Vec<(String, Instant)>,
+    started: Instant,
+}
+
+impl WebhookNotifier {
+    pub fn new(hooks: Vec<Webhook>) -> Self {
+        Self {
+            hooks,
+            last_fired: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Whether `event` is outside its cooldown window at `now`; records
+    /// the firing when it is.
+    fn due(&mut self, event: &str, now: Instant) -> bool {
+        match self.last_fired.iter_mut().find(|(name, _)| name == event) {
+            Some((_, last)) => {
+                if now.duration_since(*last) < EVENT_COOLDOWN {
+                    return false;
+                }
+                *last = now;
+                true
+            }
+            None => {
+                self.last_fired.push((event.to_string(), now));
+                true
+            }
+        }
+    }
+
+    /// Post `event` to every matching endpoint, subject to the cooldown.
+    pub fn fire(&mut self, event: &str, detail: &str) {
+        if !self.due(event, Instant::now()) {
+            return;
+        }
+        let payload = payload_json(event, detail, self.started.elapsed().as_secs());
+        for hook in &self.hooks {
+            if hook
+                .events
+                .as_ref()
+                .is_some_and(|list| !list.iter().any(|e| e == event))
+            {
+                continue;
+            }
+            let url = hook.url.clone();
+            let payload = payload.clone();
+            let event = event.to_string();
+            std::thread::spawn(move || {
+                let status = Command::new("curl")
+                    .args(["-sf", "--max-time", "10", "-o", "/dev/null"])
+                    .args(["-H", "Content-Type: application/json"])
+                    .args(["-d", &payload])
+                    .arg(&url)
+                    .status();
+                match status {
+                    Ok(code) if code.success() => {}
+                    Ok(code) => crate::log_warn!("webhook",
+                        "Webhook {} for '{}' failed: {}", url, event, code),
+                    Err(e) => crate::log_warn!("webhook",
+                        "Webhook {} for '{}' could not run curl: {}", url, event, e),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_urls_and_event_filters() {
+        let hooks =
+            parse_webhooks("https://hooks.example/abc http://other/xyz|thermal_throttle,frame_loss")
+                .unwrap();
+        assert_eq!(hooks.len(), 2);
+        assert_eq!(hooks[0].events, None);
+        assert_eq!(
+            hooks[1].events.as_deref(),
+            Some(&["thermal_throttle".to_string(), "frame_loss".to_string()][..])
+        );
+        assert!(parse_webhooks("ftp://nope").is_err());
+        assert!(parse_webhooks("   ").is_err());
+    }
+
+    #[test]
+    fn payload_escapes_the_detail() {
+        let json = payload_json("frame_loss", "host \"gone\"", 42);
+        assert_eq!(
+            json,
+            "{\"event\":\"frame_loss\",\"detail\":\"host \\\"gone\\\"\",\"uptime_secs\":42}"
+        );
+    }
+
+    #[test]
+    fn cooldown_gates_repeat_events() {
+        let mut notifier = WebhookNotifier::new(Vec::new());
+        let t0 = Instant::now();
+        assert!(notifier.due("thermal_throttle", t0));
+        assert!(!notifier.due("thermal_throttle", t0 + Duration::from_secs(10)));
+        // A different event has its own window.
+        assert!(notifier.due("frame_loss", t0 + Duration::from_secs(10)));
+        assert!(notifier.due("thermal_throttle", t0 + EVENT_COOLDOWN));
+    }
+}
@@ -0,0 +1,66 @@
+//! HTTP widgets: small self-refreshing layers fed by external data.
+//!
+//! A widget fetches something over HTTP on its own cadence, renders it
+//! into a frame-sized layer (black transparent, like the marquee), and
+//! publishes the layer through a shared slot; the output path composites
+//! whatever is current. Fetching shells out to `curl` — TLS without
+//! linking a TLS stack, in the same spirit as the arecord and v4l2-ctl
+//! shell-outs.
+//!
+//! `--widgets "weather"` names the enabled widgets; each runs on its own
+//! thread so a slow upstream never stalls the frame path.
+
+use std::io;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::frame::Pixel;
+
+/// One widget: a data source plus its rendering.
+pub trait Widget: Send {
+    fn name(&self) -> &'static str;
+    /// How often to hit the upstream. Rendering reuses the last good
+    /// data between refreshes.
+    fn refresh_interval(&self) -> Duration;
+    /// Fetch and parse fresh data. An error keeps the previous layer.
+    fn refresh(&mut self) -> io::Result<()>;
+    /// Render the current data into a width x height layer; black is
+    /// transparent. `None` hides the widget entirely (nothing to show).
+    fn render(&self, width: usize, height: usize) -> Option<Vec<Pixel>>;
+}
+
+/// The layer slot a widget thread publishes into.
+pub type SharedLayer = Arc<Mutex<Option<Vec<Pixel>>>>;
+
+/// Fetch a URL body with curl. `-f` turns HTTP errors into exit codes;
+/// the timeout keeps a dead upstream from pinning the widget thread.
+pub fn fetch_url(url: &str) -> io::Result<String> {
+    let output = Command::new("curl")
+        .args(["-sf", "--max-time", "10", url])
+        .output()
+        .map_err(|e| {
+            io::Error::new(e.kind(), format!("Cannot run curl (is it installed?): {}", e))
+        })?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("curl failed for {}", url)));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Response is not UTF-8"))
+}
+
+/// Start a widget's refresh thread and hand back the layer it fills.
+/// The first fetch happens on the thread too, so startup never blocks
+/// on the network.
+pub fn spawn_widget(mut widget: Box<dyn Widget>, width: usize, height: usize) -> SharedLayer {
+    let layer: SharedLayer = Arc::new(Mutex::new(None));
+    let slot = layer.clone();
+    std::thread::spawn(move || loop {
+        match widget.refresh() {
+            Ok(()) => *slot.lock().unwrap() = widget.render(width, height),
+            Err(e) => crate::log_warn!("widget", "{} refresh failed: {}", widget.name(), e),
+        }
+        std::thread::sleep(widget.refresh_interval());
+    });
+    layer
+}